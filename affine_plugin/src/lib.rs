@@ -0,0 +1,339 @@
+#![warn(missing_docs)]
+//! Модуль для реализации обработки изображения - аффинное преобразование
+//!
+//! Предоставляет функциональность поворота, сдвига, масштабирования и переноса изображения
+//! в пределах исходного холста, заданных матрицей 2x3
+
+use log::LevelFilter;
+use plugins_support::config_parse::warn_ignored_fields;
+use plugins_support::geometry::Geometry;
+use plugins_support::logger::{
+    LogTimezone, get_log_level, set_call_level, set_log_timezone, setup_logger_once,
+};
+use plugins_support::pixels::pixels_mut;
+use plugins_support::sampler::{Interpolation, sample};
+use plugins_support::{config_parse::ConfigReader, error::Error};
+use serde::Deserialize;
+use std::ffi::{CStr, c_char, c_uint};
+use std::slice;
+
+const PKG_NAME: &str = env!("CARGO_PKG_NAME");
+const BYTE_PER_PIXEL: usize = 4;
+
+plugins_support::export_warning_count!();
+plugins_support::export_abi_probe!();
+
+/// Поля конфигурации, которые использует плагин
+const KNOWN_FIELDS: &[&str] = &["matrix", "fill", "interpolation"];
+
+/// Определитель, ниже которого (по модулю) матрица считается вырожденной
+const MIN_DETERMINANT: f64 = 1e-12;
+
+#[derive(Deserialize, Debug)]
+struct ConfigTransform {
+    matrix: [f64; 6],
+    fill: Option<[u8; 4]>,
+    interpolation: Option<Interpolation>,
+    log_level: Option<String>,
+    log_tz: Option<LogTimezone>,
+}
+
+///
+/// Аффинная матрица 2x3 `[a, b, c, d, e, f]`
+///
+/// Точка исходного изображения `(x, y)` переходит в точку `(a * x + b * y + c, d * x + e * y + f)`,
+/// центр пикселя `(i, j)` находится в точке `(i, j)`
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Affine([f64; 6]);
+
+impl Affine {
+    /// Тождественное преобразование
+    pub const IDENTITY: Affine = Affine([1.0, 0.0, 0.0, 0.0, 1.0, 0.0]);
+
+    ///
+    /// Матрица из 6 коэффициентов
+    ///
+    /// # Возращает
+    /// `ErrorValue`, если коэффициент не конечен или матрица вырождена (определитель 0)
+    ///
+    pub fn new(matrix: [f64; 6]) -> Result<Self, Error> {
+        if matrix.iter().any(|value| !value.is_finite()) {
+            return Err(Error::ErrorValue("Matrix values must be finite".to_string()));
+        }
+        let affine = Affine(matrix);
+        if affine.determinant().abs() < MIN_DETERMINANT {
+            return Err(Error::ErrorValue("Matrix is not invertible".to_string()));
+        }
+        Ok(affine)
+    }
+
+    /// Определитель линейной части
+    pub fn determinant(&self) -> f64 {
+        let [a, b, _, d, e, _] = self.0;
+        a * e - b * d
+    }
+
+    /// Обратное преобразование (матрица невырождена по построению)
+    pub fn inverse(&self) -> Affine {
+        let [a, b, c, d, e, f] = self.0;
+        let det = self.determinant();
+        let (ia, ib, id, ie) = (e / det, -b / det, -d / det, a / det);
+        Affine([ia, ib, -(ia * c + ib * f), id, ie, -(id * c + ie * f)])
+    }
+
+    /// Образ точки `(x, y)`
+    pub fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        let [a, b, c, d, e, f] = self.0;
+        (a * x + b * y + c, d * x + e * y + f)
+    }
+}
+
+/// Аффинное преобразование RGBA буфера в пределах исходного холста
+///
+/// Для каждого пикселя результата берется точка исходного изображения по обратной матрице,
+/// точки за пределами исходного изображения заполняются цветом `fill`
+///
+/// # Аргументы
+/// * `buf`    – изменяемый буфер RGBA (длина = width * height * 4)
+/// * `width`  – ширина в пикселях
+/// * `height` – высота в пикселях
+/// * `affine` – преобразование исходного изображения
+/// * `fill`   – цвет RGBA за пределами исходного изображения
+/// * `interpolation` – способ выборки точек между пикселями
+///
+pub fn affine_transform(
+    buf: &mut [u8],
+    width: usize,
+    height: usize,
+    affine: &Affine,
+    fill: [u8; 4],
+    interpolation: Interpolation,
+) -> Result<(), Error> {
+    let inverse = affine.inverse();
+    let source = buf.to_vec();
+    let (max_x, max_y) = ((width - 1) as f64, (height - 1) as f64);
+    for (x, y, pixel) in pixels_mut(buf, width, height, BYTE_PER_PIXEL)? {
+        let (source_x, source_y) = inverse.apply(x as f64, y as f64);
+        // Допуск на погрешность вычислений у самой границы
+        let outside = source_x < -0.5
+            || source_y < -0.5
+            || source_x > max_x + 0.5
+            || source_y > max_y + 0.5;
+        if outside {
+            pixel.copy_from_slice(&fill);
+        } else {
+            pixel.copy_from_slice(&sample(&source, width, height, source_x, source_y, interpolation));
+        }
+    }
+    Ok(())
+}
+
+/// ```rust
+///
+///  Трансформация RGBA буффера аффинным преобразованием
+///
+///
+///  # Параметры
+///   - `width` (`c_uint`):  ширина изображения в пикселях
+///   - `height` (`c_uint`): высота изображения в пикселях
+///   - `rgba_data` (`*mut u8`): Мутабельный сырой указатель на RGBA буфер. Размер буфера `width * height * 4` bytes.
+///   - `params` (`*const c_char`): Сырой указатель на нуль терменированноу строку  конфигурации JSON-формата.
+///
+///  # Параметры конфигурации (JSON формат)
+///   - `log_level` (optional, string): уровень логирования ("Debug", "Info").
+///   - `log_tz` (optional, string): часовой пояс меток времени лога ("local", "utc"), по умолчанию "local"
+///   - `matrix` (required, array of 6 floats): матрица `[a, b, c, d, e, f]`, точка `(x, y)` переходит
+///     в `(a * x + b * y + c, d * x + e * y + f)`; матрица должна быть обратимой
+///   - `fill` (optional, array of 4 integers): цвет RGBA за пределами исходного изображения,
+///     по умолчанию `[0, 0, 0, 0]`
+///   - `interpolation` (optional, string): выборка точек ("nearest", "bilinear"), по умолчанию "bilinear"
+///
+///  # Пример JSON:
+///  ```json
+///  {
+///     "log_level": "Debug",
+///     "matrix": [0.866, -0.5, 40.0, 0.5, 0.866, -20.0],
+///     "fill": [255, 255, 255, 255]
+///  }
+///  ```
+///
+///
+///   # Error Handling
+///   - Ошибки фиксируются в логе
+///   - Поля конфигурации, которые плагин не использует, фиксируются в логе предупреждением
+///
+///
+///   # Пример С
+///  ```c
+///  // In C code
+///   unsigned int width = 1920;
+///   unsigned int height = 1080;
+///   unsigned char *image_data = ...; // RGBA buffer, allocated elsewhere
+///   const char *config = "{\"matrix\": [1, 0, 10, 0, 1, 0]}";
+///   process_image(width, height, image_data, config);
+///   ```
+/// # Safety
+///  Данная функция  помечена `unsafe`:
+///   - Работа напрямую с сырыми указателями (`rgba_data`, `params`) предстаялет external C code
+///   - Использования недопустимого указателя или парметров width, height undefined behavior.
+/// ```
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn process_image(
+    width: c_uint,
+    height: c_uint,
+    rgba_data: *mut u8,
+    params: *const c_char,
+) {
+    let file = PKG_NAME.to_owned() + ".log";
+    if let Err(e) = setup_logger_once(LevelFilter::Debug, &file) {
+        match e {
+            Error::LoggerInitError(_) => {
+                log::warn!("Logger init error: {}", e);
+            }
+            _ => {
+                log::error!("Logger init error: {}", e);
+                return;
+            }
+        }
+    }
+    log::info!("Start plugin {}", &file);
+    if params.is_null() {
+        log::error!("Pointer params is_null");
+        return;
+    }
+    let config = unsafe { CStr::from_ptr(params) };
+    let params_config = match config.to_str() {
+        Ok(config) => {
+            warn_ignored_fields(config, KNOWN_FIELDS);
+            let config: Result<ConfigReader<ConfigTransform>, Error> =
+                ConfigReader::try_from(config);
+            if let Ok(config) = config {
+                config
+            } else {
+                log::error!("Error converting config to string");
+                return;
+            }
+        }
+        _ => {
+            log::error!("Invalid config file");
+            return;
+        }
+    };
+    // Уровень из конфигурации действует только в потоке вызова до его завершения
+    let _log_level = params_config
+        .config
+        .log_level
+        .as_deref()
+        .map(|log_level| set_call_level(get_log_level(log_level)));
+    if let Some(log_tz) = params_config.config.log_tz {
+        set_log_timezone(log_tz);
+    }
+    if rgba_data.is_null() {
+        log::error!("Null pointer rgba_data");
+        return;
+    }
+    let geometry = match Geometry::rgba(width, height) {
+        Ok(geometry) => geometry,
+        Err(e) => {
+            log::error!("Invalid image size: {}", e);
+            return;
+        }
+    };
+    let config = &params_config.config;
+    let affine = match Affine::new(config.matrix) {
+        Ok(affine) => affine,
+        Err(e) => {
+            log::error!("Invalid matrix: {}", e);
+            return;
+        }
+    };
+    log::info!("Start converting image");
+    let buf = unsafe { slice::from_raw_parts_mut(rgba_data, geometry.total_len()) };
+    if let Err(e) = affine_transform(
+        buf,
+        geometry.width(),
+        geometry.height(),
+        &affine,
+        config.fill.unwrap_or_default(),
+        config.interpolation.unwrap_or_default(),
+    ) {
+        log::error!("Affine transform error: {}", e);
+        return;
+    }
+    log::info!("Finish converting image");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    const SIZE: usize = 4;
+
+    /// Квадратное изображение с уникальным цветом каждого пикселя
+    fn image() -> Vec<u8> {
+        (0..SIZE * SIZE)
+            .flat_map(|index| [index as u8 * 10, 200 - index as u8 * 10, 7, 255])
+            .collect()
+    }
+
+    fn transform(buf: &mut [u8], json: &str) {
+        let params = CString::new(json).unwrap();
+        let size = SIZE as c_uint;
+        unsafe { process_image(size, size, buf.as_mut_ptr(), params.as_ptr()) };
+    }
+
+    #[test]
+    fn test_affine_identity() {
+        let mut buf = image();
+        transform(&mut buf, r#"{"matrix": [1, 0, 0, 0, 1, 0]}"#);
+        assert_eq!(buf, image());
+    }
+
+    #[test]
+    fn test_affine_rotate_90() {
+        // Поворот на 90 градусов по часовой стрелке: (x, y) -> (SIZE - 1 - y, x)
+        let last = (SIZE - 1) as f64;
+        for interpolation in ["nearest", "bilinear"] {
+            let mut buf = image();
+            let json = format!(
+                r#"{{"matrix": [0, -1, {}, 1, 0, 0], "interpolation": "{}"}}"#,
+                last, interpolation
+            );
+            transform(&mut buf, &json);
+            let source = image();
+            for y in 0..SIZE {
+                for x in 0..SIZE {
+                    let (dest_x, dest_y) = (SIZE - 1 - y, x);
+                    let dest = (dest_y * SIZE + dest_x) * BYTE_PER_PIXEL;
+                    let src = (y * SIZE + x) * BYTE_PER_PIXEL;
+                    assert_eq!(buf[dest..dest + 4], source[src..src + 4], "{}", interpolation);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_affine_translate_fill() {
+        let mut buf = image();
+        transform(&mut buf, r#"{"matrix": [1, 0, 2, 0, 1, 0], "fill": [1, 2, 3, 4]}"#);
+        assert_eq!(buf[..4], [1, 2, 3, 4]);
+        let source = image();
+        assert_eq!(buf[2 * BYTE_PER_PIXEL..3 * BYTE_PER_PIXEL], source[..4]);
+    }
+
+    #[test]
+    fn test_affine_singular_matrix() {
+        assert!(Affine::new([1.0, 2.0, 0.0, 2.0, 4.0, 0.0]).is_err());
+        assert!(Affine::new([f64::NAN, 0.0, 0.0, 0.0, 1.0, 0.0]).is_err());
+        let mut buf = image();
+        transform(&mut buf, r#"{"matrix": [0, 0, 0, 0, 0, 0]}"#);
+        assert_eq!(buf, image());
+        let affine = Affine::new([2.0, 1.0, 3.0, 0.5, 1.0, -1.0]).unwrap();
+        let (x, y) = affine.apply(1.5, -2.0);
+        let (x, y) = affine.inverse().apply(x, y);
+        assert!((x - 1.5).abs() < 1e-9 && (y + 2.0).abs() < 1e-9);
+        assert_eq!(Affine::IDENTITY.inverse(), Affine::IDENTITY);
+    }
+}
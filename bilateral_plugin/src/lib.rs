@@ -0,0 +1,345 @@
+#![warn(missing_docs)]
+//! Модуль для реализации обработки изображения - билатеральный фильтр
+//!
+//! Предоставляет функциональность сглаживания шума с сохранением границ: вклад соседнего
+//! пикселя зависит и от расстояния, и от близости цвета к центральному пикселю
+
+use log::LevelFilter;
+use plugins_support::config_parse::warn_ignored_fields;
+use plugins_support::image_size::rgba_len;
+use plugins_support::logger::{
+    LogTimezone, get_log_level, set_call_level, set_log_timezone, setup_logger_once,
+};
+use plugins_support::tile::{Tile, run_tiled};
+use plugins_support::{config_parse::ConfigReader, error::Error};
+use serde::Deserialize;
+use std::ffi::{CStr, c_char, c_uint};
+use std::slice;
+
+const PKG_NAME: &str = env!("CARGO_PKG_NAME");
+const BYTE_PER_PIXEL: usize = 4;
+
+plugins_support::export_warning_count!();
+plugins_support::export_abi_probe!();
+
+/// Поля конфигурации, которые использует плагин
+const KNOWN_FIELDS: &[&str] = &["spatial_sigma", "range_sigma", "radius", "threads"];
+
+#[derive(Deserialize, Debug)]
+struct ConfigTransform {
+    spatial_sigma: f64,
+    range_sigma: f64,
+    radius: usize,
+    /// Количество потоков, `0` - по числу доступных ядер
+    #[serde(default)]
+    threads: usize,
+    log_level: Option<String>,
+    log_tz: Option<LogTimezone>,
+}
+
+/// Билатеральный фильтр каналов RGB
+///
+/// # Аргументы
+/// * `buf`    – изменяемый буфер RGBA (длина = width * height * 4)
+/// * `width`  – ширина в пикселях
+/// * `height` – высота в пикселях
+/// * `radius` – радиус окна, окно `(2 * radius + 1)²` пикселей
+/// * `spatial_sigma` – сигма гауссианы по расстоянию в пикселях (> 0)
+/// * `range_sigma` – сигма гауссианы по евклидову расстоянию цветов RGB (> 0)
+/// * `threads` – количество потоков, `0` - по числу доступных ядер
+///
+/// Изображение обрабатывается полосами строк в нескольких потоках ([`run_tiled`] с ореолом
+/// `radius` строк), результат не зависит от количества потоков.
+/// Соседи читаются из копии исходного буфера, вес соседа - произведение пространственной
+/// гауссианы и гауссианы разности цветов, поэтому пиксели по другую сторону резкой границы
+/// почти не влияют на результат. Соседи за краем изображения не учитываются,
+/// альфа-канал не изменяется.
+///
+/// Стоимость `O(width * height * (2 * radius + 1)²)` с вычислением экспоненты для каждого
+/// соседа - значительно дороже размытия по прямоугольнику, радиус стоит выбирать
+/// порядка `2 * spatial_sigma`
+///
+pub fn bilateral(
+    buf: &mut [u8],
+    width: usize,
+    height: usize,
+    radius: usize,
+    spatial_sigma: f64,
+    range_sigma: f64,
+    threads: usize,
+) -> Result<(), Error> {
+    let positive = |sigma: f64| sigma.is_finite() && sigma > 0.0;
+    if !positive(spatial_sigma) || !positive(range_sigma) {
+        return Err(Error::ErrorValue("Sigma must be positive".to_string()));
+    }
+    let source = buf.to_vec();
+    let side = 2 * radius + 1;
+    let spatial: Vec<f64> = (0..side * side)
+        .map(|index| {
+            let dx = (index % side) as f64 - radius as f64;
+            let dy = (index / side) as f64 - radius as f64;
+            (-(dx * dx + dy * dy) / (2.0 * spatial_sigma * spatial_sigma)).exp()
+        })
+        .collect();
+    let range_scale = -1.0 / (2.0 * range_sigma * range_sigma);
+    run_tiled(&source, buf, width, height, BYTE_PER_PIXEL, radius, threads, |tile, band| {
+        bilateral_band(tile, band, height, radius, &spatial, range_scale)
+    })
+}
+
+/// Билатеральный фильтр строк полосы `tile` с записью в `band`
+fn bilateral_band(
+    tile: &Tile,
+    band: &mut [u8],
+    height: usize,
+    radius: usize,
+    spatial: &[f64],
+    range_scale: f64,
+) {
+    let (width, side) = (tile.width(), 2 * radius + 1);
+    for y in tile.rows() {
+        for x in 0..width {
+            let center = &tile.pixel(x, y)[..3];
+            let mut sum = [0.0f64; 3];
+            let mut total = 0.0;
+            for ny in y.saturating_sub(radius)..(y + radius + 1).min(height) {
+                for nx in x.saturating_sub(radius)..(x + radius + 1).min(width) {
+                    let neighbor = &tile.pixel(nx, ny)[..3];
+                    let distance: f64 = center
+                        .iter()
+                        .zip(neighbor)
+                        .map(|(&a, &b)| (a as f64 - b as f64).powi(2))
+                        .sum();
+                    let weight = spatial[(ny + radius - y) * side + nx + radius - x]
+                        * (distance * range_scale).exp();
+                    for (value, &channel) in sum.iter_mut().zip(neighbor) {
+                        *value += weight * channel as f64;
+                    }
+                    total += weight;
+                }
+            }
+            let index = tile.band_index(x, y);
+            for (channel, value) in band[index..index + 3].iter_mut().zip(sum) {
+                *channel = (value / total).round().clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+}
+
+/// ```rust
+///
+///  Трансформация RGBA буффера билатеральным фильтром
+///
+///
+///  # Параметры
+///   - `width` (`c_uint`):  ширина изображения в пикселях
+///   - `height` (`c_uint`): высота изображения в пикселях
+///   - `rgba_data` (`*mut u8`): Мутабельный сырой указатель на RGBA буфер. Размер буфера `width * height * 4` bytes.
+///   - `params` (`*const c_char`): Сырой указатель на нуль терменированноу строку  конфигурации JSON-формата.
+///
+///  # Параметры конфигурации (JSON формат)
+///   - `log_level` (optional, string): уровень логирования ("Debug", "Info").
+///   - `log_tz` (optional, string): часовой пояс меток времени лога ("local", "utc"), по умолчанию "local"
+///   - `spatial_sigma` (required, float): сигма по расстоянию в пикселях. Должна быть > 0
+///   - `range_sigma` (required, float): сигма по разности цветов (0..=441). Должна быть > 0
+///   - `radius` (required, integer): радиус окна в пикселях
+///   - `threads` (optional, integer): количество потоков, 0 - по числу доступных ядер (по умолчанию)
+///
+///  # Пример JSON:
+///  ```json
+///  {
+///     "log_level": "Debug",
+///     "spatial_sigma": 3.0,
+///     "range_sigma": 25.0,
+///     "radius": 6
+///  }
+///  ```
+///
+///
+///   # Error Handling
+///   - Ошибки фиксируются в логе
+///   - Поля конфигурации, которые плагин не использует, фиксируются в логе предупреждением
+///
+///
+///   # Пример С
+///  ```c
+///  // In C code
+///   unsigned int width = 1920;
+///   unsigned int height = 1080;
+///   unsigned char *image_data = ...; // RGBA buffer, allocated elsewhere
+///   const char *config = "{\"spatial_sigma\": 3.0, \"range_sigma\": 25.0, \"radius\": 6}";
+///   process_image(width, height, image_data, config);
+///   ```
+/// # Safety
+///  Данная функция  помечена `unsafe`:
+///   - Работа напрямую с сырыми указателями (`rgba_data`, `params`) предстаялет external C code
+///   - Использования недопустимого указателя или парметров width, height undefined behavior.
+/// ```
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn process_image(
+    width: c_uint,
+    height: c_uint,
+    rgba_data: *mut u8,
+    params: *const c_char,
+) {
+    let file = PKG_NAME.to_owned() + ".log";
+    if let Err(e) = setup_logger_once(LevelFilter::Debug, &file) {
+        match e {
+            Error::LoggerInitError(_) => {
+                log::warn!("Logger init error: {}", e);
+            }
+            _ => {
+                log::error!("Logger init error: {}", e);
+                return;
+            }
+        }
+    }
+    log::info!("Start plugin {}", &file);
+    if params.is_null() {
+        log::error!("Pointer params is_null");
+        return;
+    }
+    let config = unsafe { CStr::from_ptr(params) };
+    let params_config = match config.to_str() {
+        Ok(config) => {
+            warn_ignored_fields(config, KNOWN_FIELDS);
+            let config: Result<ConfigReader<ConfigTransform>, Error> =
+                ConfigReader::try_from(config);
+            if let Ok(config) = config {
+                config
+            } else {
+                log::error!("Error converting config to string");
+                return;
+            }
+        }
+        _ => {
+            log::error!("Invalid config file");
+            return;
+        }
+    };
+    // Уровень из конфигурации действует только в потоке вызова до его завершения
+    let _log_level = params_config
+        .config
+        .log_level
+        .as_deref()
+        .map(|log_level| set_call_level(get_log_level(log_level)));
+    if let Some(log_tz) = params_config.config.log_tz {
+        set_log_timezone(log_tz);
+    }
+    if rgba_data.is_null() {
+        log::error!("Null pointer rgba_data");
+        return;
+    }
+    if width == 0 {
+        log::error!("width cannot be 0");
+        return;
+    }
+    if height == 0 {
+        log::error!("height cannot be 0");
+        return;
+    }
+    let len_in_pixel = match rgba_len(width, height) {
+        Ok(len) => len,
+        Err(e) => {
+            log::error!("Length calculation failed: {}", e);
+            return;
+        }
+    };
+    let height: usize = match height.try_into() {
+        Ok(h) => h,
+        Err(_) => {
+            log::error!("Height conversion failed");
+            return;
+        }
+    };
+    let width: usize = match width.try_into() {
+        Ok(w) => w,
+        Err(_) => {
+            log::error!("Width conversion failed");
+            return;
+        }
+    };
+    log::info!("Start converting image");
+    let buf = unsafe { slice::from_raw_parts_mut(rgba_data, len_in_pixel) };
+    let config = &params_config.config;
+    if let Err(e) = bilateral(
+        buf,
+        width,
+        height,
+        config.radius,
+        config.spatial_sigma,
+        config.range_sigma,
+        config.threads,
+    ) {
+        log::error!("Bilateral filter error: {}", e);
+        return;
+    }
+    log::info!("Finish converting image");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    /// Серое изображение с вертикальной границей 40 | 200 и шумом ±6 в шахматном порядке
+    fn noisy_edge(width: usize, height: usize) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(width * height * BYTE_PER_PIXEL);
+        for y in 0..height {
+            for x in 0..width {
+                let base: i32 = if x < width / 2 { 40 } else { 200 };
+                let noise = if (x + y) % 2 == 0 { 6 } else { -6 };
+                let value = (base + noise) as u8;
+                buf.extend_from_slice(&[value, value, value, 255]);
+            }
+        }
+        buf
+    }
+
+    /// Минимум и максимум красного канала в столбцах `columns`
+    fn spread(buf: &[u8], width: usize, height: usize, columns: std::ops::Range<usize>) -> (u8, u8) {
+        let values = (0..height).flat_map(|y| columns.clone().map(move |x| buf[(y * width + x) * BYTE_PER_PIXEL]));
+        (values.clone().min().unwrap(), values.max().unwrap())
+    }
+
+    #[test]
+    fn test_bilateral_preserves_edge() {
+        let (width, height) = (8, 6);
+        let mut buf = noisy_edge(width, height);
+        let json = r#"{"spatial_sigma": 2.0, "range_sigma": 30.0, "radius": 2}"#;
+        let params_cstring = CString::new(json).unwrap();
+        unsafe { process_image(width as c_uint, height as c_uint, buf.as_mut_ptr(), params_cstring.as_ptr()) };
+        // Шум ±6 уменьшается, значения по обе стороны границы не смешиваются
+        let (low_min, low_max) = spread(&buf, width, height, 0..width / 2);
+        let (high_min, high_max) = spread(&buf, width, height, width / 2..width);
+        assert!(low_max - low_min <= 4, "left {}..={}", low_min, low_max);
+        assert!(high_max - high_min <= 4, "right {}..={}", high_min, high_max);
+        assert!((38..=42).contains(&low_min) && (38..=42).contains(&low_max));
+        assert!((198..=202).contains(&high_min) && (198..=202).contains(&high_max));
+        assert!(buf.chunks_exact(BYTE_PER_PIXEL).all(|pixel| pixel[3] == 255));
+    }
+
+    #[test]
+    fn test_bilateral_invalid_sigma() {
+        let mut buf = vec![0; 16];
+        assert!(bilateral(&mut buf, 2, 2, 1, 0.0, 10.0, 1).is_err());
+        assert!(bilateral(&mut buf, 2, 2, 1, 1.0, f64::NAN, 1).is_err());
+    }
+
+    #[test]
+    fn test_bilateral_tiled_matches_sequential() {
+        let (width, height) = (13, 11);
+        let mut source = noisy_edge(width, height);
+        for (index, value) in source.iter_mut().enumerate() {
+            *value = value.wrapping_add((index * 7 % 23) as u8);
+        }
+        let mut sequential = source.clone();
+        bilateral(&mut sequential, width, height, 3, 2.0, 30.0, 1).unwrap();
+        assert_ne!(sequential, source);
+        for threads in [2, 3, 4, height, 64] {
+            let mut tiled = source.clone();
+            bilateral(&mut tiled, width, height, 3, 2.0, 30.0, threads).unwrap();
+            assert_eq!(tiled, sequential, "threads {}", threads);
+        }
+    }
+}
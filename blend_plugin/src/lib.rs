@@ -0,0 +1,342 @@
+#![warn(missing_docs)]
+//! Модуль для реализации обработки изображения - наложение второго изображения
+//!
+//! Предоставляет функциональность смешивания изображения со вторым изображением
+//! в одном из режимов наложения (normal, multiply, screen, overlay, add, difference)
+
+use log::LevelFilter;
+use plugins_support::logger::{
+    LogTimezone, get_log_level, set_call_level, set_log_timezone, setup_logger_once,
+};
+use plugins_support::config_parse::warn_ignored_fields;
+use plugins_support::image_size::rgba_len;
+pub use plugins_support::blend_mode::{BlendMode, blend_channel};
+use plugins_support::{config_parse::ConfigReader, error::Error};
+use serde::Deserialize;
+use std::ffi::{CStr, c_char, c_uint};
+use std::slice;
+
+const PKG_NAME: &str = env!("CARGO_PKG_NAME");
+const BYTE_PER_PIXEL: usize = 4;
+
+plugins_support::export_warning_count!();
+plugins_support::export_abi_probe!();
+
+/// Поля конфигурации, которые использует плагин
+const KNOWN_FIELDS: &[&str] = &["path", "mode", "opacity", "fit"];
+
+/// Способ согласования размеров накладываемого изображения
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Fit {
+    /// Размеры должны совпадать
+    #[default]
+    Exact,
+    /// Изображение повторяется плиткой
+    Tile,
+    /// Изображение размещается по центру, остальная часть не изменяется
+    Center,
+}
+
+#[derive(Deserialize, Debug)]
+struct ConfigTransform {
+    path: String,
+    mode: Option<BlendMode>,
+    opacity: Option<f64>,
+    fit: Option<Fit>,
+    log_level: Option<String>,
+    log_tz: Option<LogTimezone>,
+}
+
+/// Наложение буфера `top` на буфер `base`
+///
+/// # Аргументы
+/// * `base`       – изменяемый буфер RGBA исходного изображения
+/// * `width`      – ширина исходного изображения
+/// * `height`     – высота исходного изображения
+/// * `top`        – буфер RGBA накладываемого изображения
+/// * `top_width`  – ширина накладываемого изображения
+/// * `top_height` – высота накладываемого изображения
+/// * `mode`       – режим наложения
+/// * `opacity`    – непрозрачность наложения (0..=1), умножается на альфа-канал `top`
+/// * `fit`        – способ согласования размеров
+///
+/// Альфа-канал исходного изображения не изменяется
+///
+#[allow(clippy::too_many_arguments)]
+pub fn blend_buffers(
+    base: &mut [u8],
+    width: usize,
+    height: usize,
+    top: &[u8],
+    top_width: usize,
+    top_height: usize,
+    mode: BlendMode,
+    opacity: f64,
+    fit: Fit,
+) -> Result<(), Error> {
+    if top_width == 0 || top_height == 0 {
+        return Err(Error::ErrorValue("Blend image is empty".to_string()));
+    }
+    if fit == Fit::Exact && (top_width != width || top_height != height) {
+        return Err(Error::ErrorValue(format!(
+            "Blend image size {}x{} does not match {}x{}",
+            top_width, top_height, width, height
+        )));
+    }
+    let offset_x = (width as i64 - top_width as i64) / 2;
+    let offset_y = (height as i64 - top_height as i64) / 2;
+    for y in 0..height {
+        for x in 0..width {
+            let (top_x, top_y) = match fit {
+                Fit::Exact => (x, y),
+                Fit::Tile => (x % top_width, y % top_height),
+                Fit::Center => {
+                    let top_x = x as i64 - offset_x;
+                    let top_y = y as i64 - offset_y;
+                    if top_x < 0
+                        || top_y < 0
+                        || top_x >= top_width as i64
+                        || top_y >= top_height as i64
+                    {
+                        continue;
+                    }
+                    (top_x as usize, top_y as usize)
+                }
+            };
+            let index = (y * width + x) * BYTE_PER_PIXEL;
+            let top_index = (top_y * top_width + top_x) * BYTE_PER_PIXEL;
+            let alpha = opacity * top[top_index + 3] as f64 / 255.0;
+            for channel in 0..3 {
+                let a = base[index + channel];
+                let blended = blend_channel(mode, a, top[top_index + channel]);
+                let value = a as f64 + (blended as f64 - a as f64) * alpha;
+                base[index + channel] = value.round().clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// ```rust
+///
+///  Трансформация RGBA буффера наложением второго изображения
+///
+///
+///  # Параметры
+///   - `width` (`c_uint`):  ширина изображения в пикселях
+///   - `height` (`c_uint`): высота изображения в пикселях
+///   - `rgba_data` (`*mut u8`): Мутабельный сырой указатель на RGBA буфер. Размер буфера `width * height * 4` bytes.
+///   - `params` (`*const c_char`): Сырой указатель на нуль терменированноу строку  конфигурации JSON-формата.
+///
+///  # Параметры конфигурации (JSON формат)
+///   - `log_level` (optional, string): уровень логирования ("Debug", "Info").
+///   - `log_tz` (optional, string): часовой пояс меток времени лога ("local", "utc"), по умолчанию "local"
+///   - `path` (required, string): путь к накладываемому изображению
+///   - `mode` (optional, string): режим наложения ("normal", "multiply", "screen", "overlay", "add", "difference"), по умолчанию "normal"
+///   - `opacity` (optional, float): непрозрачность 0..=1, по умолчанию 1
+///   - `fit` (optional, string): согласование размеров ("exact", "tile", "center"), по умолчанию "exact"
+///
+///  # Пример JSON:
+///  ```json
+///  {
+///     "log_level": "Debug",
+///     "path": "overlay.png",
+///     "mode": "screen",
+///     "opacity": 0.5,
+///     "fit": "center"
+///  }
+///  ```
+///
+///
+///   # Error Handling
+///   - Ошибки фиксируются в логе
+///   - Поля конфигурации, которые плагин не использует, фиксируются в логе предупреждением
+///
+///
+///   # Пример С
+///  ```c
+///  // In C code
+///   unsigned int width = 1920;
+///   unsigned int height = 1080;
+///   unsigned char *image_data = ...; // RGBA buffer, allocated elsewhere
+///   const char *config = "{\"path\": \"overlay.png\", \"mode\": \"multiply\"}";
+///   process_image(width, height, image_data, config);
+///   ```
+/// # Safety
+///  Данная функция  помечена `unsafe`:
+///   - Работа напрямую с сырыми указателями (`rgba_data`, `params`) предстаялет external C code
+///   - Использования недопустимого указателя или парметров width, height undefined behavior.
+/// ```
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn process_image(
+    width: c_uint,
+    height: c_uint,
+    rgba_data: *mut u8,
+    params: *const c_char,
+) {
+    let file = PKG_NAME.to_owned() + ".log";
+    if let Err(e) = setup_logger_once(LevelFilter::Debug, &file) {
+        match e {
+            Error::LoggerInitError(_) => {
+                log::warn!("Logger init error: {}", e);
+            }
+            _ => {
+                log::error!("Logger init error: {}", e);
+                return;
+            }
+        }
+    }
+    log::info!("Start plugin {}", &file);
+    if params.is_null() {
+        log::error!("Pointer params is_null");
+        return;
+    }
+    let config = unsafe { CStr::from_ptr(params) };
+    let params_config = match config.to_str() {
+        Ok(config) => {
+            warn_ignored_fields(config, KNOWN_FIELDS);
+            let config: Result<ConfigReader<ConfigTransform>, Error> =
+                ConfigReader::try_from(config);
+            if let Ok(config) = config {
+                config
+            } else {
+                log::error!("Error converting config to string");
+                return;
+            }
+        }
+        _ => {
+            log::error!("Invalid config file");
+            return;
+        }
+    };
+    // Уровень из конфигурации действует только в потоке вызова до его завершения
+    let _log_level = params_config
+        .config
+        .log_level
+        .as_deref()
+        .map(|log_level| set_call_level(get_log_level(log_level)));
+    if let Some(log_tz) = params_config.config.log_tz {
+        set_log_timezone(log_tz);
+    }
+    if rgba_data.is_null() {
+        log::error!("Null pointer rgba_data");
+        return;
+    }
+    if width == 0 {
+        log::error!("width cannot be 0");
+        return;
+    }
+    if height == 0 {
+        log::error!("height cannot be 0");
+        return;
+    }
+    let opacity = params_config.config.opacity.unwrap_or(1.0);
+    if !(0.0..=1.0).contains(&opacity) {
+        log::error!("Opacity must be in 0..=1");
+        return;
+    }
+    let len_in_pixel = match rgba_len(width, height) {
+        Ok(len) => len,
+        Err(e) => {
+            log::error!("Length calculation failed: {}", e);
+            return;
+        }
+    };
+    let height: usize = match height.try_into() {
+        Ok(h) => h,
+        Err(_) => {
+            log::error!("Height conversion failed");
+            return;
+        }
+    };
+    let width: usize = match width.try_into() {
+        Ok(w) => w,
+        Err(_) => {
+            log::error!("Width conversion failed");
+            return;
+        }
+    };
+    let top = match image::open(&params_config.config.path) {
+        Ok(top) => top.to_rgba8(),
+        Err(e) => {
+            log::error!("Could not open blend image {}: {}", params_config.config.path, e);
+            return;
+        }
+    };
+    let mode = params_config.config.mode.unwrap_or_default();
+    log::info!("Blend {} with mode {:?}", params_config.config.path, mode);
+    let buf = unsafe { slice::from_raw_parts_mut(rgba_data, len_in_pixel) };
+    if let Err(e) = blend_buffers(
+        buf,
+        width,
+        height,
+        top.as_raw(),
+        top.width() as usize,
+        top.height() as usize,
+        mode,
+        opacity,
+        params_config.config.fit.unwrap_or_default(),
+    ) {
+        log::error!("Blend error: {}", e);
+        return;
+    }
+    log::info!("Finish converting image");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+    use std::ffi::CString;
+
+    #[test]
+    fn test_multiply_black_stays_black() {
+        for top in [0, 77, 255] {
+            assert_eq!(blend_channel(BlendMode::Multiply, 0, top), 0);
+        }
+    }
+
+    #[test]
+    fn test_screen_white_stays_white() {
+        for top in [0, 77, 255] {
+            assert_eq!(blend_channel(BlendMode::Screen, 255, top), 255);
+        }
+    }
+
+    #[test]
+    fn test_add_and_difference_clamped() {
+        assert_eq!(blend_channel(BlendMode::Add, 200, 100), 255);
+        assert_eq!(blend_channel(BlendMode::Difference, 100, 200), 100);
+    }
+
+    #[test]
+    fn test_blend_size_mismatch() {
+        let mut base = vec![0; 16];
+        let top = vec![0; 4];
+        let result = blend_buffers(&mut base, 2, 2, &top, 1, 1, BlendMode::Normal, 1.0, Fit::Exact);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_blend_center() {
+        let mut base = vec![0; 36];
+        let top = vec![255; 4];
+        blend_buffers(&mut base, 3, 3, &top, 1, 1, BlendMode::Normal, 1.0, Fit::Center).unwrap();
+        for (pixel, chunk) in base.chunks_exact(4).enumerate() {
+            let expected: [u8; 4] = if pixel == 4 { [255, 255, 255, 0] } else { [0, 0, 0, 0] };
+            assert_eq!(chunk, expected);
+        }
+    }
+
+    #[test]
+    fn test_blend_image_multiply() {
+        let path = std::env::temp_dir().join("blend_plugin_multiply.png");
+        RgbaImage::from_pixel(2, 1, Rgba([128, 255, 255, 255])).save(&path).unwrap();
+        let mut buf = vec![0, 0, 0, 255, 200, 100, 50, 255];
+        let json = format!(r#"{{"path": "{}", "mode": "multiply"}}"#, path.display());
+        let params_cstring = CString::new(json).unwrap();
+        unsafe { process_image(2, 1, buf.as_mut_ptr(), params_cstring.as_ptr()) };
+        assert_eq!(buf, vec![0, 0, 0, 255, 100, 100, 50, 255]);
+    }
+}
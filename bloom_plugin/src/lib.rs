@@ -0,0 +1,324 @@
+#![warn(missing_docs)]
+//! Модуль для реализации обработки изображения - свечение (bloom)
+//!
+//! Предоставляет функциональность эффекта свечения: яркие области выше порога выделяются,
+//! размываются фильтром Гаусса и прибавляются к исходному изображению
+
+use log::LevelFilter;
+use plugins_support::config_parse::warn_ignored_fields;
+use plugins_support::image_size::rgba_len;
+use plugins_support::logger::{
+    LogTimezone, get_log_level, set_call_level, set_log_timezone, setup_logger_once,
+};
+use plugins_support::{config_parse::ConfigReader, error::Error};
+use serde::Deserialize;
+use std::ffi::{CStr, c_char, c_uint};
+use std::slice;
+
+const PKG_NAME: &str = env!("CARGO_PKG_NAME");
+const BYTE_PER_PIXEL: usize = 4;
+const CHANNELS: usize = 3;
+
+plugins_support::export_warning_count!();
+plugins_support::export_abi_probe!();
+
+/// Поля конфигурации, которые использует плагин
+const KNOWN_FIELDS: &[&str] = &["threshold", "radius", "intensity"];
+
+#[derive(Deserialize, Debug)]
+struct ConfigTransform {
+    threshold: f32,
+    radius: usize,
+    intensity: f32,
+    log_level: Option<String>,
+    log_tz: Option<LogTimezone>,
+}
+
+/// Яркость пикселя RGBA (BT.601) в диапазоне 0..=1
+fn luminance(pixel: &[u8]) -> f32 {
+    (0.299 * pixel[0] as f32 + 0.587 * pixel[1] as f32 + 0.114 * pixel[2] as f32) / 255.0
+}
+
+/// Нормированное ядро Гаусса длиной `2 * radius + 1` (sigma = radius / 2)
+fn gaussian_kernel(radius: usize) -> Vec<f32> {
+    let sigma = (radius as f32 / 2.0).max(0.5);
+    let kernel: Vec<f32> = (0..=2 * radius)
+        .map(|i| {
+            let x = i as f32 - radius as f32;
+            (-x * x / (2.0 * sigma * sigma)).exp()
+        })
+        .collect();
+    let sum: f32 = kernel.iter().sum();
+    kernel.into_iter().map(|weight| weight / sum).collect()
+}
+
+/// Разделимое размытие Гаусса RGB буфера `f32` (3 значения на пиксель), края дополняются нулями
+///
+/// # Аргументы
+/// * `buf`    – буфер RGB (длина = width * height * 3)
+/// * `width`  – ширина в пикселях
+/// * `height` – высота в пикселях
+/// * `radius` – радиус ядра в пикселях
+///
+pub fn gaussian_blur(buf: &[f32], width: usize, height: usize, radius: usize) -> Vec<f32> {
+    let kernel = gaussian_kernel(radius);
+    let pass = |source: &[f32], horizontal: bool| {
+        let mut result = vec![0.0; source.len()];
+        for y in 0..height {
+            for x in 0..width {
+                for (offset, weight) in kernel.iter().enumerate() {
+                    let (sample_x, sample_y) = if horizontal {
+                        ((x + offset).checked_sub(radius), Some(y))
+                    } else {
+                        (Some(x), (y + offset).checked_sub(radius))
+                    };
+                    let (Some(sample_x), Some(sample_y)) = (sample_x, sample_y) else {
+                        continue;
+                    };
+                    if sample_x >= width || sample_y >= height {
+                        continue;
+                    }
+                    let from = (sample_y * width + sample_x) * CHANNELS;
+                    let to = (y * width + x) * CHANNELS;
+                    for channel in 0..CHANNELS {
+                        result[to + channel] += source[from + channel] * weight;
+                    }
+                }
+            }
+        }
+        result
+    };
+    pass(&pass(buf, true), false)
+}
+
+/// Эффект свечения RGBA буфера
+///
+/// # Аргументы
+/// * `buf`       – изменяемый буфер RGBA (длина = width * height * 4), альфа-канал не изменяется
+/// * `width`     – ширина в пикселях
+/// * `height`    – высота в пикселях
+/// * `threshold` – порог яркости 0..=1, пиксели ярче порога светятся
+/// * `radius`    – радиус размытия свечения в пикселях
+/// * `intensity` – множитель свечения (>= 0)
+///
+pub fn bloom(
+    buf: &mut [u8],
+    width: usize,
+    height: usize,
+    threshold: f32,
+    radius: usize,
+    intensity: f32,
+) -> Result<(), Error> {
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err(Error::ErrorValue("Threshold must be in 0..=1".to_string()));
+    }
+    if intensity.is_nan() || intensity < 0.0 {
+        return Err(Error::ErrorValue("Intensity must be non-negative".to_string()));
+    }
+    let bright: Vec<f32> = buf
+        .chunks_exact(BYTE_PER_PIXEL)
+        .flat_map(|pixel| {
+            let glow = luminance(pixel) > threshold;
+            (0..CHANNELS).map(move |channel| if glow { pixel[channel] as f32 } else { 0.0 })
+        })
+        .collect();
+    let glow = gaussian_blur(&bright, width, height, radius);
+    for (pixel, glow) in buf.chunks_exact_mut(BYTE_PER_PIXEL).zip(glow.chunks_exact(CHANNELS)) {
+        for (value, glow) in pixel[..CHANNELS].iter_mut().zip(glow) {
+            *value = (*value as f32 + glow * intensity).round().clamp(0.0, 255.0) as u8;
+        }
+    }
+    Ok(())
+}
+
+/// ```rust
+///
+///  Трансформация RGBA буффера эффектом свечения
+///
+///
+///  # Параметры
+///   - `width` (`c_uint`):  ширина изображения в пикселях
+///   - `height` (`c_uint`): высота изображения в пикселях
+///   - `rgba_data` (`*mut u8`): Мутабельный сырой указатель на RGBA буфер. Размер буфера `width * height * 4` bytes.
+///   - `params` (`*const c_char`): Сырой указатель на нуль терменированноу строку  конфигурации JSON-формата.
+///
+///  # Параметры конфигурации (JSON формат)
+///   - `log_level` (optional, string): уровень логирования ("Debug", "Info").
+///   - `log_tz` (optional, string): часовой пояс меток времени лога ("local", "utc"), по умолчанию "local"
+///   - `threshold` (required, float): порог яркости 0..=1, пиксели ярче порога светятся
+///   - `radius` (required, integer): радиус размытия свечения в пикселях
+///   - `intensity` (required, float): множитель свечения. Должен быть >= 0
+///
+///  # Пример JSON:
+///  ```json
+///  {
+///     "log_level": "Debug",
+///     "threshold": 0.8,
+///     "radius": 8,
+///     "intensity": 0.6
+///  }
+///  ```
+///
+///
+///   # Error Handling
+///   - Ошибки фиксируются в логе
+///   - Поля конфигурации, которые плагин не использует, фиксируются в логе предупреждением
+///
+///
+///   # Пример С
+///  ```c
+///  // In C code
+///   unsigned int width = 1920;
+///   unsigned int height = 1080;
+///   unsigned char *image_data = ...; // RGBA buffer, allocated elsewhere
+///   const char *config = "{\"threshold\": 0.8, \"radius\": 8, \"intensity\": 0.6}";
+///   process_image(width, height, image_data, config);
+///   ```
+/// # Safety
+///  Данная функция  помечена `unsafe`:
+///   - Работа напрямую с сырыми указателями (`rgba_data`, `params`) предстаялет external C code
+///   - Использования недопустимого указателя или парметров width, height undefined behavior.
+/// ```
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn process_image(
+    width: c_uint,
+    height: c_uint,
+    rgba_data: *mut u8,
+    params: *const c_char,
+) {
+    let file = PKG_NAME.to_owned() + ".log";
+    if let Err(e) = setup_logger_once(LevelFilter::Debug, &file) {
+        match e {
+            Error::LoggerInitError(_) => {
+                log::warn!("Logger init error: {}", e);
+            }
+            _ => {
+                log::error!("Logger init error: {}", e);
+                return;
+            }
+        }
+    }
+    log::info!("Start plugin {}", &file);
+    if params.is_null() {
+        log::error!("Pointer params is_null");
+        return;
+    }
+    let config = unsafe { CStr::from_ptr(params) };
+    let params_config = match config.to_str() {
+        Ok(config) => {
+            warn_ignored_fields(config, KNOWN_FIELDS);
+            let config: Result<ConfigReader<ConfigTransform>, Error> =
+                ConfigReader::try_from(config);
+            if let Ok(config) = config {
+                config
+            } else {
+                log::error!("Error converting config to string");
+                return;
+            }
+        }
+        _ => {
+            log::error!("Invalid config file");
+            return;
+        }
+    };
+    // Уровень из конфигурации действует только в потоке вызова до его завершения
+    let _log_level = params_config
+        .config
+        .log_level
+        .as_deref()
+        .map(|log_level| set_call_level(get_log_level(log_level)));
+    if let Some(log_tz) = params_config.config.log_tz {
+        set_log_timezone(log_tz);
+    }
+    if rgba_data.is_null() {
+        log::error!("Null pointer rgba_data");
+        return;
+    }
+    if width == 0 {
+        log::error!("width cannot be 0");
+        return;
+    }
+    if height == 0 {
+        log::error!("height cannot be 0");
+        return;
+    }
+    let len_in_pixel = match rgba_len(width, height) {
+        Ok(len) => len,
+        Err(e) => {
+            log::error!("Length calculation failed: {}", e);
+            return;
+        }
+    };
+    let height: usize = match height.try_into() {
+        Ok(h) => h,
+        Err(_) => {
+            log::error!("Height conversion failed");
+            return;
+        }
+    };
+    let width: usize = match width.try_into() {
+        Ok(w) => w,
+        Err(_) => {
+            log::error!("Width conversion failed");
+            return;
+        }
+    };
+    log::info!("Start converting image");
+    let buf = unsafe { slice::from_raw_parts_mut(rgba_data, len_in_pixel) };
+    let config = &params_config.config;
+    if let Err(e) = bloom(buf, width, height, config.threshold, config.radius, config.intensity) {
+        log::error!("Bloom error: {}", e);
+        return;
+    }
+    log::info!("Finish converting image");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    const SIZE: usize = 21;
+    const DARK: u8 = 20;
+
+    /// Темное изображение с белым пятном 3x3 в центре
+    fn bright_spot() -> Vec<u8> {
+        let mut buf = vec![DARK; SIZE * SIZE * BYTE_PER_PIXEL];
+        for (index, pixel) in buf.chunks_exact_mut(BYTE_PER_PIXEL).enumerate() {
+            let (x, y) = (index % SIZE, index / SIZE);
+            if x.abs_diff(SIZE / 2) <= 1 && y.abs_diff(SIZE / 2) <= 1 {
+                pixel[..3].fill(255);
+            }
+            pixel[3] = 255;
+        }
+        buf
+    }
+
+    #[test]
+    fn test_bloom_glow_around_spot() {
+        let source = bright_spot();
+        let mut buf = source.clone();
+        let json = r#"{"threshold": 0.8, "radius": 3, "intensity": 1.0}"#;
+        let params_cstring = CString::new(json).unwrap();
+        unsafe { process_image(SIZE as c_uint, SIZE as c_uint, buf.as_mut_ptr(), params_cstring.as_ptr()) };
+        let red = |buf: &[u8], x: usize, y: usize| buf[(y * SIZE + x) * BYTE_PER_PIXEL];
+        let middle = SIZE / 2;
+        assert_eq!(red(&buf, middle, middle), 255);
+        // Свечение убывает с расстоянием от пятна
+        let near = red(&buf, middle + 2, middle);
+        let far = red(&buf, middle + 4, middle);
+        assert!(near > DARK && far > DARK && near > far, "near {} far {}", near, far);
+        // Темные области вне радиуса свечения не меняются
+        assert_eq!(red(&buf, 0, 0), DARK);
+        assert_eq!(red(&buf, middle + 5, middle), DARK);
+        assert!(buf.chunks_exact(BYTE_PER_PIXEL).all(|pixel| pixel[3] == 255));
+    }
+
+    #[test]
+    fn test_bloom_invalid_config() {
+        let mut buf = bright_spot();
+        assert!(bloom(&mut buf, SIZE, SIZE, 1.5, 3, 1.0).is_err());
+        assert!(bloom(&mut buf, SIZE, SIZE, 0.8, 3, -1.0).is_err());
+        assert_eq!(buf, bright_spot());
+    }
+}
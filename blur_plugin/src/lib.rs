@@ -4,20 +4,101 @@
 //! Предоставляет функциональность размытию изображения (взатие среднего значения в пределах радиуса размытия)
 
 use log::LevelFilter;
-use plugins_support::logger::{get_log_level, setup_logger};
+use plugins_support::color::{linear_to_srgb, srgb_to_linear};
+use plugins_support::outcome::ProcessOutcome;
+use plugins_support::logger::{
+    LogTimezone, get_log_level, set_call_level, set_log_timezone, setup_logger_once,
+};
+use plugins_support::progress::{ProgressCallback, step_percent};
+use plugins_support::config_parse::{CLAMP_CONFIG_FIELD, check_range, warn_ignored_fields};
+use plugins_support::geometry::Geometry;
+use plugins_support::region::{REGIONS_FIELD, Region, resolve_regions};
 use plugins_support::{config_parse::ConfigReader, error::Error};
 use serde::Deserialize;
 use std::ffi::{CStr, c_char, c_uint};
+use std::ops::AddAssign;
 use std::slice;
 
 const PKG_NAME: &str = env!("CARGO_PKG_NAME");
 const BYTE_PER_PIXEL: usize = 4;
+/// Наибольший радиус размытия
+const MAX_RADIUS: usize = 1024;
+/// Наибольшее количество шагов размытия
+const MAX_STEP: usize = 100;
+
+plugins_support::export_warning_count!();
+plugins_support::export_abi_probe!();
+
+/// Поля конфигурации, которые использует плагин
+const KNOWN_FIELDS: &[&str] = &["radius", "step", "linear_light", CLAMP_CONFIG_FIELD, REGIONS_FIELD];
+
+/// Радиус размытия: один для всех каналов или отдельный для каждого канала
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+enum Radius {
+    /// Один радиус для всех каналов, включая альфа-канал
+    All(usize),
+    /// Радиусы каналов `{"r": 3, "g": 0, "b": 5}`, альфа-канал `a` по умолчанию 0
+    Channels {
+        r: usize,
+        g: usize,
+        b: usize,
+        #[serde(default)]
+        a: usize,
+    },
+    /// Радиусы каналов массивом `[r, g, b]` или `[r, g, b, a]`
+    List(Vec<usize>),
+}
+
+impl Radius {
+    ///
+    /// Радиусы каналов R, G, B, A, 0 - канал не размывается
+    ///
+    /// # Параметры
+    ///
+    /// * `clamp` - ограничить радиус вне диапазона вместо ошибки (`clamp_config`)
+    ///
+    /// # Возращает
+    /// `ErrorValue` для единого радиуса 0, радиуса больше `MAX_RADIUS`
+    /// или массива длиной не 3 и не 4
+    ///
+    fn channels(&self, clamp: bool) -> Result<[usize; BYTE_PER_PIXEL], Error> {
+        let channel = |radius| check_range("Radius", radius, 0..=MAX_RADIUS, clamp);
+        match *self {
+            Radius::All(radius) => Ok([check_range("Radius", radius, 1..=MAX_RADIUS, clamp)?; BYTE_PER_PIXEL]),
+            Radius::Channels { r, g, b, a } => Ok([channel(r)?, channel(g)?, channel(b)?, channel(a)?]),
+            Radius::List(ref radii) => match radii[..] {
+                [r, g, b] => Ok([channel(r)?, channel(g)?, channel(b)?, 0]),
+                [r, g, b, a] => Ok([channel(r)?, channel(g)?, channel(b)?, channel(a)?]),
+                _ => Err(Error::ErrorValue(format!(
+                    "Radius array must have 3 or 4 values, got {}",
+                    radii.len()
+                ))),
+            },
+        }
+    }
+}
 
 #[derive(Deserialize, Debug)]
 struct ConfigTransform {
-    radius: usize,
+    radius: Radius,
     step: usize,
+    regions: Option<Vec<Region>>,
+    linear_light: Option<bool>,
+    clamp_config: Option<bool>,
     log_level: Option<String>,
+    log_tz: Option<LogTimezone>,
+}
+
+/// JSON Schema параметров плагина, нуль терминированная строка
+const PARAMS_SCHEMA: &str = concat!(include_str!("../params-schema.json"), "\0");
+
+/// JSON Schema параметров плагина (нуль терминированная строка со статическим временем жизни)
+///
+/// Используется хостом для проверки параметров до вызова плагина (`--validate-schema`)
+#[unsafe(no_mangle)]
+pub extern "C" fn plugin_params_schema() -> *const c_char {
+    PARAMS_SCHEMA.as_ptr() as *const c_char
 }
 
 /// ```rust
@@ -33,8 +114,21 @@ struct ConfigTransform {
 ///
 ///  # Параметры конфигурации (JSON формат)
 ///   - `log_level` (optional, string): уровень логирования ("Debug", "Info").
-///   - `radius` (required, integer): Радиус сглаживания. Должен быть больше 0
-///   - `step` (required, integer): Количество итераций. Должен быть больше 0
+///   - `log_tz` (optional, string): часовой пояс меток времени лога ("local", "utc"), по умолчанию "local"
+///   - `radius` (required, integer | object | array): Радиус сглаживания. Целое число больше 0
+///     применяется ко всем каналам, включая альфа-канал. Радиусы каналов задаются объектом
+///     `{"r": 3, "g": 0, "b": 5}` (необязательный `a`, по умолчанию 0) или массивом
+///     `[r, g, b]` / `[r, g, b, a]`; радиус 0 - канал не размывается. Не больше 1024
+///   - `step` (required, integer): Количество итераций, 1..=100
+///   - `regions` (optional, array): Области размытия `{"x", "y", "width", "height"}`,
+///     по умолчанию все изображение. Области обрабатываются по очереди в каждой итерации,
+///     перекрывающаяся часть размывается несколько раз. Область нулевой площади пропускается,
+///     выходящая за край изображения обрезается по краю
+///   - `linear_light` (optional, bool): усреднение в линейной яркости (sRGB -> linear -> sRGB),
+///     по умолчанию false - усреднение значений sRGB
+///   - `clamp_config` (optional, bool): `radius` и `step` вне допустимого диапазона заменяются
+///     ближайшим допустимым значением с предупреждением в лог, по умолчанию false - ошибка
+///     и изображение не изменяется
 ///
 ///  # Пример JSON:
 ///  ```json
@@ -48,6 +142,7 @@ struct ConfigTransform {
 ///
 ///   # Error Handling
 ///   - Ошибки фиксируются в логе
+///   - Поля конфигурации, которые плагин не использует, фиксируются в логе предупреждением
 ///
 ///
 ///   # Пример С
@@ -70,9 +165,29 @@ pub unsafe extern "C" fn process_image(
     height: c_uint,
     rgba_data: *mut u8,
     params: *const c_char,
+) {
+    unsafe { process_image_with_progress(width, height, rgba_data, params, None) }
+}
+
+/// Трансформация RGBA буффера эффектом размытия с уведомлением о прогрессе
+///
+/// Параметры и конфигурация совпадают с [`process_image`]. Дополнительно принимает
+///   - `progress` (`Option<ProgressCallback>`): функция обратного вызова, вызывается после
+///     каждого шага `step` с процентом выполнения `(текущий шаг * 100) / step`.
+///     Может быть `NULL`
+///
+/// # Safety
+///  Требования к указателям совпадают с [`process_image`]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn process_image_with_progress(
+    width: c_uint,
+    height: c_uint,
+    rgba_data: *mut u8,
+    params: *const c_char,
+    progress: Option<ProgressCallback>,
 ) {
     let file = PKG_NAME.to_owned() + ".log";
-    if let Err(e) = setup_logger(LevelFilter::Debug, &file){
+    if let Err(e) = setup_logger_once(LevelFilter::Debug, &file){
         match e {
             Error::LoggerInitError(_) => {
                 log::warn!("Logger init error: {}", e);
@@ -91,6 +206,7 @@ pub unsafe extern "C" fn process_image(
     let config = unsafe { CStr::from_ptr(params) };
     let params_config = match config.to_str() {
         Ok(config) => {
+            warn_ignored_fields(config, KNOWN_FIELDS);
             let config: Result<ConfigReader<ConfigTransform>, Error> =
                 ConfigReader::try_from(config);
             if let Ok(config) = config {
@@ -105,81 +221,195 @@ pub unsafe extern "C" fn process_image(
             return;
         }
     };
-    if let Some(log_level) = params_config.config.log_level {
-        let log_level_filter = get_log_level(&log_level);
-        log::set_max_level(log_level_filter);
+    // Уровень из конфигурации действует только в потоке вызова до его завершения
+    let _log_level = params_config
+        .config
+        .log_level
+        .as_deref()
+        .map(|log_level| set_call_level(get_log_level(log_level)));
+    if let Some(log_tz) = params_config.config.log_tz {
+        set_log_timezone(log_tz);
     }
     if rgba_data.is_null() {
         log::error!("Null pointer rgba_data");
         return;
     }
-    if width == 0 {
-        log::error!("width cannot be 0");
-        return;
-    }
-    if height == 0 {
-        log::error!("height cannot be 0");
-        return;
-    }
     log::info!("Start converting image");
-    let height: usize = match height.try_into() {
-        Ok(h) => h,
-        Err(_) => {
-            log::error!("Height conversion failed");
+    let geometry = match Geometry::rgba(width, height) {
+        Ok(geometry) => geometry,
+        Err(e) => {
+            log::error!("Invalid image size: {}", e);
             return;
         }
     };
-    let width: usize = match width.try_into() {
-        Ok(w) => w,
-        Err(_) => {
-            log::error!("Width conversion failed");
+    let (width, height) = (geometry.width(), geometry.height());
+    let regions = match resolve_regions(params_config.config.regions.as_deref(), width, height) {
+        Ok(regions) => regions,
+        Err(e) => {
+            log::error!("Invalid regions: {}", e);
             return;
         }
     };
-    let len_image = match width.checked_mul(height) {
-        Some(wh) => wh,
-        None => {
-            log::error!("Length calculation failed");
+    let buf = unsafe { slice::from_raw_parts_mut(rgba_data, geometry.total_len()) };
+    let linear_light = params_config.config.linear_light.unwrap_or(false);
+    let clamp = params_config.config.clamp_config.unwrap_or(false);
+    let radii = match params_config.config.radius.channels(clamp) {
+        Ok(radii) => radii,
+        Err(e) => {
+            log::error!("Invalid radius: {}", e);
             return;
         }
     };
-    let len_in_pixel = match len_image.checked_mul(BYTE_PER_PIXEL) {
-        Some(len) => len,
-        None => {
-            log::error!("Length calculation failed");
+    let steps = match check_range("Step", params_config.config.step, 1..=MAX_STEP, clamp) {
+        Ok(steps) => steps,
+        Err(e) => {
+            log::error!("Invalid step: {}", e);
             return;
         }
     };
-    let buf = unsafe { slice::from_raw_parts_mut(rgba_data, len_in_pixel) };
-    if params_config.config.radius > 0 {
-        if params_config.config.step > 0 {
-            for _ in 0..params_config.config.step {
-                for i in 0..len_image {
-                    for channel in 0..4 {
-                        let result = blur_rgba(
-                            buf,
-                            i,
-                            width,
-                            height,
-                            BYTE_PER_PIXEL,
-                            params_config.config.radius,
-                            channel,
-                        );
-                        if let Ok((sum, index)) = result {
-                            buf[index] = sum;
-                        }
-                    }
+    let options = BlurOptions {
+        radii,
+        steps,
+        regions,
+        linear_light,
+    };
+    let on_step = |step| {
+        if let Some(progress) = progress {
+            progress(step_percent(step, steps));
+        }
+    };
+    match blur(buf, width, height, &options, on_step) {
+        Ok(outcome) => log::info!(
+            "Finish converting image: {} pixels changed, {} failures",
+            outcome.pixels_changed,
+            outcome.failures
+        ),
+        Err(e) => log::error!("Blur error: {}", e),
+    }
+}
+
+/// Параметры размытия безопасного API [`blur`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlurOptions {
+    /// Радиусы каналов R, G, B, A, 0 - канал не размывается
+    pub radii: [usize; BYTE_PER_PIXEL],
+    /// Количество шагов размытия
+    pub steps: usize,
+    /// Области размытия (уже нормализованные, см. `resolve_regions`)
+    pub regions: Vec<Region>,
+    /// Усреднение в линейной яркости
+    pub linear_light: bool,
+}
+
+///
+/// Размытие RGBA8 буфера
+///
+/// # Параметры
+///
+/// * `buf` - буфер RGBA (длина = width * height * 4)
+/// * `width` - ширина в пикселях
+/// * `height` - высота в пикселях
+/// * `options` - параметры размытия
+/// * `on_step` - вызывается с номером завершенного шага (с 1)
+///
+/// # Возращает
+/// Статистику обработки: изменившиеся пиксели и количество значений каналов, пропущенных
+/// из-за переполнения индекса, или `ErrorValue`, если длина буфера не равна `width * height * 4`
+///
+pub fn blur(
+    buf: &mut [u8],
+    width: usize,
+    height: usize,
+    options: &BlurOptions,
+    mut on_step: impl FnMut(usize),
+) -> Result<ProcessOutcome, Error> {
+    let len = width
+        .checked_mul(height)
+        .and_then(|len| len.checked_mul(BYTE_PER_PIXEL))
+        .ok_or(Error::OverflowError)?;
+    if buf.len() != len {
+        return Err(Error::ErrorValue(format!(
+            "Buffer length {} does not match {}x{}",
+            buf.len(),
+            width,
+            height
+        )));
+    }
+    let source = buf.to_vec();
+    let mut outcome = ProcessOutcome::default();
+    let (regions, radii) = (&options.regions, options.radii);
+    let mut linear = options.linear_light.then(|| to_linear(buf));
+    for step in 1..=options.steps {
+        outcome.failures += match linear.as_mut() {
+            Some(linear) => blur_regions(linear, regions, width, height, radii, |sum| sum),
+            None => blur_regions(buf, regions, width, height, radii, |sum| sum as u8),
+        };
+        on_step(step);
+    }
+    if let Some(linear) = linear {
+        from_linear(&linear, buf);
+    }
+    if outcome.failures > 0 {
+        outcome.warn(format!("{} channel values skipped: index overflow", outcome.failures));
+    }
+    outcome.pixels_changed = ProcessOutcome::count_changed(&source, buf, BYTE_PER_PIXEL);
+    Ok(outcome)
+}
+
+/// Перевод RGBA8 буфера в линейную яркость: каналы RGB через передаточную функцию sRGB,
+/// альфа-канал линейно в `0..=1`
+fn to_linear(buf: &[u8]) -> Vec<f64> {
+    buf.chunks_exact(BYTE_PER_PIXEL)
+        .flat_map(|pixel| {
+            [
+                srgb_to_linear(pixel[0]),
+                srgb_to_linear(pixel[1]),
+                srgb_to_linear(pixel[2]),
+                pixel[3] as f64 / 255.0,
+            ]
+        })
+        .collect()
+}
+
+/// Обратный к [`to_linear`] перевод в RGBA8 буфер `buf`
+fn from_linear(linear: &[f64], buf: &mut [u8]) {
+    for (pixel, value) in buf
+        .chunks_exact_mut(BYTE_PER_PIXEL)
+        .zip(linear.chunks_exact(BYTE_PER_PIXEL))
+    {
+        for channel in 0..3 {
+            pixel[channel] = linear_to_srgb(value[channel]);
+        }
+        pixel[3] = (value[3].clamp(0.0, 1.0) * 255.0).round() as u8;
+    }
+}
+
+/// Один шаг размытия областей `regions` с радиусами каналов `radii` (0 - канал
+/// не изменяется), `store` переводит среднее в значение буфера.
+/// Возвращает количество значений каналов, пропущенных из-за ошибки усреднения
+fn blur_regions<T: ChannelSum>(
+    buf: &mut [T],
+    regions: &[Region],
+    width: usize,
+    height: usize,
+    radii: [usize; BYTE_PER_PIXEL],
+    store: impl Fn(f64) -> T,
+) -> usize {
+    let mut failures = 0;
+    for region in regions {
+        for i in region.pixels(width) {
+            for (channel, &radius) in radii.iter().enumerate() {
+                if radius == 0 {
+                    continue;
+                }
+                match box_average(buf, i, width, height, BYTE_PER_PIXEL, radius, channel) {
+                    Ok((sum, index)) => buf[index] = store(sum),
+                    Err(_) => failures += 1,
                 }
             }
-        } else {
-            log::error!("Step cannot be 0");
-            return;
         }
-    } else {
-        log::error!("Radius cannot be 0");
-        return;
     }
-    log::info!("Finish converting image");
+    failures
 }
 
 /// Размытие RGBA8-изображения box blur'ом.
@@ -202,13 +432,72 @@ pub fn blur_rgba(
     radius: usize,
     channel: usize,
 ) -> Result<(u8, usize), Error> {
+    let (sum, index) = box_average(buf, index_pixel, width, height, byte_per_pixel, radius, channel)?;
+    Ok((sum as u8, index))
+}
+
+/// Значение канала, сумма которого накапливается при усреднении
+pub trait ChannelSum: Copy {
+    /// Тип суммы
+    type Sum: Copy + Default + AddAssign;
+    /// Значение в типе суммы
+    fn widen(self) -> Self::Sum;
+    /// Среднее `count` значений с суммой `sum`
+    fn mean(sum: Self::Sum, count: u64) -> f64;
+}
+
+/// Байты sRGB суммируются в `u64` без потери точности, деление выполняется один раз
+/// (целочисленное, с отбрасыванием дробной части), поэтому результат не зависит
+/// от радиуса и ширины изображения
+impl ChannelSum for u8 {
+    type Sum = u64;
+
+    fn widen(self) -> u64 {
+        self.into()
+    }
+
+    fn mean(sum: u64, count: u64) -> f64 {
+        (sum / count) as f64
+    }
+}
+
+/// Линейная яркость суммируется в `f64`
+impl ChannelSum for f64 {
+    type Sum = f64;
+
+    fn widen(self) -> f64 {
+        self
+    }
+
+    fn mean(sum: f64, count: u64) -> f64 {
+        sum / count as f64
+    }
+}
+
+/// Среднее значение канала в кресте радиуса `radius` вокруг пикселя
+///
+/// Аргументы совпадают с [`blur_rgba`], буфер может хранить как байты sRGB,
+/// так и линейную яркость; сумма накапливается в [`ChannelSum::Sum`]
+///
+/// # Возращает
+/// Среднее значение и индекс канала пикселя в буфере
+///
+pub fn box_average<T: ChannelSum>(
+    buf: &[T],
+    index_pixel: usize,
+    width: usize,
+    height: usize,
+    byte_per_pixel: usize,
+    radius: usize,
+    channel: usize,
+) -> Result<(f64, usize), Error> {
     assert_eq!(buf.len(), width * height * byte_per_pixel);
     if radius == 0 {
         log::error!("Radius cannot be 0");
         return Err(Error::ErrorValue("Radius cannot be 0".to_string()));
     }
     let mut count = 0;
-    let mut sum = 0.0;
+    let mut sum = T::Sum::default();
     let index_pixel: i32 = index_pixel.try_into()?;
     let radius: i32 = radius.try_into()?;
     let channel: i32 = channel.try_into()?;
@@ -263,11 +552,10 @@ pub fn blur_rgba(
             && right < buff_len
             && left < right
         {
-            sum += buf[index_column as usize] as f64;
+            sum += buf[index_column as usize].widen();
             count += 1;
         }
-        let index_row = channel
-            + index
+        let index_row = index
             + match i.checked_mul(len_width_in_byte) {
                 Some(index) => index,
                 None => {
@@ -276,17 +564,17 @@ pub fn blur_rgba(
                 }
             };
         if index_row >= 0 && index_row < buff_len {
-            sum += buf[index_row as usize] as f64;
+            sum += buf[index_row as usize].widen();
             count += 1;
         }
     }
-    let sum = sum / count as f64;
-    Ok((sum as u8, index as usize))
+    Ok((T::mean(sum, count), index as usize))
 }
 
 #[cfg(test)]
 mod tests {
     use std::ffi::CString;
+    use std::sync::Mutex;
     use super::*;
     #[test]
     fn test_blur_rgba() {
@@ -301,7 +589,17 @@ mod tests {
         let json = r#"{"step": 1, "radius": 2}"#;
         let params_cstring = CString::new(json).unwrap();
         unsafe { process_image(2, 2, buf.as_mut_ptr(), params_cstring.as_ptr()) };
-        assert_eq!(buf, vec![3, 4, 6, 7, 5, 7, 5, 7, 5, 8, 8, 9, 8, 9, 4, 7]);
+        assert_eq!(buf, vec![3, 4, 5, 6, 5, 6, 7, 8, 5, 6, 7, 8, 8, 9, 10, 11]);
+    }
+    /// Вертикальный проход читает тот же канал соседних строк: столбец
+    /// одинаковых пикселей с разными каналами не изменяется
+    #[test]
+    fn test_blur_column_keeps_channels() {
+        let mut buf = [10, 60, 110, 160].repeat(3);
+        let json = r#"{"step": 1, "radius": 1}"#;
+        let params_cstring = CString::new(json).unwrap();
+        unsafe { process_image(1, 3, buf.as_mut_ptr(), params_cstring.as_ptr()) };
+        assert_eq!(buf, [10, 60, 110, 160].repeat(3));
     }
     /// Тест радиус i32:MAX для теста переполнения
     /// Так как для теста перполнения размера изображения
@@ -317,4 +615,217 @@ mod tests {
         assert!(matches!(result.unwrap_err(), Error::OverflowError))
     }
 
+    static PROGRESS: Mutex<Vec<c_uint>> = Mutex::new(Vec::new());
+
+    extern "C" fn record_progress(percent: c_uint) {
+        PROGRESS.lock().unwrap().push(percent);
+    }
+
+    #[test]
+    fn test_blur_progress_per_step() {
+        let mut buf = (0..16).collect::<Vec<_>>();
+        let json = r#"{"step": 3, "radius": 1}"#;
+        let params_cstring = CString::new(json).unwrap();
+        unsafe {
+            process_image_with_progress(
+                2,
+                2,
+                buf.as_mut_ptr(),
+                params_cstring.as_ptr(),
+                Some(record_progress),
+            )
+        };
+        assert_eq!(*PROGRESS.lock().unwrap(), vec![33, 66, 100]);
+    }
+
+    #[test]
+    fn test_blur_regions() {
+        // 5x5: все пиксели 200, кроме черных (1, 1) и (3, 3)
+        let mut buf = vec![200; 100];
+        for pixel in [6, 18] {
+            buf[pixel * 4..pixel * 4 + 4].fill(0);
+        }
+        let source = buf.clone();
+        let json = r#"{"step": 1, "radius": 1, "regions": [
+            {"x": 1, "y": 1, "width": 1, "height": 1},
+            {"x": 3, "y": 3, "width": 1, "height": 1}
+        ]}"#;
+        let params_cstring = CString::new(json).unwrap();
+        unsafe { process_image(5, 5, buf.as_mut_ptr(), params_cstring.as_ptr()) };
+        for (pixel, (after, before)) in buf.chunks_exact(4).zip(source.chunks_exact(4)).enumerate() {
+            if pixel == 6 || pixel == 18 {
+                assert_ne!(after, before, "pixel {}", pixel);
+            } else {
+                assert_eq!(after, before, "pixel {}", pixel);
+            }
+        }
+    }
+
+    #[test]
+    fn test_blur_linear_light_edge() {
+        // Граница черное/белое 6x6: левые три столбца черные, правые белые
+        let edge: Vec<u8> = (0..36)
+            .flat_map(|pixel| {
+                let value = if pixel % 6 < 3 { 0 } else { 255 };
+                [value, value, value, 255]
+            })
+            .collect();
+        let blur = |json: &str| {
+            let mut buf = edge.clone();
+            let params_cstring = CString::new(json).unwrap();
+            unsafe { process_image(6, 6, buf.as_mut_ptr(), params_cstring.as_ptr()) };
+            buf
+        };
+        let srgb = blur(r#"{"step": 1, "radius": 1}"#);
+        let linear = blur(r#"{"step": 1, "radius": 1, "linear_light": true}"#);
+        // Пиксель (2, 2) у границы
+        let midpoint = (2 * 6 + 2) * 4;
+        assert!(linear[midpoint] > srgb[midpoint], "linear {} srgb {}", linear[midpoint], srgb[midpoint]);
+        assert_eq!(linear[midpoint + 3], 255);
+    }
+
+    #[test]
+    fn test_blur_zero_area_region() {
+        let mut buf = (0..16).collect::<Vec<_>>();
+        let json = r#"{"step": 1, "radius": 1, "regions": [{"x": 1, "y": 0, "width": 0, "height": 2}]}"#;
+        let params_cstring = CString::new(json).unwrap();
+        unsafe { process_image(2, 2, buf.as_mut_ptr(), params_cstring.as_ptr()) };
+        assert_eq!(buf, (0..16).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn test_blur_over_wide_region_clamped() {
+        // Область 10x1 с (1, 1) обрезается до последнего пикселя (1, 1)
+        let blur = |json: &str| {
+            let mut buf = (0..16).collect::<Vec<u8>>();
+            let params_cstring = CString::new(json).unwrap();
+            unsafe { process_image(2, 2, buf.as_mut_ptr(), params_cstring.as_ptr()) };
+            buf
+        };
+        let wide = blur(r#"{"step": 1, "radius": 1, "regions": [{"x": 1, "y": 1, "width": 10, "height": 1}]}"#);
+        let exact = blur(r#"{"step": 1, "radius": 1, "regions": [{"x": 1, "y": 1, "width": 1, "height": 1}]}"#);
+        assert_eq!(wide, exact);
+        assert_eq!(&wide[..12], &(0..12).collect::<Vec<u8>>()[..]);
+        assert_ne!(&wide[12..], &[12, 13, 14, 15]);
+    }
+
+    #[test]
+    fn test_blur_region_out_of_bounds() {
+        let mut buf = (0..16).collect::<Vec<_>>();
+        let json = r#"{"step": 1, "radius": 1, "regions": [{"x": 2, "y": 1, "width": 1, "height": 1}]}"#;
+        let params_cstring = CString::new(json).unwrap();
+        unsafe { process_image(2, 2, buf.as_mut_ptr(), params_cstring.as_ptr()) };
+        assert_eq!(buf, (0..16).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn test_blur_red_channel_only() {
+        let source: Vec<u8> = (0..36).flat_map(|pixel| [pixel * 7, pixel * 3, 255 - pixel * 5, 200]).collect();
+        let blur = |json: &str| {
+            let mut buf = source.clone();
+            let params_cstring = CString::new(json).unwrap();
+            unsafe { process_image(6, 6, buf.as_mut_ptr(), params_cstring.as_ptr()) };
+            buf
+        };
+        for json in [
+            r#"{"step": 1, "radius": {"r": 2, "g": 0, "b": 0}}"#,
+            r#"{"step": 1, "radius": [2, 0, 0]}"#,
+        ] {
+            let buf = blur(json);
+            let (after, before): (Vec<_>, Vec<_>) = buf.chunks_exact(4).zip(source.chunks_exact(4)).unzip();
+            assert!(after.iter().zip(&before).all(|(a, b)| a[1..] == b[1..]), "{}", json);
+            assert!(after.iter().zip(&before).any(|(a, b)| a[0] != b[0]), "{}", json);
+        }
+        // Единый радиус по-прежнему размывает все каналы
+        assert_eq!(blur(r#"{"step": 1, "radius": 2}"#), blur(r#"{"step": 1, "radius": [2, 2, 2, 2]}"#));
+    }
+
+    #[test]
+    fn test_blur_radius_forms() {
+        let radius = |json: &str| ConfigReader::<Radius>::try_from(json).unwrap().config.channels(false);
+        assert_eq!(radius("3").unwrap(), [3; 4]);
+        assert_eq!(radius(r#"{"r": 3, "g": 0, "b": 5}"#).unwrap(), [3, 0, 5, 0]);
+        assert_eq!(radius("[1, 2, 3, 4]").unwrap(), [1, 2, 3, 4]);
+        assert!(radius("0").is_err());
+        assert!(radius("[1, 2]").is_err());
+    }
+
+    #[test]
+    fn test_params_schema_exported() {
+        let schema = unsafe { CStr::from_ptr(plugin_params_schema()) };
+        let schema = schema.to_str().unwrap();
+        assert!(schema.trim_start().starts_with('{') && schema.trim_end().ends_with('}'));
+        assert!(schema.contains(r#""required": ["radius", "step"]"#));
+    }
+
+    #[test]
+    fn test_blur_clamp_config() {
+        let blur = |json: &str| {
+            let mut buf: Vec<u8> = (0..3 * 2 * 4).map(|value| (value * 10) as u8).collect();
+            let params = CString::new(json).unwrap();
+            unsafe { process_image(3, 2, buf.as_mut_ptr(), params.as_ptr()) };
+            buf
+        };
+        let source: Vec<u8> = (0..3 * 2 * 4).map(|value| (value * 10) as u8).collect();
+        assert_eq!(blur(r#"{"step": 1, "radius": 5000}"#), source);
+        let clamped = blur(r#"{"step": 1, "radius": 5000, "clamp_config": true}"#);
+        assert_ne!(clamped, source);
+        assert_eq!(clamped, blur(r#"{"step": 1, "radius": 1024}"#));
+        assert_eq!(
+            blur(r#"{"step": 0, "radius": 1, "clamp_config": true}"#),
+            blur(r#"{"step": 1, "radius": 1}"#)
+        );
+        let radius = |json: &str| ConfigReader::<Radius>::try_from(json).unwrap().config.channels(true);
+        assert_eq!(radius("5000").unwrap(), [MAX_RADIUS; 4]);
+        assert_eq!(radius("[0, 2000, 3]").unwrap(), [0, MAX_RADIUS, 3, 0]);
+    }
+
+    #[test]
+    fn test_blur_wide_uniform_row_exact() {
+        let width = 2000;
+        let mut buf = [173, 61, 254, 255].repeat(width);
+        let params = CString::new(r#"{"step": 3, "radius": 100}"#).unwrap();
+        unsafe { process_image(width as c_uint, 1, buf.as_mut_ptr(), params.as_ptr()) };
+        assert_eq!(buf, [173, 61, 254, 255].repeat(width));
+    }
+
+    #[test]
+    fn test_blur_integer_sum_matches_naive() {
+        let width = 2000;
+        let source: Vec<u8> = (0..width * 4).map(|i| ((i * 37 + i / 7) % 256) as u8).collect();
+        let regions = [Region::full(width, 1)];
+        let mut exact = source.clone();
+        blur_regions(&mut exact, &regions, width, 1, [7, 3, 50, 0], |mean| mean as u8);
+        // Наивный вариант: сумма в f64, результат отбрасывает дробную часть так же, как `as u8`
+        let mut naive: Vec<f64> = source.iter().map(|&value| value as f64).collect();
+        let mut stored = source.clone();
+        for i in 0..width {
+            for (channel, radius) in [7, 3, 50].into_iter().enumerate() {
+                let (mean, index) = box_average(&naive, i, width, 1, BYTE_PER_PIXEL, radius, channel).unwrap();
+                naive[index] = mean.floor();
+                stored[index] = mean as u8;
+            }
+        }
+        assert_eq!(exact, stored);
+    }
+
+    #[test]
+    fn test_blur_outcome_counts() {
+        let mut buf = [0, 20, 30, 255].repeat(6);
+        buf[4] = 90;
+        let options = BlurOptions { radii: [1, 0, 0, 0], steps: 1, regions: vec![Region::full(3, 2)], linear_light: false };
+        let mut steps = Vec::new();
+        let outcome = blur(&mut buf, 3, 2, &options, |step| steps.push(step)).unwrap();
+        assert_eq!(steps, [1]);
+        assert_eq!(buf.chunks(4).map(|pixel| pixel[0]).collect::<Vec<_>>(), [22, 40, 10, 11, 20, 5]);
+        assert_eq!(outcome, ProcessOutcome { pixels_changed: 6, ..Default::default() });
+
+        let mut buf = [0, 20, 30, 255].repeat(6);
+        let options = BlurOptions { radii: [i32::MAX as usize + 1, 0, 0, 0], steps: 2, ..options };
+        let outcome = blur(&mut buf, 3, 2, &options, |_| ()).unwrap();
+        assert_eq!(buf, [0, 20, 30, 255].repeat(6));
+        assert_eq!((outcome.pixels_changed, outcome.failures), (0, 12));
+        assert_eq!(outcome.warnings.len(), 1);
+        assert!(blur(&mut buf[..8], 3, 2, &options, |_| ()).is_err());
+    }
 }
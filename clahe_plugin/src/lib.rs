@@ -0,0 +1,340 @@
+#![warn(missing_docs)]
+//! Модуль для реализации обработки изображения - локальное повышение контраста (упрощенный CLAHE)
+//!
+//! Предоставляет функциональность адаптивного выравнивания гистограммы с ограничением контраста:
+//! изображение делится на плитки, для каждой плитки строится ограниченная гистограмма яркости
+//! и функция отображения, отображения билинейно интерполируются между центрами плиток.
+//! Каналы RGB масштабируются пропорционально изменению яркости
+
+use log::LevelFilter;
+use plugins_support::logger::{
+    LogTimezone, get_log_level, set_call_level, set_log_timezone, setup_logger_once,
+};
+use plugins_support::config_parse::warn_ignored_fields;
+use plugins_support::image_size::rgba_len;
+use plugins_support::{config_parse::ConfigReader, error::Error};
+use serde::Deserialize;
+use std::ffi::{CStr, c_char, c_uint};
+use std::slice;
+
+const PKG_NAME: &str = env!("CARGO_PKG_NAME");
+const BYTE_PER_PIXEL: usize = 4;
+const BINS: usize = 256;
+
+plugins_support::export_warning_count!();
+plugins_support::export_abi_probe!();
+
+/// Поля конфигурации, которые использует плагин
+const KNOWN_FIELDS: &[&str] = &["tiles_x", "tiles_y", "clip_limit"];
+
+#[derive(Deserialize, Debug)]
+struct ConfigTransform {
+    tiles_x: usize,
+    tiles_y: usize,
+    clip_limit: f64,
+    log_level: Option<String>,
+    log_tz: Option<LogTimezone>,
+}
+
+/// Яркость пикселя RGBA (BT.601)
+pub fn luminance(pixel: &[u8]) -> u8 {
+    (0.299 * pixel[0] as f64 + 0.587 * pixel[1] as f64 + 0.114 * pixel[2] as f64).round() as u8
+}
+
+/// Функция отображения яркости плитки по ограниченной гистограмме
+fn tile_mapping(histogram: &mut [u32; BINS], pixels: u32, clip_limit: f64) -> [u8; BINS] {
+    let limit = ((clip_limit * pixels as f64 / BINS as f64).ceil() as u32).max(1);
+    let mut excess = 0;
+    for bin in histogram.iter_mut() {
+        if *bin > limit {
+            excess += *bin - limit;
+            *bin = limit;
+        }
+    }
+    // Излишек равномерно распределяется по всем корзинам
+    let share = excess / BINS as u32;
+    let remainder = (excess % BINS as u32) as usize;
+    for (index, bin) in histogram.iter_mut().enumerate() {
+        *bin += share + u32::from(index < remainder);
+    }
+    let mut mapping = [0u8; BINS];
+    let mut cdf = 0u64;
+    for (value, bin) in histogram.iter().enumerate() {
+        cdf += *bin as u64;
+        mapping[value] = ((cdf * 255) / pixels as u64) as u8;
+    }
+    mapping
+}
+
+/// Соседние центры плиток по оси и вес второй плитки
+fn tile_axis(position: usize, tile_size: usize, tiles: usize) -> (usize, usize, f64) {
+    let center = (position as f64 + 0.5) / tile_size as f64 - 0.5;
+    if center <= 0.0 {
+        return (0, 0, 0.0);
+    }
+    let first = (center.floor() as usize).min(tiles - 1);
+    let second = (first + 1).min(tiles - 1);
+    (first, second, center - first as f64)
+}
+
+/// Локальное повышение контраста RGBA буфера
+///
+/// # Аргументы
+/// * `buf`        – изменяемый буфер RGBA (длина = width * height * 4)
+/// * `width`      – ширина в пикселях
+/// * `height`     – высота в пикселях
+/// * `tiles_x`    – количество плиток по горизонтали (> 0, не больше ширины)
+/// * `tiles_y`    – количество плиток по вертикали (> 0, не больше высоты)
+/// * `clip_limit` – ограничение высоты корзины гистограммы относительно среднего (> 0)
+///
+pub fn clahe(
+    buf: &mut [u8],
+    width: usize,
+    height: usize,
+    tiles_x: usize,
+    tiles_y: usize,
+    clip_limit: f64,
+) -> Result<(), Error> {
+    if tiles_x == 0 || tiles_y == 0 {
+        return Err(Error::ErrorValue("Tiles count cannot be 0".to_string()));
+    }
+    if clip_limit <= 0.0 {
+        return Err(Error::ErrorValue("Clip limit must be greater than 0".to_string()));
+    }
+    let tile_width = width.div_ceil(tiles_x.min(width));
+    let tile_height = height.div_ceil(tiles_y.min(height));
+    let tiles_x = width.div_ceil(tile_width);
+    let tiles_y = height.div_ceil(tile_height);
+    let luma: Vec<u8> = buf.chunks_exact(BYTE_PER_PIXEL).map(luminance).collect();
+    let mut mappings = Vec::with_capacity(tiles_x * tiles_y);
+    for tile_y in 0..tiles_y {
+        for tile_x in 0..tiles_x {
+            let mut histogram = [0u32; BINS];
+            let mut pixels = 0;
+            for y in tile_y * tile_height..((tile_y + 1) * tile_height).min(height) {
+                for x in tile_x * tile_width..((tile_x + 1) * tile_width).min(width) {
+                    histogram[luma[y * width + x] as usize] += 1;
+                    pixels += 1;
+                }
+            }
+            mappings.push(tile_mapping(&mut histogram, pixels, clip_limit));
+        }
+    }
+    for y in 0..height {
+        let (top, bottom, weight_y) = tile_axis(y, tile_height, tiles_y);
+        for x in 0..width {
+            let (left, right, weight_x) = tile_axis(x, tile_width, tiles_x);
+            let value = luma[y * width + x] as usize;
+            let map = |tile_x: usize, tile_y: usize| mappings[tile_y * tiles_x + tile_x][value] as f64;
+            let upper = map(left, top) * (1.0 - weight_x) + map(right, top) * weight_x;
+            let lower = map(left, bottom) * (1.0 - weight_x) + map(right, bottom) * weight_x;
+            let mapped = upper * (1.0 - weight_y) + lower * weight_y;
+            let index = (y * width + x) * BYTE_PER_PIXEL;
+            for channel in 0..3 {
+                let scaled = if value == 0 {
+                    mapped
+                } else {
+                    buf[index + channel] as f64 * mapped / value as f64
+                };
+                buf[index + channel] = scaled.round().clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// ```rust
+///
+///  Трансформация RGBA буффера локальным повышением контраста
+///
+///
+///  # Параметры
+///   - `width` (`c_uint`):  ширина изображения в пикселях
+///   - `height` (`c_uint`): высота изображения в пикселях
+///   - `rgba_data` (`*mut u8`): Мутабельный сырой указатель на RGBA буфер. Размер буфера `width * height * 4` bytes.
+///   - `params` (`*const c_char`): Сырой указатель на нуль терменированноу строку  конфигурации JSON-формата.
+///
+///  # Параметры конфигурации (JSON формат)
+///   - `log_level` (optional, string): уровень логирования ("Debug", "Info").
+///   - `log_tz` (optional, string): часовой пояс меток времени лога ("local", "utc"), по умолчанию "local"
+///   - `tiles_x` (required, integer): количество плиток по горизонтали. Должно быть больше 0
+///   - `tiles_y` (required, integer): количество плиток по вертикали. Должно быть больше 0
+///   - `clip_limit` (required, float): ограничение контраста (кратно средней высоте корзины гистограммы). Должно быть больше 0
+///
+///  # Пример JSON:
+///  ```json
+///  {
+///     "log_level": "Debug",
+///     "tiles_x": 8,
+///     "tiles_y": 8,
+///     "clip_limit": 2.0
+///  }
+///  ```
+///
+///
+///   # Error Handling
+///   - Ошибки фиксируются в логе
+///   - Поля конфигурации, которые плагин не использует, фиксируются в логе предупреждением
+///
+///
+///   # Пример С
+///  ```c
+///  // In C code
+///   unsigned int width = 1920;
+///   unsigned int height = 1080;
+///   unsigned char *image_data = ...; // RGBA buffer, allocated elsewhere
+///   const char *config = "{\"tiles_x\": 8, \"tiles_y\": 8, \"clip_limit\": 2.0}";
+///   process_image(width, height, image_data, config);
+///   ```
+/// # Safety
+///  Данная функция  помечена `unsafe`:
+///   - Работа напрямую с сырыми указателями (`rgba_data`, `params`) предстаялет external C code
+///   - Использования недопустимого указателя или парметров width, height undefined behavior.
+/// ```
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn process_image(
+    width: c_uint,
+    height: c_uint,
+    rgba_data: *mut u8,
+    params: *const c_char,
+) {
+    let file = PKG_NAME.to_owned() + ".log";
+    if let Err(e) = setup_logger_once(LevelFilter::Debug, &file) {
+        match e {
+            Error::LoggerInitError(_) => {
+                log::warn!("Logger init error: {}", e);
+            }
+            _ => {
+                log::error!("Logger init error: {}", e);
+                return;
+            }
+        }
+    }
+    log::info!("Start plugin {}", &file);
+    if params.is_null() {
+        log::error!("Pointer params is_null");
+        return;
+    }
+    let config = unsafe { CStr::from_ptr(params) };
+    let params_config = match config.to_str() {
+        Ok(config) => {
+            warn_ignored_fields(config, KNOWN_FIELDS);
+            let config: Result<ConfigReader<ConfigTransform>, Error> =
+                ConfigReader::try_from(config);
+            if let Ok(config) = config {
+                config
+            } else {
+                log::error!("Error converting config to string");
+                return;
+            }
+        }
+        _ => {
+            log::error!("Invalid config file");
+            return;
+        }
+    };
+    // Уровень из конфигурации действует только в потоке вызова до его завершения
+    let _log_level = params_config
+        .config
+        .log_level
+        .as_deref()
+        .map(|log_level| set_call_level(get_log_level(log_level)));
+    if let Some(log_tz) = params_config.config.log_tz {
+        set_log_timezone(log_tz);
+    }
+    if rgba_data.is_null() {
+        log::error!("Null pointer rgba_data");
+        return;
+    }
+    if width == 0 {
+        log::error!("width cannot be 0");
+        return;
+    }
+    if height == 0 {
+        log::error!("height cannot be 0");
+        return;
+    }
+    let len_in_pixel = match rgba_len(width, height) {
+        Ok(len) => len,
+        Err(e) => {
+            log::error!("Length calculation failed: {}", e);
+            return;
+        }
+    };
+    let height: usize = match height.try_into() {
+        Ok(h) => h,
+        Err(_) => {
+            log::error!("Height conversion failed");
+            return;
+        }
+    };
+    let width: usize = match width.try_into() {
+        Ok(w) => w,
+        Err(_) => {
+            log::error!("Width conversion failed");
+            return;
+        }
+    };
+    log::info!("Start converting image");
+    let buf = unsafe { slice::from_raw_parts_mut(rgba_data, len_in_pixel) };
+    let config = &params_config.config;
+    if let Err(e) = clahe(buf, width, height, config.tiles_x, config.tiles_y, config.clip_limit) {
+        log::error!("CLAHE error: {}", e);
+        return;
+    }
+    log::info!("Finish converting image");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    const SIZE: usize = 32;
+
+    /// Низкоконтрастный горизонтальный градиент 100..=115
+    fn gradient() -> Vec<u8> {
+        let mut buf = vec![255; SIZE * SIZE * BYTE_PER_PIXEL];
+        for (index, pixel) in buf.chunks_exact_mut(BYTE_PER_PIXEL).enumerate() {
+            let value = 100 + (index % SIZE / 2) as u8;
+            pixel[..3].fill(value);
+        }
+        buf
+    }
+
+    fn luminance_range(buf: &[u8]) -> u8 {
+        let luma: Vec<u8> = buf.chunks_exact(BYTE_PER_PIXEL).map(luminance).collect();
+        luma.iter().max().unwrap() - luma.iter().min().unwrap()
+    }
+
+    #[test]
+    fn test_clahe_increases_contrast_without_seams() {
+        let mut buf = gradient();
+        let json = r#"{"tiles_x": 2, "tiles_y": 2, "clip_limit": 4.0}"#;
+        let params_cstring = CString::new(json).unwrap();
+        unsafe { process_image(SIZE as c_uint, SIZE as c_uint, buf.as_mut_ptr(), params_cstring.as_ptr()) };
+        assert!(luminance_range(&buf) > luminance_range(&gradient()));
+        let red = |x: usize, y: usize| buf[(y * SIZE + x) * BYTE_PER_PIXEL] as i32;
+        let mut seam = 0;
+        let mut inner = 0;
+        for y in 0..SIZE {
+            for x in 0..SIZE - 1 {
+                let step = (red(x + 1, y) - red(x, y)).abs();
+                if x + 1 == SIZE / 2 {
+                    seam = seam.max(step);
+                } else {
+                    inner = inner.max(step);
+                }
+            }
+        }
+        assert!(seam <= inner, "seam {} inner {}", seam, inner);
+        assert!(buf.chunks_exact(BYTE_PER_PIXEL).all(|pixel| pixel[3] == 255));
+    }
+
+    #[test]
+    fn test_clahe_invalid_config() {
+        let mut buf = gradient();
+        assert!(clahe(&mut buf, SIZE, SIZE, 0, 2, 2.0).is_err());
+        assert!(clahe(&mut buf, SIZE, SIZE, 2, 2, 0.0).is_err());
+    }
+}
@@ -0,0 +1,330 @@
+#![warn(missing_docs)]
+//! Модуль для реализации обработки изображения - симуляция нарушений цветового зрения
+//!
+//! Предоставляет функциональность симуляции протанопии, дейтеранопии и тританопии
+//! через преобразование RGB -> LMS -> симуляция -> RGB (матрицы Viénot, Brettel, Mollon 1999)
+
+use log::LevelFilter;
+use plugins_support::logger::{
+    LogTimezone, get_log_level, set_call_level, set_log_timezone, setup_logger_once,
+};
+use plugins_support::config_parse::warn_ignored_fields;
+use plugins_support::image_size::rgba_len;
+use plugins_support::region::{REGIONS_FIELD, Region, resolve_regions};
+use plugins_support::{config_parse::ConfigReader, error::Error};
+use serde::Deserialize;
+use std::ffi::{CStr, c_char, c_uint};
+use std::slice;
+
+const PKG_NAME: &str = env!("CARGO_PKG_NAME");
+const BYTE_PER_PIXEL: usize = 4;
+
+plugins_support::export_warning_count!();
+plugins_support::export_abi_probe!();
+
+/// Поля конфигурации, которые использует плагин
+const KNOWN_FIELDS: &[&str] = &["type", "severity", REGIONS_FIELD];
+
+/// Матрица перевода RGB в пространство LMS
+const RGB_TO_LMS: [[f64; 3]; 3] = [
+    [17.8824, 43.5161, 4.11935],
+    [3.45565, 27.1554, 3.86714],
+    [0.0299566, 0.184309, 1.46709],
+];
+
+/// Обратная матрица перевода LMS в RGB
+const LMS_TO_RGB: [[f64; 3]; 3] = [
+    [0.0809444479, -0.130504409, 0.116721066],
+    [-0.0102485335, 0.0540193266, -0.113614708],
+    [-0.000365296938, -0.00412161469, 0.693511405],
+];
+
+/// Протанопия: отсутствуют L-колбочки
+const PROTAN: [[f64; 3]; 3] = [[0.0, 2.02344, -2.52581], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+/// Дейтеранопия: отсутствуют M-колбочки
+const DEUTAN: [[f64; 3]; 3] = [[1.0, 0.0, 0.0], [0.494207, 0.0, 1.24827], [0.0, 0.0, 1.0]];
+
+/// Тританопия: отсутствуют S-колбочки
+const TRITAN: [[f64; 3]; 3] = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [-0.395913, 0.801109, 0.0]];
+
+/// Тип нарушения цветового зрения
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Deficiency {
+    /// Протанопия
+    Protan,
+    /// Дейтеранопия
+    Deutan,
+    /// Тританопия
+    Tritan,
+}
+
+impl Deficiency {
+    fn matrix(self) -> &'static [[f64; 3]; 3] {
+        match self {
+            Deficiency::Protan => &PROTAN,
+            Deficiency::Deutan => &DEUTAN,
+            Deficiency::Tritan => &TRITAN,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct ConfigTransform {
+    #[serde(rename = "type")]
+    deficiency: Deficiency,
+    severity: Option<f64>,
+    regions: Option<Vec<Region>>,
+    log_level: Option<String>,
+    log_tz: Option<LogTimezone>,
+}
+
+fn multiply(matrix: &[[f64; 3]; 3], vector: [f64; 3]) -> [f64; 3] {
+    [0, 1, 2].map(|row| {
+        matrix[row][0] * vector[0] + matrix[row][1] * vector[1] + matrix[row][2] * vector[2]
+    })
+}
+
+/// Симуляция нарушения цветового зрения для одного пикселя RGBA
+///
+/// # Аргументы
+/// * `pixel`      – изменяемый срез пикселя RGBA (4 байта), альфа-канал не изменяется
+/// * `deficiency` – тип нарушения
+/// * `severity`   – степень (0..=1), доля смешивания исходного цвета с симулированным
+///
+pub fn simulate_pixel(pixel: &mut [u8], deficiency: Deficiency, severity: f64) {
+    let rgb = [pixel[0], pixel[1], pixel[2]].map(|c| c as f64 / 255.0);
+    let lms = multiply(&RGB_TO_LMS, rgb);
+    let simulated = multiply(&LMS_TO_RGB, multiply(deficiency.matrix(), lms));
+    for channel in 0..3 {
+        let value = rgb[channel] + (simulated[channel] - rgb[channel]) * severity;
+        pixel[channel] = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    }
+}
+
+/// ```rust
+///
+///  Трансформация RGBA буффера симуляцией нарушения цветового зрения
+///
+///
+///  # Параметры
+///   - `width` (`c_uint`):  ширина изображения в пикселях
+///   - `height` (`c_uint`): высота изображения в пикселях
+///   - `rgba_data` (`*mut u8`): Мутабельный сырой указатель на RGBA буфер. Размер буфера `width * height * 4` bytes.
+///   - `params` (`*const c_char`): Сырой указатель на нуль терменированноу строку  конфигурации JSON-формата.
+///
+///  # Параметры конфигурации (JSON формат)
+///   - `log_level` (optional, string): уровень логирования ("Debug", "Info").
+///   - `log_tz` (optional, string): часовой пояс меток времени лога ("local", "utc"), по умолчанию "local"
+///   - `type` (required, string): тип нарушения ("protan", "deutan", "tritan")
+///   - `severity` (optional, float): степень нарушения 0..=1, по умолчанию 1
+///   - `regions` (optional, array): Области `{"x", "y", "width", "height"}`, по умолчанию все
+///     изображение. Области обрабатываются по очереди, перекрывающаяся часть преобразуется
+///     несколько раз. Область нулевой площади пропускается, выходящая за край изображения
+///     обрезается по краю
+///
+///  # Пример JSON:
+///  ```json
+///  {
+///     "log_level": "Debug",
+///     "type": "deutan",
+///     "severity": 0.8
+///  }
+///  ```
+///
+///
+///   # Error Handling
+///   - Ошибки фиксируются в логе
+///   - Поля конфигурации, которые плагин не использует, фиксируются в логе предупреждением
+///
+///
+///   # Пример С
+///  ```c
+///  // In C code
+///   unsigned int width = 1920;
+///   unsigned int height = 1080;
+///   unsigned char *image_data = ...; // RGBA buffer, allocated elsewhere
+///   const char *config = "{\"type\": \"protan\"}";
+///   process_image(width, height, image_data, config);
+///   ```
+/// # Safety
+///  Данная функция  помечена `unsafe`:
+///   - Работа напрямую с сырыми указателями (`rgba_data`, `params`) предстаялет external C code
+///   - Использования недопустимого указателя или парметров width, height undefined behavior.
+/// ```
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn process_image(
+    width: c_uint,
+    height: c_uint,
+    rgba_data: *mut u8,
+    params: *const c_char,
+) {
+    let file = PKG_NAME.to_owned() + ".log";
+    if let Err(e) = setup_logger_once(LevelFilter::Debug, &file) {
+        match e {
+            Error::LoggerInitError(_) => {
+                log::warn!("Logger init error: {}", e);
+            }
+            _ => {
+                log::error!("Logger init error: {}", e);
+                return;
+            }
+        }
+    }
+    log::info!("Start plugin {}", &file);
+    if params.is_null() {
+        log::error!("Pointer params is_null");
+        return;
+    }
+    let config = unsafe { CStr::from_ptr(params) };
+    let params_config = match config.to_str() {
+        Ok(config) => {
+            warn_ignored_fields(config, KNOWN_FIELDS);
+            let config: Result<ConfigReader<ConfigTransform>, Error> =
+                ConfigReader::try_from(config);
+            if let Ok(config) = config {
+                config
+            } else {
+                log::error!("Error converting config to string");
+                return;
+            }
+        }
+        _ => {
+            log::error!("Invalid config file");
+            return;
+        }
+    };
+    // Уровень из конфигурации действует только в потоке вызова до его завершения
+    let _log_level = params_config
+        .config
+        .log_level
+        .as_deref()
+        .map(|log_level| set_call_level(get_log_level(log_level)));
+    if let Some(log_tz) = params_config.config.log_tz {
+        set_log_timezone(log_tz);
+    }
+    if rgba_data.is_null() {
+        log::error!("Null pointer rgba_data");
+        return;
+    }
+    if width == 0 {
+        log::error!("width cannot be 0");
+        return;
+    }
+    if height == 0 {
+        log::error!("height cannot be 0");
+        return;
+    }
+    let severity = params_config.config.severity.unwrap_or(1.0);
+    if !(0.0..=1.0).contains(&severity) {
+        log::error!("Severity must be in 0..=1");
+        return;
+    }
+    let len_in_pixel = match rgba_len(width, height) {
+        Ok(len) => len,
+        Err(e) => {
+            log::error!("Length calculation failed: {}", e);
+            return;
+        }
+    };
+    let regions = match resolve_regions(
+        params_config.config.regions.as_deref(),
+        width as usize,
+        height as usize,
+    ) {
+        Ok(regions) => regions,
+        Err(e) => {
+            log::error!("Invalid regions: {}", e);
+            return;
+        }
+    };
+    log::info!(
+        "Simulate {:?} with severity {}",
+        params_config.config.deficiency,
+        severity
+    );
+    let buf = unsafe { slice::from_raw_parts_mut(rgba_data, len_in_pixel) };
+    for region in &regions {
+        for index in region.pixels(width as usize) {
+            let pixel = &mut buf[index * BYTE_PER_PIXEL..(index + 1) * BYTE_PER_PIXEL];
+            simulate_pixel(pixel, params_config.config.deficiency, severity);
+        }
+    }
+    log::info!("Finish converting image");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    #[test]
+    fn test_deutan_red() {
+        let mut buf = vec![255, 0, 0, 255];
+        let json = r#"{"type": "deutan"}"#;
+        let params_cstring = CString::new(json).unwrap();
+        unsafe { process_image(1, 1, buf.as_mut_ptr(), params_cstring.as_ptr()) };
+        assert_eq!(buf, vec![75, 75, 0, 255]);
+    }
+
+    fn run(width: usize, height: usize, json: &str) -> (Vec<u8>, Vec<u8>) {
+        let source: Vec<u8> = (0..width * height).flat_map(|i| [255, (i * 16) as u8, 0, 255]).collect();
+        let mut buf = source.clone();
+        let params_cstring = CString::new(json).unwrap();
+        unsafe { process_image(width as c_uint, height as c_uint, buf.as_mut_ptr(), params_cstring.as_ptr()) };
+        (source, buf)
+    }
+
+    fn changed_pixels(source: &[u8], buf: &[u8]) -> Vec<usize> {
+        let pixels = source.chunks_exact(BYTE_PER_PIXEL).zip(buf.chunks_exact(BYTE_PER_PIXEL));
+        pixels.enumerate().filter(|(_, (a, b))| a != b).map(|(i, _)| i).collect()
+    }
+
+    #[test]
+    fn test_regions_only_changed() {
+        let json = r#"{"type": "deutan", "regions": [
+            {"x": 0, "y": 0, "width": 1, "height": 1},
+            {"x": 2, "y": 1, "width": 1, "height": 1}
+        ]}"#;
+        let (source, buf) = run(3, 2, json);
+        assert_eq!(changed_pixels(&source, &buf), vec![0, 5]);
+    }
+
+    #[test]
+    fn test_zero_area_region_unchanged() {
+        let json = r#"{"type": "deutan", "regions": [{"x": 1, "y": 0, "width": 0, "height": 2}]}"#;
+        let (source, buf) = run(3, 2, json);
+        assert_eq!(buf, source);
+    }
+
+    #[test]
+    fn test_over_wide_region_clamped() {
+        let json = r#"{"type": "deutan", "regions": [{"x": 1, "y": 1, "width": 100, "height": 100}]}"#;
+        let (source, buf) = run(3, 2, json);
+        assert_eq!(changed_pixels(&source, &buf), vec![4, 5]);
+    }
+
+    #[test]
+    fn test_region_outside_rejected() {
+        let json = r#"{"type": "deutan", "regions": [{"x": 3, "y": 0, "width": 1, "height": 1}]}"#;
+        let (source, buf) = run(3, 2, json);
+        assert_eq!(buf, source);
+    }
+
+    #[test]
+    fn test_severity_zero_unchanged() {
+        let mut pixel = [200, 30, 90, 128];
+        simulate_pixel(&mut pixel, Deficiency::Protan, 0.0);
+        assert_eq!(pixel, [200, 30, 90, 128]);
+    }
+
+    #[test]
+    fn test_white_preserved() {
+        for deficiency in [Deficiency::Protan, Deficiency::Deutan, Deficiency::Tritan] {
+            let mut pixel = [255, 255, 255, 255];
+            simulate_pixel(&mut pixel, deficiency, 1.0);
+            assert!(pixel.iter().all(|&c| c >= 253));
+        }
+    }
+}
@@ -0,0 +1,376 @@
+#![warn(missing_docs)]
+//! Модуль для реализации обработки изображения - автоматическое выравнивание наклона
+//!
+//! Предоставляет функциональность определения угла наклона сканированного документа
+//! по профилю проекций граничных пикселей и поворота изображения для его выравнивания
+//! в пределах исходного холста
+
+use log::LevelFilter;
+use plugins_support::config_parse::{check_range, warn_ignored_fields};
+use plugins_support::geometry::Geometry;
+use plugins_support::logger::{
+    LogTimezone, get_log_level, set_call_level, set_log_timezone, setup_logger_once,
+};
+use plugins_support::pixels::pixels_mut;
+use plugins_support::sampler::{Interpolation, sample};
+use plugins_support::{config_parse::ConfigReader, error::Error};
+use serde::Deserialize;
+use std::ffi::{CStr, c_char, c_uint};
+use std::slice;
+
+const PKG_NAME: &str = env!("CARGO_PKG_NAME");
+const BYTE_PER_PIXEL: usize = 4;
+
+plugins_support::export_warning_count!();
+plugins_support::export_abi_probe!();
+
+/// Поля конфигурации, которые использует плагин
+const KNOWN_FIELDS: &[&str] = &["max_angle", "fill"];
+
+/// Наибольшая граница поиска угла в градусах
+const MAX_SEARCH_ANGLE: f64 = 45.0;
+/// Шаг грубого поиска угла в градусах
+const COARSE_STEP: f64 = 0.5;
+/// Шаг точного поиска угла в градусах, он же наименьшая граница поиска
+const FINE_STEP: f64 = 0.05;
+/// Разность яркости соседних по вертикали пикселей, начиная с которой пиксель - граничный
+const EDGE_THRESHOLD: f64 = 48.0;
+/// Цвет заполнения углов по умолчанию: белый, как фон документа
+const DEFAULT_FILL: [u8; 4] = [255, 255, 255, 255];
+
+#[derive(Deserialize, Debug)]
+struct ConfigTransform {
+    max_angle: f64,
+    fill: Option<[u8; 4]>,
+    log_level: Option<String>,
+    log_tz: Option<LogTimezone>,
+}
+
+/// Яркость пикселя RGB
+fn luma(pixel: &[u8]) -> f64 {
+    0.299 * pixel[0] as f64 + 0.587 * pixel[1] as f64 + 0.114 * pixel[2] as f64
+}
+
+///
+/// Граничные пиксели: перепад яркости с соседом снизу больше [`EDGE_THRESHOLD`]
+///
+/// # Возращает
+/// Координаты точек между пикселем и его соседом снизу
+///
+fn edge_points(buf: &[u8], width: usize, height: usize) -> Vec<(f64, f64)> {
+    let row_len = width * BYTE_PER_PIXEL;
+    let mut points = Vec::new();
+    for y in 0..height.saturating_sub(1) {
+        for x in 0..width {
+            let index = y * row_len + x * BYTE_PER_PIXEL;
+            let below = index + row_len;
+            let difference = luma(&buf[index..index + 3]) - luma(&buf[below..below + 3]);
+            if difference.abs() > EDGE_THRESHOLD {
+                points.push((x as f64, y as f64 + 0.5));
+            }
+        }
+    }
+    points
+}
+
+///
+/// Оценка угла по профилю проекций
+///
+/// Точки проецируются на нормаль к направлению `angle` и раскладываются по полосам
+/// шириной в пиксель. Когда направление совпадает со строками документа, граничные
+/// точки собираются в немногие полосы и сумма квадратов их заполнения максимальна
+///
+fn profile_score(points: &[(f64, f64)], center: (f64, f64), radius: f64, angle: f64) -> f64 {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let mut bins = vec![0u32; 2 * radius.ceil() as usize + 3];
+    for &(x, y) in points {
+        let offset = (y - center.1) * cos - (x - center.0) * sin;
+        bins[(offset + radius + 1.0).round() as usize] += 1;
+    }
+    bins.iter().map(|&count| (count as f64).powi(2)).sum()
+}
+
+/// Угол из `angles` с наибольшей оценкой профиля
+fn best_angle(
+    points: &[(f64, f64)],
+    center: (f64, f64),
+    radius: f64,
+    angles: impl Iterator<Item = f64>,
+) -> f64 {
+    angles
+        .map(|angle| (angle, profile_score(points, center, radius, angle)))
+        // При равной оценке предпочитается угол ближе к 0
+        .max_by(|a, b| a.1.total_cmp(&b.1).then(b.0.abs().total_cmp(&a.0.abs())))
+        .map_or(0.0, |(angle, _)| angle)
+}
+
+/// Углы от `from` до `to` с шагом `step`
+fn angle_range(from: f64, to: f64, step: f64) -> impl Iterator<Item = f64> {
+    let count = ((to - from) / step).round() as i64;
+    (0..=count).map(move |index| from + index as f64 * step)
+}
+
+/// Определение угла наклона изображения
+///
+/// # Аргументы
+/// * `buf`    – буфер RGBA (длина = width * height * 4)
+/// * `width`  – ширина в пикселях
+/// * `height` – высота в пикселях
+/// * `max_angle` – граница поиска в градусах, угол ищется в `-max_angle..=max_angle`
+///
+/// Грубый поиск с шагом 0.5° по всему диапазону уточняется поиском с шагом 0.05°
+/// вокруг лучшего угла. Используются горизонтальные границы (перепад яркости по вертикали),
+/// поэтому метод рассчитан на строки текста и линии, близкие к горизонтальным
+///
+/// # Возращает
+/// Угол наклона строк в градусах (положительный - строки опускаются слева направо),
+/// `None`, если граничных пикселей нет
+///
+pub fn detect_skew(buf: &[u8], width: usize, height: usize, max_angle: f64) -> Option<f64> {
+    let points = edge_points(buf, width, height);
+    if points.is_empty() {
+        return None;
+    }
+    log::debug!("Skew search over {} edge pixels", points.len());
+    let center = ((width as f64 - 1.0) / 2.0, (height as f64 - 1.0) / 2.0);
+    let radius = center.0.hypot(center.1) + 1.0;
+    let coarse = best_angle(&points, center, radius, angle_range(-max_angle, max_angle, COARSE_STEP));
+    let (from, to) = ((coarse - COARSE_STEP).max(-max_angle), (coarse + COARSE_STEP).min(max_angle));
+    Some(best_angle(&points, center, radius, angle_range(from, to, FINE_STEP)))
+}
+
+/// Поворот изображения вокруг центра для выравнивания строк с наклоном `angle`
+///
+/// # Аргументы
+/// * `buf`    – изменяемый буфер RGBA (длина = width * height * 4)
+/// * `width`  – ширина в пикселях
+/// * `height` – высота в пикселях
+/// * `angle`  – наклон строк в градусах, как его возвращает [`detect_skew`]
+/// * `fill`   – цвет RGBA углов, в которые не попадает исходное изображение
+///
+/// Размер холста не меняется: выходящие за него части исходного изображения обрезаются
+///
+pub fn rotate(buf: &mut [u8], width: usize, height: usize, angle: f64, fill: [u8; 4]) -> Result<(), Error> {
+    let source = buf.to_vec();
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let center = ((width as f64 - 1.0) / 2.0, (height as f64 - 1.0) / 2.0);
+    let (max_x, max_y) = ((width - 1) as f64, (height - 1) as f64);
+    for (x, y, pixel) in pixels_mut(buf, width, height, BYTE_PER_PIXEL)? {
+        let (dx, dy) = (x as f64 - center.0, y as f64 - center.1);
+        let source_x = center.0 + cos * dx - sin * dy;
+        let source_y = center.1 + sin * dx + cos * dy;
+        let outside = source_x < -0.5 || source_y < -0.5 || source_x > max_x + 0.5 || source_y > max_y + 0.5;
+        if outside {
+            pixel.copy_from_slice(&fill);
+        } else {
+            pixel.copy_from_slice(&sample(&source, width, height, source_x, source_y, Interpolation::Bilinear));
+        }
+    }
+    Ok(())
+}
+
+/// Выравнивание наклона: [`detect_skew`] и [`rotate`]
+///
+/// # Аргументы
+/// * `buf`    – изменяемый буфер RGBA (длина = width * height * 4)
+/// * `width`  – ширина в пикселях
+/// * `height` – высота в пикселях
+/// * `max_angle` – граница поиска угла в градусах (0.05..=45)
+/// * `fill`   – цвет RGBA углов после поворота
+///
+/// # Возращает
+/// Найденный угол в градусах; изображение без граничных пикселей или с нулевым
+/// наклоном не изменяется
+///
+pub fn deskew(buf: &mut [u8], width: usize, height: usize, max_angle: f64, fill: [u8; 4]) -> Result<f64, Error> {
+    let max_angle = check_range("max_angle", max_angle, FINE_STEP..=MAX_SEARCH_ANGLE, false)?;
+    let Some(angle) = detect_skew(buf, width, height, max_angle) else {
+        log::info!("No edges found, image left unchanged");
+        return Ok(0.0);
+    };
+    log::info!("Detected skew {:.2} degrees", angle);
+    if angle.abs() >= FINE_STEP / 2.0 {
+        rotate(buf, width, height, angle, fill)?;
+    }
+    Ok(angle)
+}
+
+/// ```rust
+///
+///  Выравнивание наклона RGBA буффера
+///
+///
+///  # Параметры
+///   - `width` (`c_uint`):  ширина изображения в пикселях
+///   - `height` (`c_uint`): высота изображения в пикселях
+///   - `rgba_data` (`*mut u8`): Мутабельный сырой указатель на RGBA буфер. Размер буфера `width * height * 4` bytes.
+///   - `params` (`*const c_char`): Сырой указатель на нуль терменированноу строку  конфигурации JSON-формата.
+///
+///  # Параметры конфигурации (JSON формат)
+///   - `log_level` (optional, string): уровень логирования ("Debug", "Info").
+///   - `log_tz` (optional, string): часовой пояс меток времени лога ("local", "utc"), по умолчанию "local"
+///   - `max_angle` (required, float): граница поиска угла наклона в градусах (0.05..=45)
+///   - `fill` (optional, array of 4 integers): цвет RGBA углов после поворота,
+///     по умолчанию `[255, 255, 255, 255]`
+///
+///  # Пример JSON:
+///  ```json
+///  {
+///     "log_level": "Debug",
+///     "max_angle": 10.0,
+///     "fill": [255, 255, 255, 255]
+///  }
+///  ```
+///
+///
+///   # Error Handling
+///   - Ошибки фиксируются в логе
+///   - Поля конфигурации, которые плагин не использует, фиксируются в логе предупреждением
+///
+///
+///   # Пример С
+///  ```c
+///  // In C code
+///   unsigned int width = 1920;
+///   unsigned int height = 1080;
+///   unsigned char *image_data = ...; // RGBA buffer, allocated elsewhere
+///   const char *config = "{\"max_angle\": 10.0}";
+///   process_image(width, height, image_data, config);
+///   ```
+/// # Safety
+///  Данная функция  помечена `unsafe`:
+///   - Работа напрямую с сырыми указателями (`rgba_data`, `params`) предстаялет external C code
+///   - Использования недопустимого указателя или парметров width, height undefined behavior.
+/// ```
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn process_image(
+    width: c_uint,
+    height: c_uint,
+    rgba_data: *mut u8,
+    params: *const c_char,
+) {
+    let file = PKG_NAME.to_owned() + ".log";
+    if let Err(e) = setup_logger_once(LevelFilter::Debug, &file) {
+        match e {
+            Error::LoggerInitError(_) => {
+                log::warn!("Logger init error: {}", e);
+            }
+            _ => {
+                log::error!("Logger init error: {}", e);
+                return;
+            }
+        }
+    }
+    log::info!("Start plugin {}", &file);
+    if params.is_null() {
+        log::error!("Pointer params is_null");
+        return;
+    }
+    let config = unsafe { CStr::from_ptr(params) };
+    let params_config = match config.to_str() {
+        Ok(config) => {
+            warn_ignored_fields(config, KNOWN_FIELDS);
+            let config: Result<ConfigReader<ConfigTransform>, Error> =
+                ConfigReader::try_from(config);
+            if let Ok(config) = config {
+                config
+            } else {
+                log::error!("Error converting config to string");
+                return;
+            }
+        }
+        _ => {
+            log::error!("Invalid config file");
+            return;
+        }
+    };
+    // Уровень из конфигурации действует только в потоке вызова до его завершения
+    let _log_level = params_config
+        .config
+        .log_level
+        .as_deref()
+        .map(|log_level| set_call_level(get_log_level(log_level)));
+    if let Some(log_tz) = params_config.config.log_tz {
+        set_log_timezone(log_tz);
+    }
+    if rgba_data.is_null() {
+        log::error!("Null pointer rgba_data");
+        return;
+    }
+    let geometry = match Geometry::rgba(width, height) {
+        Ok(geometry) => geometry,
+        Err(e) => {
+            log::error!("Invalid image size: {}", e);
+            return;
+        }
+    };
+    log::info!("Start converting image");
+    let buf = unsafe { slice::from_raw_parts_mut(rgba_data, geometry.total_len()) };
+    let config = &params_config.config;
+    let fill = config.fill.unwrap_or(DEFAULT_FILL);
+    if let Err(e) = deskew(buf, geometry.width(), geometry.height(), config.max_angle, fill) {
+        log::error!("Deskew error: {}", e);
+        return;
+    }
+    log::info!("Finish converting image");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    const WIDTH: usize = 240;
+    const HEIGHT: usize = 180;
+    /// Допуск угла в градусах: полосы профиля шириной в пиксель различают углы
+    /// примерно до atan(1 / WIDTH) ≈ 0.24°
+    const TOLERANCE: f64 = 0.25;
+
+    /// Белая страница с черными горизонтальными полосами толщиной 4 пикселя через 12,
+    /// наклоненными на `angle` градусов вокруг центра
+    fn skewed_page(angle: f64) -> Vec<u8> {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let center = ((WIDTH - 1) as f64 / 2.0, (HEIGHT - 1) as f64 / 2.0);
+        let mut buf = Vec::with_capacity(WIDTH * HEIGHT * BYTE_PER_PIXEL);
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                let (dx, dy) = (x as f64 - center.0, y as f64 - center.1);
+                // Координата пикселя поперек строк до наклона
+                let line = (dy * cos - dx * sin + 1000.0).rem_euclid(12.0);
+                let value = if line < 4.0 { 0 } else { 255 };
+                buf.extend_from_slice(&[value, value, value, 255]);
+            }
+        }
+        buf
+    }
+
+    #[test]
+    fn test_detect_skew_synthetic() {
+        for skew in [3.0, -5.0, 0.0, 7.3] {
+            let angle = detect_skew(&skewed_page(skew), WIDTH, HEIGHT, 10.0).unwrap();
+            assert!((angle - skew).abs() <= TOLERANCE, "skew {} detected {}", skew, angle);
+        }
+    }
+
+    #[test]
+    fn test_deskew_straightens() {
+        let mut buf = skewed_page(4.0);
+        let params = CString::new(r#"{"max_angle": 10, "fill": [255, 0, 0, 255]}"#).unwrap();
+        unsafe { process_image(WIDTH as c_uint, HEIGHT as c_uint, buf.as_mut_ptr(), params.as_ptr()) };
+        // Углы, в которые не попадает исходное изображение, залиты цветом fill
+        assert_eq!(buf[..4], [255, 0, 0, 255]);
+        let angle = detect_skew(&buf, WIDTH, HEIGHT, 10.0).unwrap();
+        assert!(angle.abs() <= TOLERANCE, "residual skew {}", angle);
+    }
+
+    #[test]
+    fn test_deskew_unchanged() {
+        let mut blank = vec![255; WIDTH * HEIGHT * BYTE_PER_PIXEL];
+        assert_eq!(deskew(&mut blank, WIDTH, HEIGHT, 10.0, DEFAULT_FILL).unwrap(), 0.0);
+        assert!(blank.iter().all(|&value| value == 255));
+        let mut buf = skewed_page(4.0);
+        assert!(deskew(&mut buf, WIDTH, HEIGHT, 90.0, DEFAULT_FILL).is_err());
+        assert!(deskew(&mut buf, WIDTH, HEIGHT, 0.0, DEFAULT_FILL).is_err());
+        assert_eq!(buf, skewed_page(4.0));
+    }
+}
@@ -0,0 +1,281 @@
+#![warn(missing_docs)]
+//! Модуль для реализации обработки изображения - разница с эталонным изображением
+//!
+//! Предоставляет функциональность попиксельного сравнения изображения с эталоном:
+//! в буфер записывается модуль разности каналов, что позволяет увидеть, где расходятся
+//! результаты двух запусков обработки
+
+use log::LevelFilter;
+use plugins_support::config_parse::warn_ignored_fields;
+use plugins_support::image_size::rgba_len;
+use plugins_support::logger::{
+    LogTimezone, get_log_level, set_call_level, set_log_timezone, setup_logger_once,
+};
+use plugins_support::{config_parse::ConfigReader, error::Error};
+use serde::Deserialize;
+use std::ffi::{CStr, c_char, c_uint};
+use std::slice;
+
+const PKG_NAME: &str = env!("CARGO_PKG_NAME");
+const BYTE_PER_PIXEL: usize = 4;
+
+plugins_support::export_warning_count!();
+plugins_support::export_abi_probe!();
+
+/// Поля конфигурации, которые использует плагин
+const KNOWN_FIELDS: &[&str] = &["reference", "scale"];
+
+#[derive(Deserialize, Debug)]
+struct ConfigTransform {
+    reference: String,
+    scale: Option<f64>,
+    log_level: Option<String>,
+    log_tz: Option<LogTimezone>,
+}
+
+/// Разница буфера `buf` с эталонным буфером `reference`
+///
+/// # Аргументы
+/// * `buf`              – изменяемый буфер RGBA изображения
+/// * `width`            – ширина изображения
+/// * `height`           – высота изображения
+/// * `reference`        – буфер RGBA эталонного изображения
+/// * `reference_width`  – ширина эталонного изображения
+/// * `reference_height` – высота эталонного изображения
+/// * `scale`            – множитель разницы (> 0)
+///
+/// В каналы R, G, B записывается `|buf - reference| * scale` (с ограничением 255),
+/// альфа-канал устанавливается в 255, чтобы разница была видна
+///
+pub fn diff_buffers(
+    buf: &mut [u8],
+    width: usize,
+    height: usize,
+    reference: &[u8],
+    reference_width: usize,
+    reference_height: usize,
+    scale: f64,
+) -> Result<(), Error> {
+    if reference_width != width || reference_height != height {
+        return Err(Error::ErrorValue(format!(
+            "Reference image size {}x{} does not match {}x{}",
+            reference_width, reference_height, width, height
+        )));
+    }
+    if scale.is_nan() || scale <= 0.0 {
+        return Err(Error::ErrorValue("Scale must be greater than 0".to_string()));
+    }
+    for (pixel, reference) in buf
+        .chunks_exact_mut(BYTE_PER_PIXEL)
+        .zip(reference.chunks_exact(BYTE_PER_PIXEL))
+    {
+        for channel in 0..3 {
+            let diff = pixel[channel].abs_diff(reference[channel]) as f64 * scale;
+            pixel[channel] = diff.round().min(255.0) as u8;
+        }
+        pixel[3] = u8::MAX;
+    }
+    Ok(())
+}
+
+/// ```rust
+///
+///  Трансформация RGBA буффера в разницу с эталонным изображением
+///
+///
+///  # Параметры
+///   - `width` (`c_uint`):  ширина изображения в пикселях
+///   - `height` (`c_uint`): высота изображения в пикселях
+///   - `rgba_data` (`*mut u8`): Мутабельный сырой указатель на RGBA буфер. Размер буфера `width * height * 4` bytes.
+///   - `params` (`*const c_char`): Сырой указатель на нуль терменированноу строку  конфигурации JSON-формата.
+///
+///  # Параметры конфигурации (JSON формат)
+///   - `log_level` (optional, string): уровень логирования ("Debug", "Info").
+///   - `log_tz` (optional, string): часовой пояс меток времени лога ("local", "utc"), по умолчанию "local"
+///   - `reference` (required, string): путь к эталонному изображению, размеры должны совпадать
+///   - `scale` (optional, float): множитель разницы для наглядности (> 0), по умолчанию 1
+///
+///  # Пример JSON:
+///  ```json
+///  {
+///     "log_level": "Debug",
+///     "reference": "expected.png",
+///     "scale": 8.0
+///  }
+///  ```
+///
+///
+///   # Error Handling
+///   - Ошибки фиксируются в логе
+///   - Поля конфигурации, которые плагин не использует, фиксируются в логе предупреждением
+///
+///
+///   # Пример С
+///  ```c
+///  // In C code
+///   unsigned int width = 1920;
+///   unsigned int height = 1080;
+///   unsigned char *image_data = ...; // RGBA buffer, allocated elsewhere
+///   const char *config = "{\"reference\": \"expected.png\", \"scale\": 8.0}";
+///   process_image(width, height, image_data, config);
+///   ```
+/// # Safety
+///  Данная функция  помечена `unsafe`:
+///   - Работа напрямую с сырыми указателями (`rgba_data`, `params`) предстаялет external C code
+///   - Использования недопустимого указателя или парметров width, height undefined behavior.
+/// ```
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn process_image(
+    width: c_uint,
+    height: c_uint,
+    rgba_data: *mut u8,
+    params: *const c_char,
+) {
+    let file = PKG_NAME.to_owned() + ".log";
+    if let Err(e) = setup_logger_once(LevelFilter::Debug, &file) {
+        match e {
+            Error::LoggerInitError(_) => {
+                log::warn!("Logger init error: {}", e);
+            }
+            _ => {
+                log::error!("Logger init error: {}", e);
+                return;
+            }
+        }
+    }
+    log::info!("Start plugin {}", &file);
+    if params.is_null() {
+        log::error!("Pointer params is_null");
+        return;
+    }
+    let config = unsafe { CStr::from_ptr(params) };
+    let params_config = match config.to_str() {
+        Ok(config) => {
+            warn_ignored_fields(config, KNOWN_FIELDS);
+            let config: Result<ConfigReader<ConfigTransform>, Error> =
+                ConfigReader::try_from(config);
+            if let Ok(config) = config {
+                config
+            } else {
+                log::error!("Error converting config to string");
+                return;
+            }
+        }
+        _ => {
+            log::error!("Invalid config file");
+            return;
+        }
+    };
+    // Уровень из конфигурации действует только в потоке вызова до его завершения
+    let _log_level = params_config
+        .config
+        .log_level
+        .as_deref()
+        .map(|log_level| set_call_level(get_log_level(log_level)));
+    if let Some(log_tz) = params_config.config.log_tz {
+        set_log_timezone(log_tz);
+    }
+    if rgba_data.is_null() {
+        log::error!("Null pointer rgba_data");
+        return;
+    }
+    if width == 0 {
+        log::error!("width cannot be 0");
+        return;
+    }
+    if height == 0 {
+        log::error!("height cannot be 0");
+        return;
+    }
+    let len_in_pixel = match rgba_len(width, height) {
+        Ok(len) => len,
+        Err(e) => {
+            log::error!("Length calculation failed: {}", e);
+            return;
+        }
+    };
+    let height: usize = match height.try_into() {
+        Ok(h) => h,
+        Err(_) => {
+            log::error!("Height conversion failed");
+            return;
+        }
+    };
+    let width: usize = match width.try_into() {
+        Ok(w) => w,
+        Err(_) => {
+            log::error!("Width conversion failed");
+            return;
+        }
+    };
+    let reference = match image::open(&params_config.config.reference) {
+        Ok(reference) => reference.to_rgba8(),
+        Err(e) => {
+            log::error!(
+                "Could not open reference image {}: {}",
+                params_config.config.reference,
+                e
+            );
+            return;
+        }
+    };
+    let scale = params_config.config.scale.unwrap_or(1.0);
+    log::info!("Diff with {} scale {}", params_config.config.reference, scale);
+    let buf = unsafe { slice::from_raw_parts_mut(rgba_data, len_in_pixel) };
+    if let Err(e) = diff_buffers(
+        buf,
+        width,
+        height,
+        reference.as_raw(),
+        reference.width() as usize,
+        reference.height() as usize,
+        scale,
+    ) {
+        log::error!("Diff error: {}", e);
+        return;
+    }
+    log::info!("Finish converting image");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+    use std::ffi::CString;
+
+    fn gradient() -> RgbaImage {
+        RgbaImage::from_fn(4, 2, |x, y| image::Rgba([(x * 50) as u8, (y * 80) as u8, 30, 255]))
+    }
+
+    #[test]
+    fn test_diff_with_itself() {
+        let path = std::env::temp_dir().join("diff_plugin_self.png");
+        let source = gradient();
+        source.save(&path).unwrap();
+        let mut buf = source.to_vec();
+        let json = format!(r#"{{"reference": "{}", "scale": 4.0}}"#, path.display());
+        let params_cstring = CString::new(json).unwrap();
+        unsafe { process_image(4, 2, buf.as_mut_ptr(), params_cstring.as_ptr()) };
+        for pixel in buf.chunks_exact(4) {
+            assert_eq!(pixel, [0, 0, 0, 255]);
+        }
+    }
+
+    #[test]
+    fn test_diff_with_shifted_copy() {
+        let source = gradient();
+        let shifted = RgbaImage::from_fn(4, 2, |x, y| *source.get_pixel((x + 1) % 4, y));
+        let mut buf = source.to_vec();
+        diff_buffers(&mut buf, 4, 2, shifted.as_raw(), 4, 2, 2.0).unwrap();
+        assert!(buf.chunks_exact(4).any(|pixel| pixel[..3] != [0, 0, 0]));
+        // |0 - 50| * 2 = 100 в красном канале первого пикселя
+        assert_eq!(&buf[..4], [100, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_diff_size_mismatch() {
+        let mut buf = vec![0; 16];
+        let reference = vec![0; 4];
+        assert!(diff_buffers(&mut buf, 2, 2, &reference, 1, 1, 1.0).is_err());
+    }
+}
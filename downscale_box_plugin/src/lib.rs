@@ -0,0 +1,285 @@
+#![warn(missing_docs)]
+//! Модуль для реализации обработки изображения - пикселизация усреднением по площади
+//!
+//! Предоставляет функциональность уменьшения изображения в целое число раз усреднением
+//! блоков и обратного увеличения (ближайший сосед) до исходного размера
+
+use log::LevelFilter;
+use plugins_support::color::{linear_to_srgb, srgb_to_linear};
+use plugins_support::config_parse::warn_ignored_fields;
+use plugins_support::image_size::rgba_len;
+use plugins_support::logger::{
+    LogTimezone, get_log_level, set_call_level, set_log_timezone, setup_logger_once,
+};
+use plugins_support::{config_parse::ConfigReader, error::Error};
+use serde::Deserialize;
+use std::ffi::{CStr, c_char, c_uint};
+use std::slice;
+
+const PKG_NAME: &str = env!("CARGO_PKG_NAME");
+const BYTE_PER_PIXEL: usize = 4;
+
+plugins_support::export_warning_count!();
+plugins_support::export_abi_probe!();
+
+/// Поля конфигурации, которые использует плагин
+const KNOWN_FIELDS: &[&str] = &["factor", "linear_light"];
+
+#[derive(Deserialize, Debug)]
+struct ConfigTransform {
+    factor: usize,
+    linear_light: Option<bool>,
+    log_level: Option<String>,
+    log_tz: Option<LogTimezone>,
+}
+
+/// Уменьшение усреднением блоков `factor x factor` и увеличение обратно до исходного размера
+///
+/// # Аргументы
+/// * `buf`    – изменяемый буфер RGBA (длина = width * height * 4)
+/// * `width`  – ширина в пикселях
+/// * `height` – высота в пикселях
+/// * `factor` – коэффициент уменьшения (>= 2)
+/// * `linear_light` – усреднение каналов RGB в линейной яркости (sRGB -> linear -> sRGB)
+///
+/// Каждый блок заполняется средним значением своих пикселей по всем каналам.
+/// Блоки у правого и нижнего края, если размер не делится на `factor`, усредняются
+/// по имеющимся пикселям. Усреднение значений sRGB быстрее, но затемняет блоки
+/// с контрастными деталями, `linear_light` дает перцептивно корректную яркость
+///
+pub fn downscale_box(
+    buf: &mut [u8],
+    width: usize,
+    height: usize,
+    factor: usize,
+    linear_light: bool,
+) -> Result<(), Error> {
+    if factor < 2 {
+        return Err(Error::ErrorValue("Factor must be at least 2".to_string()));
+    }
+    for block_y in (0..height).step_by(factor) {
+        for block_x in (0..width).step_by(factor) {
+            let rows = block_y..(block_y + factor).min(height);
+            let columns = block_x..(block_x + factor).min(width);
+            let count = rows.len() * columns.len();
+            let mut sum = [0usize; BYTE_PER_PIXEL];
+            let mut linear_sum = [0.0f64; 3];
+            for y in rows.clone() {
+                for x in columns.clone() {
+                    let index = (y * width + x) * BYTE_PER_PIXEL;
+                    for (value, channel) in sum.iter_mut().zip(&buf[index..index + BYTE_PER_PIXEL]) {
+                        *value += *channel as usize;
+                    }
+                    if linear_light {
+                        for (value, channel) in linear_sum.iter_mut().zip(&buf[index..index + 3]) {
+                            *value += srgb_to_linear(*channel);
+                        }
+                    }
+                }
+            }
+            let mut average = sum.map(|value| ((value + count / 2) / count) as u8);
+            if linear_light {
+                for (channel, value) in average.iter_mut().zip(linear_sum) {
+                    *channel = linear_to_srgb(value / count as f64);
+                }
+            }
+            for y in rows.clone() {
+                for x in columns.clone() {
+                    let index = (y * width + x) * BYTE_PER_PIXEL;
+                    buf[index..index + BYTE_PER_PIXEL].copy_from_slice(&average);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// ```rust
+///
+///  Трансформация RGBA буффера пикселизацией с усреднением по площади
+///
+///
+///  # Параметры
+///   - `width` (`c_uint`):  ширина изображения в пикселях
+///   - `height` (`c_uint`): высота изображения в пикселях
+///   - `rgba_data` (`*mut u8`): Мутабельный сырой указатель на RGBA буфер. Размер буфера `width * height * 4` bytes.
+///   - `params` (`*const c_char`): Сырой указатель на нуль терменированноу строку  конфигурации JSON-формата.
+///
+///  # Параметры конфигурации (JSON формат)
+///   - `log_level` (optional, string): уровень логирования ("Debug", "Info").
+///   - `log_tz` (optional, string): часовой пояс меток времени лога ("local", "utc"), по умолчанию "local"
+///   - `factor` (required, integer): коэффициент уменьшения. Должен быть >= 2
+///   - `linear_light` (optional, bool): усреднение в линейной яркости (sRGB -> linear -> sRGB),
+///     по умолчанию false - усреднение значений sRGB
+///
+///  # Пример JSON:
+///  ```json
+///  {
+///     "log_level": "Debug",
+///     "factor": 8
+///  }
+///  ```
+///
+///
+///   # Error Handling
+///   - Ошибки фиксируются в логе
+///   - Поля конфигурации, которые плагин не использует, фиксируются в логе предупреждением
+///
+///
+///   # Пример С
+///  ```c
+///  // In C code
+///   unsigned int width = 1920;
+///   unsigned int height = 1080;
+///   unsigned char *image_data = ...; // RGBA buffer, allocated elsewhere
+///   const char *config = "{\"factor\": 8}";
+///   process_image(width, height, image_data, config);
+///   ```
+/// # Safety
+///  Данная функция  помечена `unsafe`:
+///   - Работа напрямую с сырыми указателями (`rgba_data`, `params`) предстаялет external C code
+///   - Использования недопустимого указателя или парметров width, height undefined behavior.
+/// ```
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn process_image(
+    width: c_uint,
+    height: c_uint,
+    rgba_data: *mut u8,
+    params: *const c_char,
+) {
+    let file = PKG_NAME.to_owned() + ".log";
+    if let Err(e) = setup_logger_once(LevelFilter::Debug, &file) {
+        match e {
+            Error::LoggerInitError(_) => {
+                log::warn!("Logger init error: {}", e);
+            }
+            _ => {
+                log::error!("Logger init error: {}", e);
+                return;
+            }
+        }
+    }
+    log::info!("Start plugin {}", &file);
+    if params.is_null() {
+        log::error!("Pointer params is_null");
+        return;
+    }
+    let config = unsafe { CStr::from_ptr(params) };
+    let params_config = match config.to_str() {
+        Ok(config) => {
+            warn_ignored_fields(config, KNOWN_FIELDS);
+            let config: Result<ConfigReader<ConfigTransform>, Error> =
+                ConfigReader::try_from(config);
+            if let Ok(config) = config {
+                config
+            } else {
+                log::error!("Error converting config to string");
+                return;
+            }
+        }
+        _ => {
+            log::error!("Invalid config file");
+            return;
+        }
+    };
+    // Уровень из конфигурации действует только в потоке вызова до его завершения
+    let _log_level = params_config
+        .config
+        .log_level
+        .as_deref()
+        .map(|log_level| set_call_level(get_log_level(log_level)));
+    if let Some(log_tz) = params_config.config.log_tz {
+        set_log_timezone(log_tz);
+    }
+    if rgba_data.is_null() {
+        log::error!("Null pointer rgba_data");
+        return;
+    }
+    if width == 0 {
+        log::error!("width cannot be 0");
+        return;
+    }
+    if height == 0 {
+        log::error!("height cannot be 0");
+        return;
+    }
+    let len_in_pixel = match rgba_len(width, height) {
+        Ok(len) => len,
+        Err(e) => {
+            log::error!("Length calculation failed: {}", e);
+            return;
+        }
+    };
+    let height: usize = match height.try_into() {
+        Ok(h) => h,
+        Err(_) => {
+            log::error!("Height conversion failed");
+            return;
+        }
+    };
+    let width: usize = match width.try_into() {
+        Ok(w) => w,
+        Err(_) => {
+            log::error!("Width conversion failed");
+            return;
+        }
+    };
+    log::info!("Start converting image");
+    let buf = unsafe { slice::from_raw_parts_mut(rgba_data, len_in_pixel) };
+    let linear_light = params_config.config.linear_light.unwrap_or(false);
+    if let Err(e) = downscale_box(buf, width, height, params_config.config.factor, linear_light) {
+        log::error!("Downscale error: {}", e);
+        return;
+    }
+    log::info!("Finish converting image");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    #[test]
+    fn test_downscale_box_factor_2() {
+        // 4x4, красный канал: номер пикселя * 10, остальные каналы постоянные
+        let mut buf: Vec<u8> = (0..16).flat_map(|pixel| [pixel * 10, 20, 30, 255]).collect();
+        let json = r#"{"factor": 2}"#;
+        let params_cstring = CString::new(json).unwrap();
+        unsafe { process_image(4, 4, buf.as_mut_ptr(), params_cstring.as_ptr()) };
+        // Блоки: (0, 1, 4, 5) -> 25, (2, 3, 6, 7) -> 45, (8, 9, 12, 13) -> 105, (10, 11, 14, 15) -> 125
+        let expected_red = [25, 25, 45, 45, 25, 25, 45, 45, 105, 105, 125, 125, 105, 105, 125, 125];
+        let expected: Vec<u8> = expected_red.iter().flat_map(|&red| [red, 20, 30, 255]).collect();
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn test_downscale_box_partial_block() {
+        // 3x1: блок из двух пикселей и крайний блок из одного
+        let mut buf = vec![0, 0, 0, 255, 100, 100, 100, 255, 7, 7, 7, 255];
+        downscale_box(&mut buf, 3, 1, 2, false).unwrap();
+        assert_eq!(buf, vec![50, 50, 50, 255, 50, 50, 50, 255, 7, 7, 7, 255]);
+    }
+
+    #[test]
+    fn test_downscale_box_invalid_factor() {
+        let mut buf = vec![0; 16];
+        assert!(downscale_box(&mut buf, 2, 2, 1, false).is_err());
+    }
+
+    #[test]
+    fn test_downscale_box_linear_checkerboard() {
+        let checkerboard: Vec<u8> = (0..16)
+            .flat_map(|pixel| {
+                let value = if (pixel % 4 + pixel / 4) % 2 == 0 { 255 } else { 0 };
+                [value, value, value, 255]
+            })
+            .collect();
+        let mut srgb = checkerboard.clone();
+        downscale_box(&mut srgb, 4, 4, 2, false).unwrap();
+        let mut linear = checkerboard;
+        downscale_box(&mut linear, 4, 4, 2, true).unwrap();
+        // Среднее sRGB 128, среднее в линейной яркости (50%) - 188
+        assert!(srgb.chunks_exact(4).all(|pixel| pixel == [128, 128, 128, 255]));
+        assert!(linear.chunks_exact(4).all(|pixel| pixel == [188, 188, 188, 255]));
+    }
+}
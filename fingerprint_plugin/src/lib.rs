@@ -0,0 +1,238 @@
+#![warn(missing_docs)]
+//! Модуль для реализации обработки изображения - вычисление отпечатка (хеша) буфера
+//!
+//! Предоставляет функциональность вычисления стабильного хеша RGBA буфера и записи его в лог.
+//! Изображение не изменяется, что позволяет проверять воспроизводимость цепочки обработки
+
+use log::LevelFilter;
+use plugins_support::logger::{
+    LogTimezone, get_log_level, set_call_level, set_log_timezone, setup_logger_once,
+};
+use plugins_support::config_parse::warn_ignored_fields;
+use plugins_support::image_size::rgba_len;
+use plugins_support::{config_parse::ConfigReader, error::Error};
+use serde::Deserialize;
+use std::ffi::{CStr, c_char, c_uint};
+use std::slice;
+
+const PKG_NAME: &str = env!("CARGO_PKG_NAME");
+
+plugins_support::export_warning_count!();
+plugins_support::export_abi_probe!();
+
+/// Поля конфигурации, которые использует плагин
+const KNOWN_FIELDS: &[&str] = &["algorithm"];
+
+const FNV32_OFFSET: u32 = 0x811c_9dc5;
+const FNV32_PRIME: u32 = 0x0100_0193;
+const FNV64_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV64_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Алгоритм вычисления отпечатка
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Algorithm {
+    /// FNV-1a 32 бита
+    Fnv1a32,
+    /// FNV-1a 64 бита
+    #[default]
+    Fnv1a64,
+}
+
+#[derive(Deserialize, Debug)]
+struct ConfigTransform {
+    algorithm: Option<Algorithm>,
+    log_level: Option<String>,
+    log_tz: Option<LogTimezone>,
+}
+
+/// Вычисление отпечатка буфера
+///
+/// # Аргументы
+/// * `buf`       – буфер RGBA
+/// * `algorithm` – алгоритм хеширования
+///
+/// # Возращает
+/// Значение хеша (для 32-битного алгоритма в младших битах)
+///
+pub fn fingerprint(buf: &[u8], algorithm: Algorithm) -> u64 {
+    match algorithm {
+        Algorithm::Fnv1a32 => buf.iter().fold(FNV32_OFFSET, |hash, &byte| {
+            (hash ^ byte as u32).wrapping_mul(FNV32_PRIME)
+        }) as u64,
+        Algorithm::Fnv1a64 => buf.iter().fold(FNV64_OFFSET, |hash, &byte| {
+            (hash ^ byte as u64).wrapping_mul(FNV64_PRIME)
+        }),
+    }
+}
+
+/// ```rust
+///
+///  Вычисление отпечатка RGBA буффера, буфер не изменяется
+///
+///
+///  # Параметры
+///   - `width` (`c_uint`):  ширина изображения в пикселях
+///   - `height` (`c_uint`): высота изображения в пикселях
+///   - `rgba_data` (`*mut u8`): Мутабельный сырой указатель на RGBA буфер. Размер буфера `width * height * 4` bytes.
+///   - `params` (`*const c_char`): Сырой указатель на нуль терменированноу строку  конфигурации JSON-формата.
+///
+///  # Параметры конфигурации (JSON формат)
+///   - `log_level` (optional, string): уровень логирования ("Debug", "Info").
+///   - `log_tz` (optional, string): часовой пояс меток времени лога ("local", "utc"), по умолчанию "local"
+///   - `algorithm` (optional, string): алгоритм ("fnv1a32", "fnv1a64"), по умолчанию "fnv1a64"
+///
+///  # Пример JSON:
+///  ```json
+///  {
+///     "log_level": "Info",
+///     "algorithm": "fnv1a64"
+///  }
+///  ```
+///
+///
+///   # Error Handling
+///   - Ошибки фиксируются в логе
+///   - Поля конфигурации, которые плагин не использует, фиксируются в логе предупреждением
+///
+///
+///   # Пример С
+///  ```c
+///  // In C code
+///   unsigned int width = 1920;
+///   unsigned int height = 1080;
+///   unsigned char *image_data = ...; // RGBA buffer, allocated elsewhere
+///   const char *config = "{\"algorithm\": \"fnv1a64\"}";
+///   process_image(width, height, image_data, config);
+///   ```
+/// # Safety
+///  Данная функция  помечена `unsafe`:
+///   - Работа напрямую с сырыми указателями (`rgba_data`, `params`) предстаялет external C code
+///   - Использования недопустимого указателя или парметров width, height undefined behavior.
+/// ```
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn process_image(
+    width: c_uint,
+    height: c_uint,
+    rgba_data: *mut u8,
+    params: *const c_char,
+) {
+    let file = PKG_NAME.to_owned() + ".log";
+    if let Err(e) = setup_logger_once(LevelFilter::Debug, &file) {
+        match e {
+            Error::LoggerInitError(_) => {
+                log::warn!("Logger init error: {}", e);
+            }
+            _ => {
+                log::error!("Logger init error: {}", e);
+                return;
+            }
+        }
+    }
+    log::info!("Start plugin {}", &file);
+    if params.is_null() {
+        log::error!("Pointer params is_null");
+        return;
+    }
+    let config = unsafe { CStr::from_ptr(params) };
+    let params_config = match config.to_str() {
+        Ok(config) => {
+            warn_ignored_fields(config, KNOWN_FIELDS);
+            let config: Result<ConfigReader<ConfigTransform>, Error> =
+                ConfigReader::try_from(config);
+            if let Ok(config) = config {
+                config
+            } else {
+                log::error!("Error converting config to string");
+                return;
+            }
+        }
+        _ => {
+            log::error!("Invalid config file");
+            return;
+        }
+    };
+    // Уровень из конфигурации действует только в потоке вызова до его завершения
+    let _log_level = params_config
+        .config
+        .log_level
+        .as_deref()
+        .map(|log_level| set_call_level(get_log_level(log_level)));
+    if let Some(log_tz) = params_config.config.log_tz {
+        set_log_timezone(log_tz);
+    }
+    if rgba_data.is_null() {
+        log::error!("Null pointer rgba_data");
+        return;
+    }
+    if width == 0 {
+        log::error!("width cannot be 0");
+        return;
+    }
+    if height == 0 {
+        log::error!("height cannot be 0");
+        return;
+    }
+    let len_in_pixel = match rgba_len(width, height) {
+        Ok(len) => len,
+        Err(e) => {
+            log::error!("Length calculation failed: {}", e);
+            return;
+        }
+    };
+    let height: usize = match height.try_into() {
+        Ok(h) => h,
+        Err(_) => {
+            log::error!("Height conversion failed");
+            return;
+        }
+    };
+    let width: usize = match width.try_into() {
+        Ok(w) => w,
+        Err(_) => {
+            log::error!("Width conversion failed");
+            return;
+        }
+    };
+    let buf = unsafe { slice::from_raw_parts(rgba_data, len_in_pixel) };
+    let algorithm = params_config.config.algorithm.unwrap_or_default();
+    let hash = fingerprint(buf, algorithm);
+    let hash = match algorithm {
+        Algorithm::Fnv1a32 => format!("{:08x}", hash),
+        Algorithm::Fnv1a64 => format!("{:016x}", hash),
+    };
+    log::info!("Fingerprint {:?} {}x{}: {}", algorithm, width, height, hash);
+    log::info!("Finish fingerprint image");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    #[test]
+    fn test_fingerprint_known_values() {
+        assert_eq!(fingerprint(b"a", Algorithm::Fnv1a64), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fingerprint(b"a", Algorithm::Fnv1a32), 0xe40c_292c);
+    }
+
+    #[test]
+    fn test_fingerprint_stable() {
+        let buf = (0..64).collect::<Vec<u8>>();
+        let first = fingerprint(&buf, Algorithm::default());
+        let second = fingerprint(&buf, Algorithm::default());
+        assert_eq!(first, second);
+        let mut changed = buf.clone();
+        changed[10] = 0;
+        assert_ne!(first, fingerprint(&changed, Algorithm::default()));
+    }
+
+    #[test]
+    fn test_fingerprint_image_unchanged() {
+        let mut buf = (0..16).collect::<Vec<_>>();
+        let json = r#"{"algorithm": "fnv1a32"}"#;
+        let params_cstring = CString::new(json).unwrap();
+        unsafe { process_image(2, 2, buf.as_mut_ptr(), params_cstring.as_ptr()) };
+        assert_eq!(buf, (0..16).collect::<Vec<u8>>());
+    }
+}
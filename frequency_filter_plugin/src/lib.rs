@@ -0,0 +1,326 @@
+#![warn(missing_docs)]
+//! Модуль для реализации обработки изображения - частотная фильтрация
+//!
+//! Предоставляет функциональность фильтрации каналов RGB в частотной области:
+//! двумерное БПФ, круговая маска нижних или верхних частот, обратное БПФ.
+//! Размеры изображения могут быть любыми (БПФ смешанного основания), альфа-канал не изменяется
+
+use log::LevelFilter;
+use plugins_support::config_parse::warn_ignored_fields;
+use plugins_support::image_size::rgba_len;
+use plugins_support::logger::{
+    LogTimezone, get_log_level, set_call_level, set_log_timezone, setup_logger_once,
+};
+use plugins_support::{config_parse::ConfigReader, error::Error};
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+use serde::Deserialize;
+use std::ffi::{CStr, c_char, c_uint};
+use std::slice;
+
+const PKG_NAME: &str = env!("CARGO_PKG_NAME");
+const BYTE_PER_PIXEL: usize = 4;
+
+plugins_support::export_warning_count!();
+plugins_support::export_abi_probe!();
+
+/// Поля конфигурации, которые использует плагин
+const KNOWN_FIELDS: &[&str] = &["mode", "cutoff"];
+
+/// Тип фильтра
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FilterMode {
+    /// Фильтр нижних частот: сглаживание
+    Low,
+    /// Фильтр верхних частот: выделение границ и мелких деталей
+    High,
+}
+
+#[derive(Deserialize, Debug)]
+struct ConfigTransform {
+    mode: FilterMode,
+    cutoff: f64,
+    log_level: Option<String>,
+    log_tz: Option<LogTimezone>,
+}
+
+/// Частота индекса `index` спектра длины `len` в долях частоты Найквиста
+fn nyquist_fraction(index: usize, len: usize) -> f64 {
+    if len < 2 {
+        return 0.0;
+    }
+    index.min(len - index) as f64 / (len as f64 / 2.0)
+}
+
+/// Двумерное БПФ буфера `width x height`: сначала строки, затем столбцы
+fn fft_2d(
+    data: &mut [Complex<f64>],
+    width: usize,
+    height: usize,
+    row: &dyn Fft<f64>,
+    column: &dyn Fft<f64>,
+) {
+    row.process(data);
+    let mut line = vec![Complex::default(); height];
+    for x in 0..width {
+        for (y, value) in line.iter_mut().enumerate() {
+            *value = data[y * width + x];
+        }
+        column.process(&mut line);
+        for (y, value) in line.iter().enumerate() {
+            data[y * width + x] = *value;
+        }
+    }
+}
+
+/// Частотная фильтрация RGBA буфера
+///
+/// # Аргументы
+/// * `buf`    – изменяемый буфер RGBA (длина = width * height * 4)
+/// * `width`  – ширина в пикселях
+/// * `height` – высота в пикселях
+/// * `mode`   – фильтр нижних или верхних частот
+/// * `cutoff` – радиус круговой маски в долях частоты Найквиста (0..=1)
+///
+/// Фильтр нижних частот сохраняет частоты с радиусом `<= cutoff`, верхних - остальные
+/// (в том числе убирает среднее значение, поэтому результат в основном темный).
+/// Результат ограничивается диапазоном 0..=255
+///
+pub fn frequency_filter(
+    buf: &mut [u8],
+    width: usize,
+    height: usize,
+    mode: FilterMode,
+    cutoff: f64,
+) -> Result<(), Error> {
+    if !(0.0..=1.0).contains(&cutoff) {
+        return Err(Error::ErrorValue("Cutoff must be in 0..=1".to_string()));
+    }
+    let mut planner = FftPlanner::<f64>::new();
+    let row_forward = planner.plan_fft_forward(width);
+    let row_inverse = planner.plan_fft_inverse(width);
+    let column_forward = planner.plan_fft_forward(height);
+    let column_inverse = planner.plan_fft_inverse(height);
+    let keep: Vec<bool> = (0..height)
+        .flat_map(|v| (0..width).map(move |u| (u, v)))
+        .map(|(u, v)| {
+            let radius = nyquist_fraction(u, width).hypot(nyquist_fraction(v, height));
+            match mode {
+                FilterMode::Low => radius <= cutoff,
+                FilterMode::High => radius > cutoff,
+            }
+        })
+        .collect();
+    let scale = (width * height) as f64;
+    for channel in 0..3 {
+        let mut data: Vec<Complex<f64>> = buf
+            .chunks_exact(BYTE_PER_PIXEL)
+            .map(|pixel| Complex::new(pixel[channel] as f64, 0.0))
+            .collect();
+        fft_2d(&mut data, width, height, &*row_forward, &*column_forward);
+        for (value, keep) in data.iter_mut().zip(&keep) {
+            if !keep {
+                *value = Complex::default();
+            }
+        }
+        fft_2d(&mut data, width, height, &*row_inverse, &*column_inverse);
+        for (pixel, value) in buf.chunks_exact_mut(BYTE_PER_PIXEL).zip(&data) {
+            pixel[channel] = (value.re / scale).round().clamp(0.0, 255.0) as u8;
+        }
+    }
+    Ok(())
+}
+
+/// ```rust
+///
+///  Трансформация RGBA буффера частотным фильтром
+///
+///
+///  # Параметры
+///   - `width` (`c_uint`):  ширина изображения в пикселях
+///   - `height` (`c_uint`): высота изображения в пикселях
+///   - `rgba_data` (`*mut u8`): Мутабельный сырой указатель на RGBA буфер. Размер буфера `width * height * 4` bytes.
+///   - `params` (`*const c_char`): Сырой указатель на нуль терменированноу строку  конфигурации JSON-формата.
+///
+///  # Параметры конфигурации (JSON формат)
+///   - `log_level` (optional, string): уровень логирования ("Debug", "Info").
+///   - `log_tz` (optional, string): часовой пояс меток времени лога ("local", "utc"), по умолчанию "local"
+///   - `mode` (required, string): тип фильтра ("low", "high")
+///   - `cutoff` (required, float): радиус маски в долях частоты Найквиста, 0..=1
+///
+///  # Пример JSON:
+///  ```json
+///  {
+///     "log_level": "Debug",
+///     "mode": "low",
+///     "cutoff": 0.25
+///  }
+///  ```
+///
+///
+///   # Error Handling
+///   - Ошибки фиксируются в логе
+///   - Поля конфигурации, которые плагин не использует, фиксируются в логе предупреждением
+///
+///
+///   # Пример С
+///  ```c
+///  // In C code
+///   unsigned int width = 1920;
+///   unsigned int height = 1080;
+///   unsigned char *image_data = ...; // RGBA buffer, allocated elsewhere
+///   const char *config = "{\"mode\": \"low\", \"cutoff\": 0.25}";
+///   process_image(width, height, image_data, config);
+///   ```
+/// # Safety
+///  Данная функция  помечена `unsafe`:
+///   - Работа напрямую с сырыми указателями (`rgba_data`, `params`) предстаялет external C code
+///   - Использования недопустимого указателя или парметров width, height undefined behavior.
+/// ```
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn process_image(
+    width: c_uint,
+    height: c_uint,
+    rgba_data: *mut u8,
+    params: *const c_char,
+) {
+    let file = PKG_NAME.to_owned() + ".log";
+    if let Err(e) = setup_logger_once(LevelFilter::Debug, &file) {
+        match e {
+            Error::LoggerInitError(_) => {
+                log::warn!("Logger init error: {}", e);
+            }
+            _ => {
+                log::error!("Logger init error: {}", e);
+                return;
+            }
+        }
+    }
+    log::info!("Start plugin {}", &file);
+    if params.is_null() {
+        log::error!("Pointer params is_null");
+        return;
+    }
+    let config = unsafe { CStr::from_ptr(params) };
+    let params_config = match config.to_str() {
+        Ok(config) => {
+            warn_ignored_fields(config, KNOWN_FIELDS);
+            let config: Result<ConfigReader<ConfigTransform>, Error> =
+                ConfigReader::try_from(config);
+            if let Ok(config) = config {
+                config
+            } else {
+                log::error!("Error converting config to string");
+                return;
+            }
+        }
+        _ => {
+            log::error!("Invalid config file");
+            return;
+        }
+    };
+    // Уровень из конфигурации действует только в потоке вызова до его завершения
+    let _log_level = params_config
+        .config
+        .log_level
+        .as_deref()
+        .map(|log_level| set_call_level(get_log_level(log_level)));
+    if let Some(log_tz) = params_config.config.log_tz {
+        set_log_timezone(log_tz);
+    }
+    if rgba_data.is_null() {
+        log::error!("Null pointer rgba_data");
+        return;
+    }
+    if width == 0 {
+        log::error!("width cannot be 0");
+        return;
+    }
+    if height == 0 {
+        log::error!("height cannot be 0");
+        return;
+    }
+    let len_in_pixel = match rgba_len(width, height) {
+        Ok(len) => len,
+        Err(e) => {
+            log::error!("Length calculation failed: {}", e);
+            return;
+        }
+    };
+    let height: usize = match height.try_into() {
+        Ok(h) => h,
+        Err(_) => {
+            log::error!("Height conversion failed");
+            return;
+        }
+    };
+    let width: usize = match width.try_into() {
+        Ok(w) => w,
+        Err(_) => {
+            log::error!("Width conversion failed");
+            return;
+        }
+    };
+    log::info!("Start converting image");
+    let buf = unsafe { slice::from_raw_parts_mut(rgba_data, len_in_pixel) };
+    let config = &params_config.config;
+    if let Err(e) = frequency_filter(buf, width, height, config.mode, config.cutoff) {
+        log::error!("Frequency filter error: {}", e);
+        return;
+    }
+    log::info!("Finish converting image");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    /// Шахматная доска 0/255 с полупрозрачным альфа-каналом
+    fn checkerboard(width: usize, height: usize) -> Vec<u8> {
+        (0..width * height)
+            .flat_map(|index| {
+                let value = if (index % width + index / width).is_multiple_of(2) { 0 } else { 255 };
+                [value, value, value, 200]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_low_pass_removes_checkerboard() {
+        // Остается только среднее значение 127.5, размеры 6x5 - не степени двойки
+        for (width, height) in [(6, 5), (8, 8)] {
+            let mut buf = checkerboard(width, height);
+            let json = r#"{"mode": "low", "cutoff": 0.5}"#;
+            let params_cstring = CString::new(json).unwrap();
+            let (w, h) = (width as c_uint, height as c_uint);
+            unsafe { process_image(w, h, buf.as_mut_ptr(), params_cstring.as_ptr()) };
+            for pixel in buf.chunks_exact(4) {
+                assert!((127..=128).contains(&pixel[0]), "{:?}", pixel);
+                assert_eq!(pixel[0], pixel[1]);
+                assert_eq!(pixel[3], 200);
+            }
+        }
+    }
+
+    #[test]
+    fn test_high_pass_keeps_checkerboard() {
+        let mut buf = checkerboard(8, 8);
+        frequency_filter(&mut buf, 8, 8, FilterMode::High, 0.5).unwrap();
+        // Без средней составляющей остается чередование -127.5 / 127.5, отрицательные значения обрезаются
+        for (index, pixel) in buf.chunks_exact(4).enumerate() {
+            if (index % 8 + index / 8) % 2 == 0 {
+                assert_eq!(pixel, [0, 0, 0, 200]);
+            } else {
+                assert!((127..=128).contains(&pixel[0]), "{:?}", pixel);
+            }
+        }
+    }
+
+    #[test]
+    fn test_frequency_filter_invalid_cutoff() {
+        let mut buf = checkerboard(2, 2);
+        assert!(frequency_filter(&mut buf, 2, 2, FilterMode::Low, 1.5).is_err());
+    }
+}
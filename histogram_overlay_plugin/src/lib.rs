@@ -0,0 +1,358 @@
+#![warn(missing_docs)]
+//! Модуль для реализации обработки изображения - наложение гистограммы
+//!
+//! Предоставляет функциональность построения гистограмм каналов RGB и отрисовки их
+//! полупрозрачным графиком в углу изображения
+
+use log::LevelFilter;
+use plugins_support::config_parse::warn_ignored_fields;
+use plugins_support::image_size::rgba_len;
+use plugins_support::logger::{
+    LogTimezone, get_log_level, set_call_level, set_log_timezone, setup_logger_once,
+};
+use plugins_support::{config_parse::ConfigReader, error::Error};
+use serde::Deserialize;
+use std::ffi::{CStr, c_char, c_uint};
+use std::slice;
+
+const PKG_NAME: &str = env!("CARGO_PKG_NAME");
+const BYTE_PER_PIXEL: usize = 4;
+const BINS: usize = 256;
+/// Непрозрачность фона графика
+const BACKGROUND_ALPHA: f32 = 0.5;
+/// Непрозрачность столбцов гистограммы
+const BAR_ALPHA: f32 = 0.5;
+
+plugins_support::export_warning_count!();
+plugins_support::export_abi_probe!();
+
+/// Поля конфигурации, которые использует плагин
+const KNOWN_FIELDS: &[&str] = &["position", "height", "channels"];
+
+/// Угол изображения, в котором рисуется график
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum Position {
+    /// Левый верхний угол
+    TopLeft,
+    /// Правый верхний угол
+    TopRight,
+    /// Левый нижний угол
+    #[default]
+    BottomLeft,
+    /// Правый нижний угол
+    BottomRight,
+}
+
+/// Канал гистограммы
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Channel {
+    /// Красный
+    Red,
+    /// Зеленый
+    Green,
+    /// Синий
+    Blue,
+}
+
+impl Channel {
+    fn index(self) -> usize {
+        match self {
+            Channel::Red => 0,
+            Channel::Green => 1,
+            Channel::Blue => 2,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct ConfigTransform {
+    position: Option<Position>,
+    height: usize,
+    channels: Option<Vec<Channel>>,
+    log_level: Option<String>,
+    log_tz: Option<LogTimezone>,
+}
+
+/// Гистограммы каналов R, G, B
+pub fn histograms(buf: &[u8]) -> [[u32; BINS]; 3] {
+    let mut histograms = [[0u32; BINS]; 3];
+    for pixel in buf.chunks_exact(BYTE_PER_PIXEL) {
+        for (histogram, value) in histograms.iter_mut().zip(pixel) {
+            histogram[*value as usize] += 1;
+        }
+    }
+    histograms
+}
+
+/// Смешивание цвета с пикселем с заданной непрозрачностью, альфа-канал не изменяется
+fn blend(pixel: &mut [u8], color: [u8; 3], alpha: f32) {
+    for (value, color) in pixel.iter_mut().zip(color) {
+        *value = (*value as f32 * (1.0 - alpha) + color as f32 * alpha).round() as u8;
+    }
+}
+
+/// Отрисовка гистограммы в углу RGBA буфера
+///
+/// # Аргументы
+/// * `buf`      – изменяемый буфер RGBA (длина = width * height * 4)
+/// * `width`    – ширина в пикселях
+/// * `height`   – высота в пикселях
+/// * `position` – угол изображения
+/// * `graph_height` – высота графика в пикселях (> 0)
+/// * `channels` – отображаемые каналы
+///
+/// График занимает `min(256, width)` пикселей по ширине и `min(graph_height, height)` по высоте
+///
+pub fn draw_histogram(
+    buf: &mut [u8],
+    width: usize,
+    height: usize,
+    position: Position,
+    graph_height: usize,
+    channels: &[Channel],
+) -> Result<(), Error> {
+    if graph_height == 0 {
+        return Err(Error::ErrorValue("Height cannot be 0".to_string()));
+    }
+    let histograms = histograms(buf);
+    let graph_width = width.min(BINS);
+    let graph_height = graph_height.min(height);
+    let left = match position {
+        Position::TopLeft | Position::BottomLeft => 0,
+        Position::TopRight | Position::BottomRight => width - graph_width,
+    };
+    let top = match position {
+        Position::TopLeft | Position::TopRight => 0,
+        Position::BottomLeft | Position::BottomRight => height - graph_height,
+    };
+    // Столбец графика объединяет несколько корзин, если ширина меньше 256
+    let column = |histogram: &[u32; BINS], x: usize| {
+        histogram[x * BINS / graph_width..(x + 1) * BINS / graph_width]
+            .iter()
+            .copied()
+            .max()
+            .unwrap_or(0)
+    };
+    let peak = channels
+        .iter()
+        .flat_map(|channel| (0..graph_width).map(|x| column(&histograms[channel.index()], x)).max())
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    for y in 0..graph_height {
+        // Высота строки над нижним краем графика
+        let level = graph_height - y;
+        for x in 0..graph_width {
+            let index = ((top + y) * width + left + x) * BYTE_PER_PIXEL;
+            let pixel = &mut buf[index..index + 3];
+            blend(pixel, [0, 0, 0], BACKGROUND_ALPHA);
+            for channel in channels {
+                let bar = (column(&histograms[channel.index()], x) as u64 * graph_height as u64)
+                    .div_ceil(peak as u64) as usize;
+                if bar >= level {
+                    let mut color = [0; 3];
+                    color[channel.index()] = 255;
+                    blend(pixel, color, BAR_ALPHA);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// ```rust
+///
+///  Наложение гистограммы каналов на RGBA буффер
+///
+///
+///  # Параметры
+///   - `width` (`c_uint`):  ширина изображения в пикселях
+///   - `height` (`c_uint`): высота изображения в пикселях
+///   - `rgba_data` (`*mut u8`): Мутабельный сырой указатель на RGBA буфер. Размер буфера `width * height * 4` bytes.
+///   - `params` (`*const c_char`): Сырой указатель на нуль терменированноу строку  конфигурации JSON-формата.
+///
+///  # Параметры конфигурации (JSON формат)
+///   - `log_level` (optional, string): уровень логирования ("Debug", "Info").
+///   - `log_tz` (optional, string): часовой пояс меток времени лога ("local", "utc"), по умолчанию "local"
+///   - `position` (optional, string): угол ("top-left", "top-right", "bottom-left", "bottom-right"), по умолчанию "bottom-left"
+///   - `height` (required, integer): высота графика в пикселях. Должна быть больше 0
+///   - `channels` (optional, array): каналы ("red", "green", "blue"), по умолчанию все три
+///
+///  # Пример JSON:
+///  ```json
+///  {
+///     "log_level": "Debug",
+///     "position": "bottom-right",
+///     "height": 64,
+///     "channels": ["red", "green", "blue"]
+///  }
+///  ```
+///
+///
+///   # Error Handling
+///   - Ошибки фиксируются в логе
+///   - Поля конфигурации, которые плагин не использует, фиксируются в логе предупреждением
+///
+///
+///   # Пример С
+///  ```c
+///  // In C code
+///   unsigned int width = 1920;
+///   unsigned int height = 1080;
+///   unsigned char *image_data = ...; // RGBA buffer, allocated elsewhere
+///   const char *config = "{\"position\": \"bottom-right\", \"height\": 64}";
+///   process_image(width, height, image_data, config);
+///   ```
+/// # Safety
+///  Данная функция  помечена `unsafe`:
+///   - Работа напрямую с сырыми указателями (`rgba_data`, `params`) предстаялет external C code
+///   - Использования недопустимого указателя или парметров width, height undefined behavior.
+/// ```
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn process_image(
+    width: c_uint,
+    height: c_uint,
+    rgba_data: *mut u8,
+    params: *const c_char,
+) {
+    let file = PKG_NAME.to_owned() + ".log";
+    if let Err(e) = setup_logger_once(LevelFilter::Debug, &file) {
+        match e {
+            Error::LoggerInitError(_) => {
+                log::warn!("Logger init error: {}", e);
+            }
+            _ => {
+                log::error!("Logger init error: {}", e);
+                return;
+            }
+        }
+    }
+    log::info!("Start plugin {}", &file);
+    if params.is_null() {
+        log::error!("Pointer params is_null");
+        return;
+    }
+    let config = unsafe { CStr::from_ptr(params) };
+    let params_config = match config.to_str() {
+        Ok(config) => {
+            warn_ignored_fields(config, KNOWN_FIELDS);
+            let config: Result<ConfigReader<ConfigTransform>, Error> =
+                ConfigReader::try_from(config);
+            if let Ok(config) = config {
+                config
+            } else {
+                log::error!("Error converting config to string");
+                return;
+            }
+        }
+        _ => {
+            log::error!("Invalid config file");
+            return;
+        }
+    };
+    // Уровень из конфигурации действует только в потоке вызова до его завершения
+    let _log_level = params_config
+        .config
+        .log_level
+        .as_deref()
+        .map(|log_level| set_call_level(get_log_level(log_level)));
+    if let Some(log_tz) = params_config.config.log_tz {
+        set_log_timezone(log_tz);
+    }
+    if rgba_data.is_null() {
+        log::error!("Null pointer rgba_data");
+        return;
+    }
+    if width == 0 {
+        log::error!("width cannot be 0");
+        return;
+    }
+    if height == 0 {
+        log::error!("height cannot be 0");
+        return;
+    }
+    let len_in_pixel = match rgba_len(width, height) {
+        Ok(len) => len,
+        Err(e) => {
+            log::error!("Length calculation failed: {}", e);
+            return;
+        }
+    };
+    let height: usize = match height.try_into() {
+        Ok(h) => h,
+        Err(_) => {
+            log::error!("Height conversion failed");
+            return;
+        }
+    };
+    let width: usize = match width.try_into() {
+        Ok(w) => w,
+        Err(_) => {
+            log::error!("Width conversion failed");
+            return;
+        }
+    };
+    log::info!("Start converting image");
+    let buf = unsafe { slice::from_raw_parts_mut(rgba_data, len_in_pixel) };
+    let config = &params_config.config;
+    let channels = config
+        .channels
+        .clone()
+        .unwrap_or_else(|| vec![Channel::Red, Channel::Green, Channel::Blue]);
+    let position = config.position.unwrap_or_default();
+    if let Err(e) = draw_histogram(buf, width, height, position, config.height, &channels) {
+        log::error!("Histogram overlay error: {}", e);
+        return;
+    }
+    log::info!("Finish converting image");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    const WIDTH: usize = 300;
+    const HEIGHT: usize = 20;
+    const GRAPH: usize = 8;
+
+    /// Горизонтальный градиент по красному каналу
+    fn gradient() -> Vec<u8> {
+        let mut buf = vec![100; WIDTH * HEIGHT * BYTE_PER_PIXEL];
+        for (index, pixel) in buf.chunks_exact_mut(BYTE_PER_PIXEL).enumerate() {
+            pixel[0] = (index % WIDTH) as u8;
+            pixel[3] = 255;
+        }
+        buf
+    }
+
+    #[test]
+    fn test_overlay_region_only() {
+        let source = gradient();
+        let mut buf = source.clone();
+        let json = r#"{"position": "bottom-left", "height": 8, "channels": ["red", "green"]}"#;
+        let params_cstring = CString::new(json).unwrap();
+        unsafe { process_image(WIDTH as c_uint, HEIGHT as c_uint, buf.as_mut_ptr(), params_cstring.as_ptr()) };
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                let index = (y * WIDTH + x) * BYTE_PER_PIXEL;
+                let inside = x < BINS && y >= HEIGHT - GRAPH;
+                let changed = buf[index..index + 3] != source[index..index + 3];
+                assert_eq!(changed, inside, "pixel ({}, {})", x, y);
+                assert_eq!(buf[index + 3], 255);
+            }
+        }
+        // Зеленый канал сосредоточен в одной корзине - столбец на всю высоту графика
+        let top_row = ((HEIGHT - GRAPH) * WIDTH + 100) * BYTE_PER_PIXEL;
+        assert!(buf[top_row + 1] > buf[top_row + 2]);
+    }
+
+    #[test]
+    fn test_overlay_invalid_height() {
+        let mut buf = gradient();
+        assert!(draw_histogram(&mut buf, WIDTH, HEIGHT, Position::TopLeft, 0, &[Channel::Red]).is_err());
+        assert_eq!(buf, gradient());
+    }
+}
@@ -0,0 +1,85 @@
+//! Модуль для записи анимации обработки
+//!
+//! Предоставляет функциональность `--animate`: буфер захватывается после каждого прохода
+//! плагина (`--repeat`) и каждого этапа пресета, кадры записываются анимированным PNG (APNG)
+
+use crate::error::ImageProcessorError;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+/// Задержка кадра по умолчанию, мс
+pub(crate) const DEFAULT_FRAME_DELAY_MS: u16 = 200;
+
+/// Кадры анимации RGBA8 одного размера
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Animation {
+    width: u32,
+    height: u32,
+    frames: Vec<Vec<u8>>,
+}
+
+impl Animation {
+    /// Пустая анимация размера `width` x `height`
+    pub(crate) fn new(width: u32, height: u32) -> Self {
+        Animation {
+            width,
+            height,
+            frames: Vec::new(),
+        }
+    }
+
+    /// Добавление снимка RGBA буфера
+    pub(crate) fn push(&mut self, rgba: &[u8]) {
+        self.frames.push(rgba.to_vec());
+    }
+
+    /// Количество кадров
+    pub(crate) fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    ///
+    /// Запись анимированного PNG (бесконечное повторение)
+    ///
+    /// # Параметры
+    ///
+    /// * `path` - путь к выходному файлу
+    /// * `delay_ms` - задержка каждого кадра в миллисекундах
+    ///
+    pub(crate) fn write_apng(&self, path: &Path, delay_ms: u16) -> Result<(), ImageProcessorError> {
+        let num_frames = u32::try_from(self.frames.len()).map_err(plugins_support::error::Error::from)?;
+        let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_animated(num_frames, 0)?;
+        encoder.set_frame_delay(delay_ms, 1000)?;
+        let mut writer = encoder.write_header()?;
+        for frame in &self.frames {
+            writer.write_image_data(frame)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apng_frames() {
+        let mut animation = Animation::new(2, 1);
+        for value in [0, 100, 200] {
+            animation.push(&[value, value, value, 255].repeat(2));
+        }
+        let path = std::env::temp_dir().join("image_processor_animation.png");
+        animation.write_apng(&path, 150).unwrap();
+        let decoder = png::Decoder::new(File::open(&path).unwrap());
+        let mut reader = decoder.read_info().unwrap();
+        assert_eq!(reader.info().animation_control.unwrap().num_frames, 3);
+        let mut buf = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut buf).unwrap();
+        assert_eq!(buf, [0, 0, 0, 255].repeat(2));
+    }
+}
@@ -0,0 +1,292 @@
+//! Модуль для размещения изображения на холсте фиксированного размера
+//!
+//! Предоставляет функциональность вписывания обработанного изображения в холст
+//! с полями (`contain`) или обрезкой (`cover`), а также уменьшение слишком больших
+//! изображений перед обработкой (`--max-process-pixels`)
+
+use crate::error::ImageProcessorError;
+use crate::io::RgbaBuffer;
+use clap::ValueEnum;
+use image::imageops::{self, FilterType};
+use image::{ImageBuffer, Rgba, RgbaImage};
+use plugins_support::color::{linear_to_srgb, srgb_to_linear};
+
+/// Размер холста
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct CanvasSize {
+    /// Ширина в пикселях
+    pub(crate) width: u32,
+    /// Высота в пикселях
+    pub(crate) height: u32,
+}
+
+/// Способ вписывания изображения в холст
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum Fit {
+    /// Изображение целиком помещается в холст, свободное место заполняется фоном
+    #[default]
+    Contain,
+    /// Изображение покрывает холст целиком, выступающие части обрезаются
+    Cover,
+}
+
+///
+/// Разбор размера холста в формате `WxH`
+///
+/// # Параметры
+///
+/// * `value` - строка вида `800x600`
+///
+pub(crate) fn parse_canvas(value: &str) -> Result<CanvasSize, String> {
+    let (width, height) = value
+        .split_once(['x', 'X'])
+        .ok_or_else(|| format!("expected WxH, got `{}`", value))?;
+    let width: u32 = width.trim().parse().map_err(|e| format!("invalid width: {}", e))?;
+    let height: u32 = height.trim().parse().map_err(|e| format!("invalid height: {}", e))?;
+    if width == 0 || height == 0 {
+        return Err("canvas size cannot be 0".to_string());
+    }
+    Ok(CanvasSize { width, height })
+}
+
+///
+/// Разбор цвета фона в формате `RRGGBB` или `RRGGBBAA` (допускается префикс `#`)
+///
+/// # Параметры
+///
+/// * `value` - шестнадцатеричная запись цвета
+///
+pub(crate) fn parse_background(value: &str) -> Result<Rgba<u8>, String> {
+    let hex = value.strip_prefix('#').unwrap_or(value);
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.is_ascii() {
+        return Err(format!("expected RRGGBB or RRGGBBAA, got `{}`", value));
+    }
+    let mut color = [0, 0, 0, 255];
+    for (channel, index) in color.iter_mut().zip((0..hex.len()).step_by(2)) {
+        *channel = u8::from_str_radix(&hex[index..index + 2], 16)
+            .map_err(|e| format!("invalid color `{}`: {}", value, e))?;
+    }
+    Ok(Rgba(color))
+}
+
+///
+/// Масштабирование изображения билинейным фильтром
+///
+/// Усреднение значений sRGB затемняет уменьшенное изображение: шахматная доска из черных
+/// и белых пикселей становится серой 128 вместо перцептивно корректных ~188. С `linear`
+/// каналы RGB переводятся в линейную яркость перед масштабированием и обратно после;
+/// это точнее, но медленнее (вычисления в `f32` и передаточная функция для каждого пикселя)
+///
+/// # Параметры
+///
+/// * `image` - исходное изображение
+/// * `width` - новая ширина
+/// * `height` - новая высота
+/// * `linear` - масштабирование в линейной яркости
+///
+pub(crate) fn resize(image: &RgbaImage, width: u32, height: u32, linear: bool) -> RgbaImage {
+    if !linear {
+        return imageops::resize(image, width, height, FilterType::Triangle);
+    }
+    let linear_image: ImageBuffer<Rgba<f32>, Vec<f32>> = ImageBuffer::from_fn(image.width(), image.height(), |x, y| {
+        let [r, g, b, a] = image.get_pixel(x, y).0;
+        Rgba([
+            srgb_to_linear(r) as f32,
+            srgb_to_linear(g) as f32,
+            srgb_to_linear(b) as f32,
+            a as f32 / 255.0,
+        ])
+    });
+    let scaled = imageops::resize(&linear_image, width, height, FilterType::Triangle);
+    RgbaImage::from_fn(width, height, |x, y| {
+        let [r, g, b, a] = scaled.get_pixel(x, y).0;
+        Rgba([
+            linear_to_srgb(r as f64),
+            linear_to_srgb(g as f64),
+            linear_to_srgb(b as f64),
+            (a.clamp(0.0, 1.0) * 255.0).round() as u8,
+        ])
+    })
+}
+
+///
+/// Размещение изображения на холсте
+///
+/// # Параметры
+///
+/// * `image` - обработанное изображение
+/// * `canvas` - размер холста
+/// * `fit` - способ вписывания
+/// * `background` - цвет полей
+/// * `linear` - масштабирование в линейной яркости (см. [`resize`])
+///
+/// # Возращает
+/// Изображение размером ровно `canvas`, исходное изображение масштабируется с сохранением
+/// пропорций и центрируется
+///
+pub(crate) fn fit_canvas(
+    image: &RgbaImage,
+    canvas: CanvasSize,
+    fit: Fit,
+    background: Rgba<u8>,
+    linear: bool,
+) -> RgbaImage {
+    let scale_x = canvas.width as f64 / image.width() as f64;
+    let scale_y = canvas.height as f64 / image.height() as f64;
+    let scale = match fit {
+        Fit::Contain => scale_x.min(scale_y),
+        Fit::Cover => scale_x.max(scale_y),
+    };
+    let width = ((image.width() as f64 * scale).round() as u32).max(1);
+    let height = ((image.height() as f64 * scale).round() as u32).max(1);
+    log::info!(
+        "Fit {}x{} into canvas {}x{} ({:?}), scaled to {}x{}",
+        image.width(),
+        image.height(),
+        canvas.width,
+        canvas.height,
+        fit,
+        width,
+        height
+    );
+    let scaled = if (width, height) == image.dimensions() {
+        image.clone()
+    } else {
+        resize(image, width, height, linear)
+    };
+    let mut result = RgbaImage::from_pixel(canvas.width, canvas.height, background);
+    let x = (canvas.width as i64 - width as i64) / 2;
+    let y = (canvas.height as i64 - height as i64) / 2;
+    imageops::replace(&mut result, &scaled, x, y);
+    result
+}
+
+///
+/// Размер, до которого нужно уменьшить изображение, чтобы оно содержало не больше `max_pixels` пикселей
+///
+/// # Параметры
+///
+/// * `width` - ширина изображения
+/// * `height` - высота изображения
+/// * `max_pixels` - максимальное количество пикселей, не меньше 1
+///
+/// # Возращает
+/// Новый размер с сохранением пропорций (каждая сторона не меньше 1 пикселя),
+/// `None`, если изображение уже помещается в ограничение
+///
+pub(crate) fn capped_size(width: u32, height: u32, max_pixels: u64) -> Option<(u32, u32)> {
+    let pixels = u64::from(width) * u64::from(height);
+    if pixels <= max_pixels {
+        return None;
+    }
+    let scale = (max_pixels as f64 / pixels as f64).sqrt();
+    let mut width = ((width as f64 * scale).floor() as u32).max(1);
+    let mut height = ((height as f64 * scale).floor() as u32).max(1);
+    // Погрешность вычислений и ограничение сторон снизу могут дать лишние пиксели
+    while u64::from(width) * u64::from(height) > max_pixels && (width > 1 || height > 1) {
+        if width >= height {
+            width -= 1;
+        } else {
+            height -= 1;
+        }
+    }
+    Some((width, height))
+}
+
+///
+/// Масштабирование RGBA буфера
+///
+/// # Параметры
+///
+/// * `image` - изображение
+/// * `width` - новая ширина
+/// * `height` - новая высота
+/// * `linear` - масштабирование в линейной яркости
+///
+/// # Возращает
+/// Изображение нового размера или `ConvertFromRawError`, если длина буфера не совпадает с размером
+///
+pub(crate) fn resize_buffer(
+    image: RgbaBuffer,
+    width: u32,
+    height: u32,
+    linear: bool,
+) -> Result<RgbaBuffer, ImageProcessorError> {
+    let source = RgbaImage::from_raw(image.width, image.height, image.data).ok_or_else(|| {
+        log::error!("Error convert image");
+        ImageProcessorError::ConvertFromRawError
+    })?;
+    log::info!("Resize {}x{} to {}x{}", source.width(), source.height(), width, height);
+    Ok(RgbaBuffer {
+        width,
+        height,
+        data: resize(&source, width, height, linear).into_raw(),
+        ..image
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+    const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+    #[test]
+    fn test_fit_contain_letterbox() {
+        let image = RgbaImage::from_pixel(4, 2, RED);
+        let canvas = parse_canvas("4x4").unwrap();
+        let result = fit_canvas(&image, canvas, Fit::Contain, WHITE, false);
+        assert_eq!(result.dimensions(), (4, 4));
+        for x in 0..4 {
+            assert_eq!(result.get_pixel(x, 0), &WHITE);
+            assert_eq!(result.get_pixel(x, 1), &RED);
+            assert_eq!(result.get_pixel(x, 2), &RED);
+            assert_eq!(result.get_pixel(x, 3), &WHITE);
+        }
+    }
+
+    #[test]
+    fn test_fit_cover_crop() {
+        let image = RgbaImage::from_fn(4, 2, |x, _| if x < 2 { RED } else { WHITE });
+        let result = fit_canvas(&image, parse_canvas("2x2").unwrap(), Fit::Cover, Rgba([0; 4]), false);
+        assert_eq!(result.dimensions(), (2, 2));
+        assert_eq!(result.get_pixel(0, 0), &RED);
+        assert_eq!(result.get_pixel(1, 1), &WHITE);
+    }
+
+    #[test]
+    fn test_parse_canvas_and_background() {
+        assert_eq!(parse_canvas("800x600").unwrap(), CanvasSize { width: 800, height: 600 });
+        assert!(parse_canvas("800").is_err());
+        assert!(parse_canvas("0x10").is_err());
+        assert_eq!(parse_background("#ff8000").unwrap(), Rgba([255, 128, 0, 255]));
+        assert_eq!(parse_background("00000080").unwrap(), Rgba([0, 0, 0, 128]));
+        assert!(parse_background("fff").is_err());
+    }
+
+    #[test]
+    fn test_linear_resize_checkerboard_brighter() {
+        let black = Rgba([0, 0, 0, 255]);
+        let checkerboard = RgbaImage::from_fn(8, 8, |x, y| if (x + y) % 2 == 0 { WHITE } else { black });
+        let srgb = resize(&checkerboard, 4, 4, false);
+        let linear = resize(&checkerboard, 4, 4, true);
+        for (srgb, linear) in srgb.pixels().zip(linear.pixels()) {
+            // Среднее sRGB около 128, среднее в линейной яркости около 188
+            assert!((120..=135).contains(&srgb[0]), "srgb {}", srgb[0]);
+            assert!(linear[0] > srgb[0] + 40, "linear {} srgb {}", linear[0], srgb[0]);
+            assert_eq!(linear[3], 255);
+        }
+    }
+
+    #[test]
+    fn test_capped_size() {
+        assert_eq!(capped_size(40, 30, 1200), None);
+        assert_eq!(capped_size(40, 30, 300), Some((20, 15)));
+        assert_eq!(capped_size(1000, 1, 10), Some((10, 1)));
+        assert_eq!(capped_size(7, 5, 1), Some((1, 1)));
+        let (width, height) = capped_size(4000, 3000, 1_000_000).unwrap();
+        assert!(u64::from(width) * u64::from(height) <= 1_000_000);
+        assert_eq!((width, height), (1154, 866));
+    }
+}
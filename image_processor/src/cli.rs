@@ -2,29 +2,136 @@
 //!
 //! Предоставляет функциональность парметров командной строки
 
+use crate::animation::DEFAULT_FRAME_DELAY_MS;
+use crate::canvas::{CanvasSize, Fit, parse_background, parse_canvas};
+use crate::raw::RawFormat;
+use image::Rgba;
 use std::path::PathBuf;
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use clap_complete::Shell;
 //output: PathBuf
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 #[command(name = "image-processor")]
 #[command(about = "Image processor application", version = "1.0")]
+#[command(args_override_self = true)]
+#[command(subcommand_negates_reqs = true, args_conflicts_with_subcommands = true)]
 pub(crate) struct Cli {
+    /// Host-only commands that run instead of image processing
+    #[command(subcommand)]
+    pub(crate) command: Option<Command>,
     /// Path image file
+    #[arg(long, required_unless_present_any = ["manifest", "list_formats"])]
+    pub(crate) input: Option<PathBuf>,
+    /// Path store converted image, not required with `--no-save`
+    #[arg(long, required_unless_present_any = ["no_save", "manifest", "info", "list_formats"], conflicts_with = "no_save")]
+    pub(crate) output: Option<PathBuf>,
+    /// Name plugin, not required with host-only operations (`--info`)
     #[arg(long)]
-    pub(crate) input: PathBuf,
-    /// Path store converted image
-    #[arg(long)]
-    pub(crate) output: PathBuf,
-    /// Name plugin
-    #[arg(long)]
-    pub(crate) plugin: PathBuf,
-    /// Path config file for plugin
-    #[arg(long)]
-    pub(crate) params: PathBuf,
+    pub(crate) plugin: Option<PathBuf>,
+    /// Path config file for plugin, `-` reads config from stdin
+    #[arg(long, required_unless_present_any = ["manifest", "info", "preset", "list_formats"])]
+    pub(crate) params: Option<PathBuf>,
     /// Plugin directory path
-    #[arg(long)]
-    pub(crate) plugin_path: PathBuf,
+    #[arg(long, required_unless_present_any = ["info", "list_formats"])]
+    pub(crate) plugin_path: Option<PathBuf>,
     /// Log level (info, warn, error, debug, trace), default info
     #[arg(long, default_value = "error", required = false)]
     pub(crate) log_level: String,
+    /// Write log timestamps in UTC instead of local time
+    #[arg(long)]
+    pub(crate) log_utc: bool,
+    /// Path to write JSON run report (paths, dimensions, plugin, params, timings, result)
+    #[arg(long)]
+    pub(crate) report: Option<PathBuf>,
+    /// Fail if the plugin logs any warning or error during processing
+    #[arg(long)]
+    pub(crate) strict: bool,
+    /// Validate params against the JSON Schema exported by the plugin before calling it
+    #[arg(long)]
+    pub(crate) validate_schema: bool,
+    /// If processing fails after decoding, save the current buffer to <output>.partial.png
+    #[arg(long, conflicts_with = "no_save")]
+    pub(crate) save_on_error: bool,
+    /// Run decode and plugin processing without encoding and saving the image
+    #[arg(long)]
+    pub(crate) no_save: bool,
+    /// Do not write the output if plugin processing left the image bytes unchanged
+    #[arg(long, conflicts_with = "no_save")]
+    pub(crate) skip_unchanged: bool,
+    /// CSV manifest with `input,output,plugin,params` columns, one job per row
+    #[arg(long, conflicts_with_all = ["input", "output", "plugin", "params"])]
+    pub(crate) manifest: Option<PathBuf>,
+    /// Compose the processed image onto a canvas of exact size WxH
+    #[arg(long, value_parser = parse_canvas)]
+    pub(crate) canvas: Option<CanvasSize>,
+    /// How to fit the image into the canvas (contain - letterbox, cover - crop)
+    #[arg(long, value_enum, default_value_t = Fit::Contain, requires = "canvas")]
+    pub(crate) fit: Fit,
+    /// Canvas background color RRGGBB or RRGGBBAA
+    #[arg(long, value_parser = parse_background, default_value = "000000", requires = "canvas")]
+    pub(crate) background: Rgba<u8>,
+    /// Resample for --canvas in linear light (slower, avoids darkening fine detail)
+    #[arg(long, requires = "canvas")]
+    pub(crate) linear_resize: bool,
+    /// Save as indexed PNG with a palette of N colors (2..=256, median cut)
+    #[arg(long, value_parser = clap::value_parser!(u16).range(2..=256))]
+    pub(crate) palette: Option<u16>,
+    /// Write plugins, params and timestamp into PNG text chunks (PNG output only)
+    #[arg(long)]
+    pub(crate) embed_metadata: bool,
+    /// Print input dimensions, color type, bit depth and alpha, then exit without processing
+    #[arg(long, conflicts_with = "manifest")]
+    pub(crate) info: bool,
+    /// Print image formats this build can read and write, then exit
+    #[arg(long, conflicts_with_all = ["manifest", "info", "input"])]
+    pub(crate) list_formats: bool,
+    /// Write an animated PNG with a frame after every plugin pass (--repeat) and preset stage
+    #[arg(long)]
+    pub(crate) animate: Option<PathBuf>,
+    /// Delay of each --animate frame in milliseconds
+    #[arg(long, default_value_t = DEFAULT_FRAME_DELAY_MS, requires = "animate")]
+    pub(crate) frame_delay: u16,
+    /// Apply the plugin N times to the buffer (N >= 1)
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    pub(crate) repeat: u32,
+    /// Run the named preset (ordered plugin stages) from the preset file instead of --plugin
+    #[arg(long, conflicts_with_all = ["plugin", "params", "manifest"])]
+    pub(crate) preset: Option<String>,
+    /// TOML file with presets
+    #[arg(long, default_value = "presets.toml", requires = "preset")]
+    pub(crate) preset_file: PathBuf,
+    /// Read --input (or stdin with `-`) as raw pixels of size WxH instead of decoding it
+    #[arg(long, value_parser = parse_canvas, conflicts_with_all = ["info", "manifest"])]
+    pub(crate) raw: Option<CanvasSize>,
+    /// Rotate and flip the decoded image to its EXIF orientation (lossless, no resampling)
+    #[arg(long, conflicts_with = "raw")]
+    pub(crate) auto_orient: bool,
+    /// Downscale inputs larger than N pixels to fit N before running plugins
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    pub(crate) max_process_pixels: Option<u64>,
+    /// Upscale the processed image back to the input size (with --max-process-pixels)
+    #[arg(long, requires = "max_process_pixels")]
+    pub(crate) restore_size: bool,
+    /// Pixel format of --raw input
+    #[arg(long, value_enum, default_value_t = RawFormat::Rgba8, requires = "raw")]
+    pub(crate) raw_format: RawFormat,
+    /// Do not draw progress bars (they are also hidden when stderr is not a terminal)
+    #[arg(long)]
+    pub(crate) quiet: bool,
+    /// Job of a --manifest batch: progress is shown per job, not per plugin call
+    #[arg(skip)]
+    pub(crate) batch_job: bool,
+    /// TOML file with any of the options above; flags given on the command line override it
+    #[arg(long)]
+    pub(crate) config: Option<PathBuf>,
+}
+
+/// Команды хоста, выполняемые вместо обработки изображения
+#[derive(Subcommand, Debug, Clone)]
+pub(crate) enum Command {
+    /// Print a shell completion script to stdout
+    Completions {
+        /// Target shell
+        shell: Shell,
+    },
 }
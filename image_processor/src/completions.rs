@@ -0,0 +1,43 @@
+//! Модуль для генерации скриптов автодополнения
+//!
+//! Предоставляет функциональность `image-processor completions bash|zsh|fish`: скрипт
+//! строится по описанию параметров командной строки и выводится в стандартный вывод
+
+use crate::cli::Cli;
+use clap::CommandFactory;
+use clap_complete::{Shell, generate};
+use std::io::Write;
+
+///
+/// Запись скрипта автодополнения
+///
+/// # Параметры
+///
+/// * `shell` - командная оболочка
+/// * `out` - приемник скрипта (стандартный вывод)
+///
+pub(crate) fn write_completions(shell: Shell, out: &mut impl Write) {
+    let mut command = Cli::command();
+    let name = command.get_name().to_string();
+    generate(shell, &mut command, name, out);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Command;
+    use clap::Parser;
+
+    #[test]
+    fn test_bash_completions() {
+        let cli = Cli::try_parse_from(["image-processor", "completions", "bash"]).unwrap();
+        let Some(Command::Completions { shell }) = cli.command else {
+            panic!("completions subcommand not parsed");
+        };
+        let mut out = Vec::new();
+        write_completions(shell, &mut out);
+        let script = String::from_utf8(out).unwrap();
+        assert!(script.contains("image-processor"));
+        assert!(script.contains("--plugin-path"));
+    }
+}
@@ -12,10 +12,49 @@ pub(crate) enum ImageProcessorError {
     ImageReadError(#[from] image::ImageError),
     #[error("Library loading error: {0}")]
     PluginError(#[from] libloading::Error),
+    #[error("Plugin ABI mismatch: {0}")]
+    AbiMismatch(String),
     #[error("Error convert image from raw")]
     ConvertFromRawError,
+    #[error("Error convert image from raw: plugin left buffer of {actual} bytes, expected {expected}")]
+    BufferLengthMismatch { expected: usize, actual: usize },
     #[error("Params string NulError: {0}")]
     ParamsNulError(#[from] std::ffi::NulError),
     #[error("Logger setup failed")]
     LoggerSetupFailed,
+    #[error("Report serialization error: {0}")]
+    ReportError(#[from] serde_json::Error),
+    #[error("Strict mode: plugin logged {0} warnings or errors")]
+    StrictModeViolation(usize),
+    #[error("Strict mode: plugin does not export plugin_warning_count")]
+    StrictModeUnsupported,
+    #[error(
+        "No plugin specified: pass --plugin <name> with a library from --plugin-path, \
+         or use a host-only operation such as --info"
+    )]
+    PluginNotSpecified,
+    #[error("Missing argument {0}")]
+    MissingArgument(String),
+    #[error("Manifest error: {0}")]
+    ManifestError(String),
+    #[error("Manifest: {0} of {1} jobs failed")]
+    ManifestJobsFailed(usize, usize),
+    #[error("Config file error: {0}")]
+    ConfigFileError(String),
+    #[error("Preset error: {0}")]
+    PresetError(String),
+    #[error("Params schema validation failed:\n{0}")]
+    SchemaValidationError(String),
+    #[error("Params schema: plugin does not export plugin_params_schema")]
+    SchemaUnsupported,
+    #[error("Unsupported EXIF orientation {0}, expected 1..=8")]
+    UnsupportedOrientation(u16),
+    #[error("Raw input error: {0}")]
+    RawInputError(String),
+    #[error("--input and --params cannot both be read from stdin (`-`)")]
+    StdinConflict,
+    #[error("Image size error: {0}")]
+    ImageSizeError(#[from] plugins_support::error::Error),
+    #[error("PNG encoding error: {0}")]
+    PngEncodingError(#[from] png::EncodingError),
 }
@@ -0,0 +1,78 @@
+//! Модуль для вывода поддерживаемых форматов изображений
+//!
+//! Предоставляет функциональность `--list-formats`: список строится по `image::ImageFormat`,
+//! поддержка чтения и записи зависит от включенных features крейта `image` в этой сборке
+
+use image::ImageFormat;
+use std::io::{self, Write};
+
+/// Формат изображения с поддержкой чтения и записи в этой сборке
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct FormatSupport {
+    /// Формат
+    pub(crate) format: ImageFormat,
+    /// Декодирование доступно
+    pub(crate) read: bool,
+    /// Кодирование доступно
+    pub(crate) write: bool,
+}
+
+/// Форматы, для которых доступно чтение или запись
+pub(crate) fn supported_formats() -> Vec<FormatSupport> {
+    ImageFormat::all()
+        .map(|format| FormatSupport {
+            format,
+            read: format.reading_enabled(),
+            write: format.writing_enabled(),
+        })
+        .filter(|support| support.read || support.write)
+        .collect()
+}
+
+///
+/// Вывод таблицы форматов: имя, чтение, запись, расширения файлов
+///
+/// # Параметры
+///
+/// * `out` - приемник (стандартный вывод)
+///
+pub(crate) fn write_formats(out: &mut impl Write) -> io::Result<()> {
+    writeln!(out, "{:<10} {:<5} {:<5} Extensions", "Format", "Read", "Write")?;
+    for support in supported_formats() {
+        let mark = |enabled: bool| if enabled { "yes" } else { "no" };
+        writeln!(
+            out,
+            "{:<10} {:<5} {:<5} {}",
+            format!("{:?}", support.format).to_uppercase(),
+            mark(support.read),
+            mark(support.write),
+            support.format.extensions_str().join(", ")
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Cli;
+    use clap::Parser;
+
+    #[test]
+    fn test_png_readable_and_writable() {
+        let mut out = Vec::new();
+        write_formats(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let png = out.lines().find(|line| line.starts_with("PNG ")).unwrap();
+        let columns: Vec<&str> = png.split_whitespace().collect();
+        assert_eq!(&columns[..3], ["PNG", "yes", "yes"]);
+        assert!(png.contains("png"));
+    }
+
+    #[test]
+    fn test_list_formats_without_other_args() {
+        let cli = Cli::try_parse_from(["image-processor", "--list-formats"]).unwrap();
+        assert!(cli.list_formats);
+        assert!(Cli::try_parse_from(["image-processor", "--list-formats", "--info"]).is_err());
+    }
+}
@@ -0,0 +1,130 @@
+//! Модуль для вывода сведений о входном изображении
+//!
+//! Предоставляет функциональность `--info`: размеры, `ColorType`, глубина канала, наличие
+//! альфа-канала и предупреждение о потере точности при передаче плагину
+
+use crate::error::ImageProcessorError;
+use image::{ColorType, ImageDecoder, ImageReader};
+use std::fmt;
+use std::path::Path;
+
+/// Сведения о входном изображении
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ImageInfo {
+    /// Ширина в пикселях
+    pub(crate) width: u32,
+    /// Высота в пикселях
+    pub(crate) height: u32,
+    /// Тип цвета декодера
+    pub(crate) color_type: ColorType,
+}
+
+impl ImageInfo {
+    /// Количество бит на канал
+    pub(crate) fn bits_per_channel(&self) -> u16 {
+        bits_per_channel(self.color_type)
+    }
+}
+
+impl fmt::Display for ImageInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Dimensions: {}x{}", self.width, self.height)?;
+        writeln!(f, "Color type: {:?}", self.color_type)?;
+        writeln!(f, "Bits per channel: {}", self.bits_per_channel())?;
+        write!(f, "Alpha: {}", if self.color_type.has_alpha() { "yes" } else { "no" })
+    }
+}
+
+/// Количество бит на канал для типа цвета
+fn bits_per_channel(color_type: ColorType) -> u16 {
+    color_type.bits_per_pixel() / color_type.channel_count() as u16
+}
+
+///
+/// Предупреждение о понижении точности
+///
+/// Плагины экспортируют только `process_image` для RGBA8 буфера, поэтому изображение
+/// с глубиной канала больше 8 бит перед вызовом плагина преобразуется в 8 бит
+///
+/// # Параметры
+///
+/// * `color_type` - тип цвета входного изображения
+///
+/// # Возращает
+/// Текст предупреждения или `None`, если точность не теряется
+///
+pub(crate) fn precision_warning(color_type: ColorType) -> Option<String> {
+    let bits = bits_per_channel(color_type);
+    (bits > 8).then(|| {
+        format!(
+            "{}-bit input ({:?}) will be down-converted to 8-bit: no plugin entry point for more than 8 bits per channel",
+            bits, color_type
+        )
+    })
+}
+
+///
+/// Чтение сведений об изображении без декодирования пикселей
+///
+/// # Параметры
+///
+/// * `path` - путь к изображению
+///
+pub(crate) fn describe(path: &Path) -> Result<ImageInfo, ImageProcessorError> {
+    let decoder = ImageReader::open(path)?.with_guessed_format()?.into_decoder()?;
+    let (width, height) = decoder.dimensions();
+    Ok(ImageInfo {
+        width,
+        height,
+        color_type: decoder.color_type(),
+    })
+}
+
+///
+/// Вывод сведений об изображении (`--info`)
+///
+/// # Параметры
+///
+/// * `path` - путь к изображению
+///
+pub(crate) fn print_info(path: &Path) -> Result<ImageInfo, ImageProcessorError> {
+    let info = describe(path)?;
+    println!("Input: {}", path.display());
+    println!("{}", info);
+    if let Some(warning) = precision_warning(info.color_type) {
+        log::warn!("{}", warning);
+        println!("Warning: {}", warning);
+    }
+    Ok(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageBuffer, Rgb, RgbaImage};
+
+    #[test]
+    fn test_info_16_bit_png() {
+        let path = std::env::temp_dir().join("image_processor_info_16.png");
+        ImageBuffer::<Rgb<u16>, Vec<u16>>::from_pixel(3, 2, Rgb([1000, 20000, 65535]))
+            .save(&path)
+            .unwrap();
+        let info = print_info(&path).unwrap();
+        assert_eq!((info.width, info.height), (3, 2));
+        assert_eq!(info.color_type, ColorType::Rgb16);
+        assert_eq!(info.bits_per_channel(), 16);
+        assert!(!info.color_type.has_alpha());
+        let warning = precision_warning(info.color_type).unwrap();
+        assert!(warning.contains("down-converted to 8-bit"));
+    }
+
+    #[test]
+    fn test_info_8_bit_no_warning() {
+        let path = std::env::temp_dir().join("image_processor_info_8.png");
+        RgbaImage::from_pixel(2, 2, image::Rgba([1, 2, 3, 4])).save(&path).unwrap();
+        let info = describe(&path).unwrap();
+        assert_eq!(info.color_type, ColorType::Rgba8);
+        assert_eq!(info.bits_per_channel(), 8);
+        assert!(precision_warning(info.color_type).is_none());
+    }
+}
@@ -0,0 +1,200 @@
+//! Модуль для чтения и записи изображений
+//!
+//! Предоставляет общие для всех режимов функции декодирования изображения в RGBA буфер
+//! и кодирования RGBA буфера в файл: формат по расширению или явно, качество JPEG,
+//! атомарная запись (через временный файл в той же директории)
+
+use crate::error::ImageProcessorError;
+use crate::orientation::exif_orientation;
+use image::codecs::jpeg::JpegEncoder;
+use image::{
+    ColorType, DynamicImage, ImageBuffer, ImageDecoder, ImageFormat, ImageReader, Rgba, RgbImage,
+};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Seek, Write};
+use std::path::{Path, PathBuf};
+
+/// Качество JPEG по умолчанию
+pub(crate) const DEFAULT_JPEG_QUALITY: u8 = 90;
+
+/// Декодированное изображение в виде RGBA8 буфера
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RgbaBuffer {
+    /// Ширина в пикселях
+    pub(crate) width: u32,
+    /// Высота в пикселях
+    pub(crate) height: u32,
+    /// Исходный тип цвета до преобразования в RGBA8
+    pub(crate) color: ColorType,
+    /// Значение тега ориентации EXIF, `None` - EXIF или тега нет
+    pub(crate) exif_orientation: Option<u16>,
+    /// Пиксели RGBA8 построчно, `width * height * 4` байт
+    pub(crate) data: Vec<u8>,
+}
+
+impl From<DynamicImage> for RgbaBuffer {
+    fn from(image: DynamicImage) -> Self {
+        RgbaBuffer {
+            width: image.width(),
+            height: image.height(),
+            color: image.color(),
+            exif_orientation: None,
+            data: image.into_rgba8().into_raw(),
+        }
+    }
+}
+
+///
+/// Декодирование изображения из файла в RGBA8 буфер
+///
+/// # Параметры
+///
+/// * `path` - путь к изображению, формат определяется по содержимому
+///
+pub(crate) fn load_rgba(path: &Path) -> Result<RgbaBuffer, ImageProcessorError> {
+    load_rgba_from(BufReader::new(File::open(path)?))
+}
+
+///
+/// Декодирование изображения из потока в RGBA8 буфер
+///
+/// # Параметры
+///
+/// * `reader` - поток с закодированным изображением, формат определяется по содержимому
+///
+/// Значение тега ориентации EXIF сохраняется в `exif_orientation`, пиксели не поворачиваются
+///
+pub(crate) fn load_rgba_from(reader: impl BufRead + Seek) -> Result<RgbaBuffer, ImageProcessorError> {
+    let mut decoder = ImageReader::new(reader).with_guessed_format()?.into_decoder()?;
+    let exif_orientation = decoder.exif_metadata()?.as_deref().and_then(exif_orientation);
+    Ok(RgbaBuffer {
+        exif_orientation,
+        ..DynamicImage::from_decoder(decoder)?.into()
+    })
+}
+
+/// Временный файл для атомарной записи `dest`
+fn temp_path(dest: &Path) -> PathBuf {
+    let mut name = std::ffi::OsString::from(".");
+    name.push(dest.file_name().unwrap_or(dest.as_os_str()));
+    name.push(".tmp");
+    dest.with_file_name(name)
+}
+
+fn encode(
+    image: &ImageBuffer<Rgba<u8>, &[u8]>,
+    writer: &mut BufWriter<File>,
+    format: ImageFormat,
+    quality: Option<u8>,
+) -> Result<(), ImageProcessorError> {
+    match format {
+        // JPEG не поддерживает альфа-канал: он отбрасывается
+        ImageFormat::Jpeg => {
+            let rgb = RgbImage::from_fn(image.width(), image.height(), |x, y| {
+                let [r, g, b, _] = image.get_pixel(x, y).0;
+                image::Rgb([r, g, b])
+            });
+            let quality = quality.unwrap_or(DEFAULT_JPEG_QUALITY).clamp(1, 100);
+            JpegEncoder::new_with_quality(&mut *writer, quality).encode_image(&rgb)?;
+        }
+        format => {
+            if quality.is_some() {
+                log::debug!("Quality is ignored for {:?}", format);
+            }
+            image.write_to(&mut *writer, format)?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+///
+/// Кодирование RGBA8 буфера в файл
+///
+/// Файл записывается во временный файл в той же директории и переименовывается,
+/// поэтому при ошибке кодирования существующий `dest` не повреждается
+///
+/// # Параметры
+///
+/// * `data` - пиксели RGBA8 построчно
+/// * `width` - ширина в пикселях
+/// * `height` - высота в пикселях
+/// * `dest` - путь к выходному файлу
+/// * `format` - формат, `None` - по расширению `dest`
+/// * `quality` - качество JPEG 1..=100 (по умолчанию [`DEFAULT_JPEG_QUALITY`]),
+///   для остальных форматов не используется
+///
+pub(crate) fn save_rgba(
+    data: &[u8],
+    width: u32,
+    height: u32,
+    dest: &Path,
+    format: Option<ImageFormat>,
+    quality: Option<u8>,
+) -> Result<(), ImageProcessorError> {
+    let image = ImageBuffer::<Rgba<u8>, &[u8]>::from_raw(width, height, data).ok_or_else(|| {
+        log::error!("RGBA buffer of {} bytes does not match {}x{}", data.len(), width, height);
+        ImageProcessorError::ConvertFromRawError
+    })?;
+    let format = match format {
+        Some(format) => format,
+        None => ImageFormat::from_path(dest)?,
+    };
+    let temp = temp_path(dest);
+    let mut writer = BufWriter::new(File::create(&temp)?);
+    let result = encode(&image, &mut writer, format, quality);
+    drop(writer);
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temp);
+        return Err(e);
+    }
+    fs::rename(&temp, dest)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn gradient() -> (Vec<u8>, u32, u32) {
+        let (width, height) = (6, 4);
+        let data = (0..width * height)
+            .flat_map(|pixel| [(pixel * 10) as u8, 128, (255 - pixel * 10) as u8, 255])
+            .collect();
+        (data, width, height)
+    }
+
+    #[test]
+    fn test_png_round_trip() {
+        let (data, width, height) = gradient();
+        let path = std::env::temp_dir().join("image_processor_io.png");
+        save_rgba(&data, width, height, &path, None, None).unwrap();
+        assert!(!temp_path(&path).exists());
+        let image = load_rgba(&path).unwrap();
+        assert_eq!((image.width, image.height, image.color), (width, height, ColorType::Rgba8));
+        assert_eq!(image.data, data);
+        let image = load_rgba_from(Cursor::new(fs::read(&path).unwrap())).unwrap();
+        assert_eq!(image.data, data);
+    }
+
+    #[test]
+    fn test_jpeg_round_trip() {
+        let (width, height) = (16, 8);
+        let data = [200, 120, 40, 255].repeat((width * height) as usize);
+        let path = std::env::temp_dir().join("image_processor_io.jpeg");
+        save_rgba(&data, width, height, &path, Some(ImageFormat::Jpeg), Some(95)).unwrap();
+        let image = load_rgba(&path).unwrap();
+        assert_eq!((image.width, image.height, image.color), (width, height, ColorType::Rgb8));
+        for (decoded, source) in image.data.iter().zip(&data) {
+            assert!(decoded.abs_diff(*source) <= 4, "decoded {} source {}", decoded, source);
+        }
+    }
+
+    #[test]
+    fn test_save_rgba_invalid_buffer() {
+        let path = std::env::temp_dir().join("image_processor_io_invalid.png");
+        assert!(save_rgba(&[0; 7], 2, 1, &path, None, None).is_err());
+        assert!(save_rgba(&[0; 8], 2, 1, Path::new("image_processor_io.unknown"), None, None).is_err());
+    }
+}
@@ -0,0 +1,273 @@
+//! Модуль для файла конфигурации запуска
+//!
+//! Предоставляет функциональность `--config job.toml`: файл задает любые параметры командной
+//! строки, явно указанные флаги имеют приоритет над значениями из файла
+//!
+//! ```toml
+//! input = "images.jpeg"
+//! output = "blur.png"
+//! plugin = "libblur_plugin"
+//! params = "blur-plugin-config.json"
+//! plugin_path = "target/debug"
+//! log_level = "info"
+//! ```
+//!
+//! Относительные пути файла разрешаются от директории файла конфигурации, см. [`resolve_path`]
+
+use crate::error::ImageProcessorError;
+use crate::params::is_stdin;
+use serde::Deserialize;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Флаг командной строки с путем к файлу конфигурации
+const CONFIG_FLAG: &str = "--config";
+
+/// Параметры запуска из файла конфигурации, поля совпадают с параметрами командной строки
+#[derive(Deserialize, Debug, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct JobConfig {
+    input: Option<PathBuf>,
+    output: Option<PathBuf>,
+    plugin: Option<PathBuf>,
+    params: Option<PathBuf>,
+    plugin_path: Option<PathBuf>,
+    log_level: Option<String>,
+    log_utc: Option<bool>,
+    report: Option<PathBuf>,
+    strict: Option<bool>,
+    validate_schema: Option<bool>,
+    no_save: Option<bool>,
+    skip_unchanged: Option<bool>,
+    save_on_error: Option<bool>,
+    manifest: Option<PathBuf>,
+    canvas: Option<String>,
+    fit: Option<String>,
+    background: Option<String>,
+    linear_resize: Option<bool>,
+    palette: Option<u16>,
+    embed_metadata: Option<bool>,
+    info: Option<bool>,
+    animate: Option<PathBuf>,
+    frame_delay: Option<u16>,
+    repeat: Option<u32>,
+    preset: Option<String>,
+    preset_file: Option<PathBuf>,
+    raw: Option<String>,
+    raw_format: Option<String>,
+    auto_orient: Option<bool>,
+    max_process_pixels: Option<u64>,
+    restore_size: Option<bool>,
+    quiet: Option<bool>,
+}
+
+impl JobConfig {
+    /// Разрешение относительных путей файла от директории `base`
+    fn resolve_paths(&mut self, base: &Path) {
+        let paths = [
+            &mut self.input,
+            &mut self.output,
+            &mut self.params,
+            &mut self.plugin_path,
+            &mut self.report,
+            &mut self.manifest,
+            &mut self.preset_file,
+            &mut self.animate,
+        ];
+        for path in paths.into_iter().flatten() {
+            *path = resolve_path(base, path);
+        }
+    }
+
+    /// Параметры командной строки, соответствующие файлу
+    fn args(&self) -> Vec<OsString> {
+        let mut args = Vec::new();
+        let mut value = |flag: &str, value: Option<OsString>| {
+            if let Some(value) = value {
+                args.push(OsString::from(flag));
+                args.push(value);
+            }
+        };
+        value("--input", self.input.clone().map(OsString::from));
+        value("--output", self.output.clone().map(OsString::from));
+        value("--plugin", self.plugin.clone().map(OsString::from));
+        value("--params", self.params.clone().map(OsString::from));
+        value("--plugin-path", self.plugin_path.clone().map(OsString::from));
+        value("--log-level", self.log_level.clone().map(OsString::from));
+        value("--report", self.report.clone().map(OsString::from));
+        value("--manifest", self.manifest.clone().map(OsString::from));
+        value("--canvas", self.canvas.clone().map(OsString::from));
+        value("--fit", self.fit.clone().map(OsString::from));
+        value("--background", self.background.clone().map(OsString::from));
+        value("--palette", self.palette.map(|palette| palette.to_string().into()));
+        value("--animate", self.animate.clone().map(OsString::from));
+        value("--frame-delay", self.frame_delay.map(|delay| delay.to_string().into()));
+        value("--repeat", self.repeat.map(|repeat| repeat.to_string().into()));
+        value("--preset", self.preset.clone().map(OsString::from));
+        value("--preset-file", self.preset_file.clone().map(OsString::from));
+        value("--raw", self.raw.clone().map(OsString::from));
+        value("--raw-format", self.raw_format.clone().map(OsString::from));
+        value("--max-process-pixels", self.max_process_pixels.map(|pixels| pixels.to_string().into()));
+        let flags = [
+            ("--log-utc", self.log_utc),
+            ("--strict", self.strict),
+            ("--validate-schema", self.validate_schema),
+            ("--no-save", self.no_save),
+            ("--skip-unchanged", self.skip_unchanged),
+            ("--save-on-error", self.save_on_error),
+            ("--embed-metadata", self.embed_metadata),
+            ("--info", self.info),
+            ("--linear-resize", self.linear_resize),
+            ("--auto-orient", self.auto_orient),
+            ("--restore-size", self.restore_size),
+            ("--quiet", self.quiet),
+        ];
+        for (flag, enabled) in flags {
+            if enabled == Some(true) {
+                args.push(flag.into());
+            }
+        }
+        args
+    }
+}
+
+/// Путь к файлу конфигурации из аргументов (`--config PATH` или `--config=PATH`)
+fn config_path(args: &[OsString]) -> Option<PathBuf> {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == CONFIG_FLAG {
+            return iter.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.to_str().and_then(|arg| arg.strip_prefix("--config=")) {
+            return Some(PathBuf::from(path));
+        }
+    }
+    None
+}
+
+///
+/// Разрешение пути из файла конфигурации или манифеста
+///
+/// Абсолютный путь и `-` (стандартный ввод) возвращаются без изменений,
+/// относительный путь присоединяется к `base`
+///
+/// # Параметры
+///
+/// * `base` - директория файла, в котором указан путь
+/// * `path` - путь из файла
+///
+pub(crate) fn resolve_path(base: &Path, path: &Path) -> PathBuf {
+    if path.is_absolute() || is_stdin(path) {
+        path.to_path_buf()
+    } else {
+        base.join(path)
+    }
+}
+
+///
+/// Подстановка параметров из файла конфигурации
+///
+/// Параметры из файла вставляются перед аргументами командной строки, поэтому
+/// явно указанный флаг переопределяет значение из файла
+///
+/// # Параметры
+///
+/// * `args` - аргументы командной строки, первый - имя программы
+///
+/// # Возращает
+/// Аргументы для разбора или `ImageProcessorError::ConfigFileError`, если файл не разобран
+///
+pub(crate) fn expand_args(args: Vec<OsString>) -> Result<Vec<OsString>, ImageProcessorError> {
+    let Some(path) = config_path(&args) else {
+        return Ok(args);
+    };
+    let text = fs::read_to_string(&path)?;
+    let mut config: JobConfig = toml::from_str(&text)
+        .map_err(|e| ImageProcessorError::ConfigFileError(format!("{}: {}", path.display(), e)))?;
+    config.resolve_paths(path.parent().unwrap_or(Path::new("")));
+    let mut args = args.into_iter();
+    let mut expanded: Vec<OsString> = args.next().into_iter().collect();
+    expanded.extend(config.args());
+    expanded.extend(args);
+    Ok(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Cli;
+    use clap::Parser;
+
+    #[test]
+    fn test_config_file_overridden_by_flags() {
+        let path = std::env::temp_dir().join("image_processor_job.toml");
+        fs::write(
+            &path,
+            r#"
+            input = "images.jpeg"
+            output = "blur.png"
+            plugin = "libblur_plugin"
+            params = "blur-plugin-config.json"
+            plugin_path = "target/debug"
+            log_level = "debug"
+            repeat = 2
+            strict = true
+            "#,
+        )
+        .unwrap();
+        let args: Vec<OsString> = vec![
+            "image-processor".into(),
+            "--config".into(),
+            path.clone().into(),
+            "--log-level".into(),
+            "warn".into(),
+            "--output".into(),
+            "other.png".into(),
+        ];
+        let cli = Cli::try_parse_from(expand_args(args).unwrap()).unwrap();
+        assert_eq!(cli.input, Some(std::env::temp_dir().join("images.jpeg")));
+        assert_eq!(cli.plugin, Some(PathBuf::from("libblur_plugin")));
+        assert_eq!(cli.repeat, 2);
+        assert!(cli.strict);
+        assert_eq!(cli.log_level, "warn");
+        assert_eq!(cli.output, Some(PathBuf::from("other.png")));
+    }
+
+    #[test]
+    fn test_config_file_unknown_field() {
+        let path = std::env::temp_dir().join("image_processor_job_unknown.toml");
+        fs::write(&path, "threads = 4\n").unwrap();
+        let args: Vec<OsString> = vec!["image-processor".into(), format!("--config={}", path.display()).into()];
+        let error = expand_args(args).unwrap_err();
+        assert!(error.to_string().contains("threads"));
+    }
+
+    #[test]
+    fn test_config_relative_paths() {
+        let dir = std::env::temp_dir().join("image_processor_relative").join("jobs");
+        fs::create_dir_all(dir.join("plugins")).unwrap();
+        fs::write(dir.join("plugins").join(crate::validate::plugin_library_name(Path::new("libblur_plugin"))), "")
+            .unwrap();
+        let path = dir.join("job.toml");
+        fs::write(
+            &path,
+            r#"
+            input = "images/in.png"
+            output = "/tmp/out.png"
+            plugin = "libblur_plugin"
+            params = "-"
+            plugin_path = "plugins"
+            "#,
+        )
+        .unwrap();
+        let args: Vec<OsString> = vec!["image-processor".into(), "--config".into(), path.into()];
+        let cli = Cli::try_parse_from(expand_args(args).unwrap()).unwrap();
+        assert_eq!(cli.input, Some(dir.join("images/in.png")));
+        assert_eq!(cli.output, Some(PathBuf::from("/tmp/out.png")));
+        assert_eq!(cli.params, Some(PathBuf::from("-")));
+        assert_eq!(cli.plugin_path, Some(dir.join("plugins")));
+        let plugin = crate::validate::plugin_library(&cli, cli.plugin.as_ref().unwrap()).unwrap();
+        assert!(plugin.starts_with(dir.join("plugins")));
+    }
+}
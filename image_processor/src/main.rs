@@ -3,95 +3,964 @@
 //! Предоставляет функциональность по обработке изображений с подключаемыми плагинами
 
 #![warn(missing_docs)]
+mod animation;
+mod canvas;
 mod cli;
+mod completions;
 mod error;
+mod formats;
+mod info;
+mod io;
+mod job_config;
+mod manifest;
+mod metadata;
+mod orientation;
+#[cfg(test)]
+mod mock_plugin;
+mod palette;
+mod params;
+mod preset;
+mod progress;
+mod raw;
 mod plugin_loader;
+mod report;
+mod schema;
+mod strict;
+mod validate;
 
-use crate::cli::Cli;
+use crate::animation::Animation;
+use crate::canvas::{capped_size, fit_canvas, resize_buffer};
+use crate::cli::{Cli, Command};
+use crate::completions::write_completions;
 use crate::error::ImageProcessorError;
+use crate::formats::write_formats;
+use crate::info::{precision_warning, print_info};
+use crate::io::{RgbaBuffer, load_rgba, save_rgba};
+use crate::job_config::expand_args;
+use crate::manifest::parse_manifest;
+use crate::metadata::{ProcessingMetadata, is_png, write_png_with_metadata};
+use crate::orientation::reorient;
+use crate::palette::write_indexed_png;
+use crate::params::{ParamsHandle, inject_source_color_type, read_params, source_color_type};
+use crate::preset::preset_stages;
+use crate::progress::{PluginProgress, ProgressBar, plugin_progress};
+use crate::raw::read_raw;
+use crate::report::{RunReport, millis, write_reports};
+use crate::schema::validate_params;
+use crate::strict::check_strict;
+use crate::validate::{plugin_library, validate_cli, validate_input};
 use clap::Parser;
-use image::{ImageReader, RgbaImage};
+use image::RgbaImage;
 use plugin_loader::Plugin;
-use std::ffi::CString;
-use std::io::ErrorKind;
-use std::ops::Add;
-use std::{fs, io};
-use plugins_support::logger::{get_log_level, setup_logger};
+use std::fs;
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use plugins_support::image_size::rgba_len;
+use plugins_support::logger::{LogTimezone, get_log_level, set_log_timezone, setup_logger};
 
 const PKG_NAME: &str = env!("CARGO_PKG_NAME");
 
 fn main() -> Result<(), ImageProcessorError> {
-    let cli = Cli::parse();
+    let cli = Cli::parse_from(expand_args(std::env::args_os().collect())?);
+    if let Some(Command::Completions { shell }) = cli.command {
+        write_completions(shell, &mut std::io::stdout());
+        return Ok(());
+    }
+    if cli.list_formats {
+        write_formats(&mut std::io::stdout())?;
+        return Ok(());
+    }
     let file = PKG_NAME.to_owned() + ".log";
     let log_level_filter = get_log_level(&cli.log_level);
+    if cli.log_utc {
+        set_log_timezone(LogTimezone::Utc);
+    }
     if setup_logger(log_level_filter, &file).is_err()
     {
         return Err(ImageProcessorError::LoggerSetupFailed);
     }
     log::info!("Starting image processor");
-    if !cli.plugin_path.exists() {
-        log::error!("Could not find plugin {}", cli.plugin_path.to_string_lossy());
-        return Err(ImageProcessorError::PathNotExist(io::Error::new(
-            ErrorKind::NotFound,
-            "Path plugin not exists",
-        )));
-    }
-    let plugin_lib = cli.plugin.to_str().unwrap().to_owned();
-    #[cfg(target_os = "windows")]
-    let plugin_lib = plugin_lib.add(".dll");
-    #[cfg(target_os = "linux")]
-    let plugin_lib = plugin_lib.add(".so");
-    let plugin_path = cli.plugin_path.join(&plugin_lib);
-    log::info!("Plugin: {}", plugin_path.display());
-    if !plugin_path.exists() {
-        log::error!("Could not find plugin path {}", plugin_path.display());
-        return Err(ImageProcessorError::PathNotExist(io::Error::new(
-            ErrorKind::NotFound,
-            "Lib plugin not exists",
-        )));
-    }
-    if !cli.input.exists() {
-        log::error!("Could not find image {}", cli.input.to_string_lossy());
-        return Err(ImageProcessorError::PathNotExist(io::Error::new(
-            ErrorKind::NotFound,
-            "Image not exists",
-        )));
-    }
-    if !cli.params.exists() {
-        log::error!("Could not find params file {}", cli.params.to_string_lossy());
-        return Err(ImageProcessorError::PathNotExist(io::Error::new(
-            ErrorKind::NotFound,
-            "Params file not exists",
-        )));
-    }
-    log::info!("Image input: {}", cli.input.to_string_lossy());
-    log::info!(
-        "Image plugin lib: {}",
-        plugin_path.to_str().unwrap_or("unknown")
-    );
-    let params = fs::read_to_string(cli.params)?;
-    let image = ImageReader::open(&cli.input)?.decode()?;
-    let mut rgba_img = image.to_rgba8().to_vec();
-    let plugin = Plugin::new(plugin_path.to_str().unwrap())?;
-    let plugin = plugin.interface()?;
-    let params_cstring = CString::new(params)?;
-    unsafe {
-        (plugin.process_image)(
-            image.width(),
-            image.height(),
-            rgba_img.as_mut_ptr(),
-            params_cstring.as_ptr(),
-        );
+    if cli.info {
+        let input = cli
+            .input
+            .as_ref()
+            .ok_or_else(|| ImageProcessorError::MissingArgument("--input".to_owned()))?;
+        print_info(input)?;
+        return Ok(());
+    }
+    run_with_report(&cli)
+}
+
+/// Обработка одного изображения или манифеста и запись отчета `--report`
+fn run_with_report(cli: &Cli) -> Result<(), ImageProcessorError> {
+    let start = Instant::now();
+    let mut report = RunReport::new(cli);
+    let mut job_reports = Vec::new();
+    let result = match &cli.manifest {
+        Some(manifest) => run_manifest(cli, manifest, &mut job_reports),
+        None => run(cli, &mut report, &mut HashMap::new()),
+    };
+    if let Err(e) = &result {
+        eprintln!("Error: {}", e);
+    }
+    match &cli.report {
+        // В пакетном режиме отчет - массив отчетов заданий
+        Some(report_path) if cli.manifest.is_some() => write_reports(&job_reports, report_path)?,
+        Some(report_path) => {
+            report.finish(&result, start.elapsed());
+            report.write(report_path)?;
+        }
+        None => {}
+    }
+    result
+}
+
+/// Проверка, что длина буфера равна `width * height * 4` перед каждой передачей буфера
+/// плагину: плагин получает только указатель и записывает `width * height * 4` байт
+fn check_buffer_len(buf: &[u8], expected: usize) -> Result<(), ImageProcessorError> {
+    if buf.len() != expected {
+        log::error!("RGBA buffer length {} bytes, expected {}", buf.len(), expected);
+        return Err(ImageProcessorError::BufferLengthMismatch {
+            expected,
+            actual: buf.len(),
+        });
+    }
+    Ok(())
+}
+
+/// Пакетная обработка по манифесту, отчет каждого задания добавляется в `reports`
+fn run_manifest(cli: &Cli, manifest: &Path, reports: &mut Vec<RunReport>) -> Result<(), ImageProcessorError> {
+    let mut jobs = parse_manifest(&fs::read_to_string(manifest)?)?;
+    let base = manifest.parent().unwrap_or(Path::new(""));
+    jobs.iter_mut().for_each(|job| job.resolve_paths(base));
+    log::info!("Manifest {}: {} jobs", manifest.display(), jobs.len());
+    let mut failed = 0;
+    // Библиотека плагина загружается один раз на весь манифест
+    let mut plugins = HashMap::new();
+    let mut bar = ProgressBar::for_stderr(format!("{} jobs", jobs.len()), jobs.len() as u64, cli.quiet);
+    for (index, job) in jobs.iter().enumerate() {
+        let job_cli = job.cli(cli);
+        let mut report = RunReport::new(&job_cli);
+        let started = Instant::now();
+        let result = run(&job_cli, &mut report, &mut plugins);
+        report.finish(&result, started.elapsed());
+        reports.push(report);
+        if let Some(bar) = bar.as_mut() {
+            bar.clear();
+        }
+        match result {
+            Ok(()) => println!("Job {} ({}): OK", job.line, job.input.display()),
+            Err(e) => {
+                failed += 1;
+                log::error!("Job {} ({}) failed: {}", job.line, job.input.display(), e);
+                println!("Job {} ({}): FAILED: {}", job.line, job.input.display(), e);
+            }
+        }
+        if let Some(bar) = bar.as_mut() {
+            bar.set(index as u64 + 1);
+        }
+    }
+    if let Some(bar) = bar {
+        bar.finish();
+    }
+    if failed > 0 {
+        return Err(ImageProcessorError::ManifestJobsFailed(failed, jobs.len()));
+    }
+    Ok(())
+}
+
+/// Этап обработки: библиотека плагина и его параметры
+struct Stage {
+    /// Полный путь к библиотеке плагина
+    plugin: PathBuf,
+    /// Параметры плагина (JSON)
+    params: String,
+}
+
+/// Имя библиотеки этапа для подписи полосы прогресса
+fn stage_name(stage: &Stage) -> String {
+    stage.plugin.file_stem().unwrap_or(stage.plugin.as_os_str()).to_string_lossy().into_owned()
+}
+
+/// Входное изображение и этапы обработки: один плагин или этапы пресета
+fn stages(cli: &Cli, report: &mut RunReport) -> Result<(PathBuf, Vec<Stage>), ImageProcessorError> {
+    let Some(preset) = &cli.preset else {
+        let paths = validate_cli(cli)?;
+        let params = read_params(&paths.params, std::io::stdin().lock())?;
+        report.set_params(&params);
+        return Ok((paths.input, vec![Stage { plugin: paths.plugin, params }]));
+    };
+    let input = validate_input(cli)?;
+    let stages = preset_stages(&fs::read_to_string(&cli.preset_file)?, preset)?;
+    log::info!("Preset {}: {} stages", preset, stages.len());
+    let stages = stages
+        .iter()
+        .map(|stage| {
+            Ok(Stage {
+                plugin: plugin_library(cli, &stage.plugin)?,
+                params: stage.params_json()?,
+            })
+        })
+        .collect::<Result<Vec<_>, ImageProcessorError>>()?;
+    let params: Vec<&str> = stages.iter().map(|stage| stage.params.as_str()).collect();
+    report.set_params(&format!("[{}]", params.join(",")));
+    Ok((input, stages))
+}
+
+/// Применение этапов обработки к RGBA буферу `image` длиной `len` байт.
+/// Длина проверяется перед каждым вызовом плагина, после каждого прохода
+/// буфер добавляется кадром в `animation`
+fn apply_stages(
+    cli: &Cli,
+    stages: &[Stage],
+    plugins: &mut HashMap<PathBuf, Plugin>,
+    image: &mut RgbaBuffer,
+    len: usize,
+    mut animation: Option<&mut Animation>,
+) -> Result<(), ImageProcessorError> {
+    for stage in stages {
+        log::info!("Image plugin lib: {}", stage.plugin.display());
+        let plugin = match plugins.entry(stage.plugin.clone()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(Plugin::new(&stage.plugin)?),
+        };
+        let plugin = plugin.interface()?;
+        if cli.validate_schema {
+            validate_params(plugin.params_schema().as_deref(), &stage.params)?;
+        }
+        let params = inject_source_color_type(&stage.params, source_color_type(image.color));
+        let params = ParamsHandle::new(params)?;
+        let warnings_before = plugin.warning_count();
+        for pass in 1..=cli.repeat {
+            log::debug!("Plugin pass {} of {}", pass, cli.repeat);
+            let label = format!("{} {}/{}", stage_name(stage), pass, cli.repeat);
+            let bar = ProgressBar::for_stderr(label, 100, cli.quiet || cli.batch_job);
+            check_buffer_len(&image.data, len)?;
+            unsafe {
+                match bar {
+                    Some(bar) => {
+                        let _progress = PluginProgress::install(bar);
+                        plugin.process_image_with_progress(
+                            image.width,
+                            image.height,
+                            image.data.as_mut_ptr(),
+                            &params,
+                            plugin_progress,
+                        );
+                    }
+                    None => plugin.process_image(image.width, image.height, image.data.as_mut_ptr(), &params),
+                }
+            }
+            if let Some(animation) = animation.as_deref_mut() {
+                animation.push(&image.data);
+            }
+        }
+        if cli.strict {
+            let warnings_after = plugin.warning_count();
+            check_strict(warnings_before, warnings_after)?;
+        }
     }
-    let image = RgbaImage::from_raw(image.width(), image.height(), rgba_img);
+    Ok(())
+}
+
+/// Путь частичного результата: `<output>.partial.png` в директории выходного файла
+fn partial_path(output: &Path) -> PathBuf {
+    let mut name = output.file_stem().unwrap_or(output.as_os_str()).to_owned();
+    name.push(".partial.png");
+    output.with_file_name(name)
+}
+
+/// Сохранение содержимого буфера после ошибки обработки (`--save-on-error`).
+/// Ошибки сохранения только записываются в лог, чтобы не скрыть исходную ошибку
+fn save_partial(image: &RgbaBuffer, output: &Path) {
+    let path = partial_path(output);
+    match save_rgba(&image.data, image.width, image.height, &path, None, None) {
+        Ok(()) => {
+            println!("Partial image saved to {}", path.display());
+            log::warn!("Processing failed, partial image saved to {}", path.display());
+        }
+        Err(e) => log::error!("Partial output {} not saved: {}", path.display(), e),
+    }
+}
+
+fn run(
+    cli: &Cli,
+    report: &mut RunReport,
+    plugins: &mut HashMap<PathBuf, Plugin>,
+) -> Result<(), ImageProcessorError> {
+    let (input, stages) = stages(cli, report)?;
+    log::info!("Image input: {}", input.to_string_lossy());
+    let stage = Instant::now();
+    let mut image = match cli.raw {
+        Some(size) => RgbaBuffer::from(read_raw(&input, size, cli.raw_format, std::io::stdin().lock())?),
+        None => load_rgba(&input)?,
+    };
+    if let Some(warning) = precision_warning(image.color) {
+        log::warn!("{}", warning);
+    }
+    if cli.auto_orient
+        && let Some(orientation) = image.exif_orientation
+    {
+        image = reorient(image, orientation)?;
+    }
+    let input_size = (image.width, image.height);
+    if let Some(max_pixels) = cli.max_process_pixels
+        && let Some((width, height)) = capped_size(image.width, image.height, max_pixels)
+    {
+        log::info!("Input exceeds {} pixels (--max-process-pixels)", max_pixels);
+        image = resize_buffer(image, width, height, cli.linear_resize)?;
+    }
+    let len = rgba_len(image.width, image.height)?;
+    log::debug!("RGBA buffer size {} bytes", len);
+    check_buffer_len(&image.data, len)?;
+    report.width = Some(input_size.0);
+    report.height = Some(input_size.1);
+    report.timings.decode_ms = millis(stage.elapsed());
+    let started = Instant::now();
+    let mut animation = cli.animate.as_ref().map(|_| Animation::new(image.width, image.height));
+    let original = cli.skip_unchanged.then(|| image.data.clone());
+    if let Err(e) = apply_stages(cli, &stages, plugins, &mut image, len, animation.as_mut()) {
+        if cli.save_on_error
+            && let Some(output) = &cli.output
+        {
+            save_partial(&image, output);
+        }
+        return Err(e);
+    }
+    report.timings.process_ms = millis(started.elapsed());
+    if let (Some(path), Some(animation)) = (&cli.animate, &animation) {
+        animation.write_apng(path, cli.frame_delay)?;
+        println!("Animation of {} frames saved to {}", animation.len(), path.display());
+    }
+    let Some(output) = &cli.output else {
+        log::info!("Save skipped (--no-save)");
+        println!("Successfully processed!");
+        return Ok(());
+    };
+    if original.as_deref() == Some(image.data.as_slice()) {
+        log::warn!("Plugins left the image unchanged, {} not written (--skip-unchanged)", output.display());
+        println!("Image unchanged, output not written");
+        return Ok(());
+    }
+    let stage = Instant::now();
+    if cli.restore_size && (image.width, image.height) != input_size {
+        image = resize_buffer(image, input_size.0, input_size.1, cli.linear_resize)?;
+    }
+    let image = RgbaImage::from_raw(image.width, image.height, image.data);
     if let Some(image) = image {
-        image.save(cli.output.clone())?;
-        println!("Image saved to {}", cli.output.to_string_lossy());
-        log::info!("Image successfully saved to {}", cli.output.to_string_lossy());
+        let image = match cli.canvas {
+            Some(canvas) => fit_canvas(&image, canvas, cli.fit, cli.background, cli.linear_resize),
+            None => image,
+        };
+        let metadata = cli.embed_metadata.then(|| {
+            ProcessingMetadata::new(stages.iter().map(|stage| (stage.plugin.as_path(), stage.params.as_str())))
+        });
+        match (cli.palette, &metadata) {
+            (Some(colors), _) => write_indexed_png(&image, colors as usize, output, metadata.as_ref())?,
+            (None, Some(metadata)) if is_png(output) => write_png_with_metadata(&image, output, metadata)?,
+            (None, Some(_)) => {
+                log::warn!("Metadata is embedded only into PNG output, {} saved without it", output.display());
+                save_rgba(&image, image.width(), image.height(), output, None, None)?
+            }
+            (None, None) => save_rgba(&image, image.width(), image.height(), output, None, None)?,
+        }
+        println!("Image saved to {}", output.to_string_lossy());
+        log::info!("Image successfully saved to {}", output.to_string_lossy());
     } else {
         log::error!("Error convert image");
         return Err(ImageProcessorError::ConvertFromRawError);
     }
+    report.timings.encode_ms = millis(stage.elapsed());
     println!("Successfully converted!");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_plugin::{MockPlugin, workspace_plugins};
+    use plugins_support::color_type::SourceColorType;
+    use std::ffi::OsString;
+
+    /// Запуск тестового плагина без сохранения: отчет заполнен, выходных файлов нет
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_run_no_save() {
+        let library = MockPlugin::new("mock_no_save").build();
+        let dir = std::env::temp_dir().join("image_processor_no_save");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        RgbaImage::from_pixel(4, 2, image::Rgba([10, 20, 30, 255]))
+            .save(dir.join("input.png"))
+            .unwrap();
+        fs::write(dir.join("params.json"), r#"{"log_level": "Info"}"#).unwrap();
+        let args: Vec<OsString> = vec![
+            "image-processor".into(),
+            "--input".into(),
+            dir.join("input.png").into(),
+            "--no-save".into(),
+            "--plugin".into(),
+            library.file_stem().unwrap().into(),
+            "--params".into(),
+            dir.join("params.json").into(),
+            "--plugin-path".into(),
+            library.parent().unwrap().into(),
+        ];
+        let cli = Cli::parse_from(args);
+        assert!(cli.output.is_none());
+        let mut report = RunReport::new(&cli);
+        run(&cli, &mut report, &mut HashMap::new()).unwrap();
+        assert_eq!((report.width, report.height), (Some(4), Some(2)));
+        assert!(report.output.is_none());
+        assert_eq!(report.timings.encode_ms, 0.0);
+        let files: Vec<_> = fs::read_dir(&dir).unwrap().collect();
+        assert_eq!(files.len(), 2);
+    }
+
+    /// Параметры запуска для [`apply_stages`]: плагин и параметры задает [`Stage`]
+    fn stage_cli() -> Cli {
+        Cli::try_parse_from([
+            "image-processor",
+            "--input",
+            "input.png",
+            "--no-save",
+            "--plugin",
+            "mock",
+            "--params",
+            "params.json",
+            "--plugin-path",
+            ".",
+        ])
+        .unwrap()
+    }
+
+    /// Черное RGBA изображение `width` x `height`
+    fn rgba_image(width: u32, height: u32) -> RgbaBuffer {
+        RgbaBuffer {
+            width,
+            height,
+            color: image::ColorType::Rgba8,
+            exif_orientation: None,
+            data: vec![0; rgba_len(width, height).unwrap()],
+        }
+    }
+
+    /// Буфер короче `width * height * 4` не передается плагину
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_buffer_len_guard() {
+        let library = MockPlugin::new("mock_buffer_len").build();
+        let cli = stage_cli();
+        let stages = [Stage { plugin: library, params: "{}".to_owned() }];
+        let len = rgba_len(4, 2).unwrap();
+        let mut image = rgba_image(4, 2);
+        let mut plugins = HashMap::new();
+        apply_stages(&cli, &stages, &mut plugins, &mut image, len, None).unwrap();
+        assert_eq!(image.data, [255, 255, 255, 0].repeat(8));
+        image.data.truncate(len / 2);
+        let error = apply_stages(&cli, &stages, &mut plugins, &mut image, len, None).unwrap_err();
+        assert!(matches!(
+            error,
+            ImageProcessorError::BufferLengthMismatch { expected: 32, actual: 16 }
+        ));
+        assert!(error.to_string().contains("Error convert image from raw"));
+        // Плагин не вызывался: иначе он записал бы 32 байта в буфер из 16
+        assert_eq!(image.data, [255, 255, 255, 0].repeat(4));
+    }
+
+    /// Параметры, прочитанные из `-`, передаются плагину
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_stdin_params_reach_plugin() {
+        // Плагин копирует полученную строку параметров в буфер изображения
+        let library = MockPlugin::new("mock_stdin_params")
+            .process_body(
+                "let params = unsafe { std::ffi::CStr::from_ptr(params) }.to_bytes();\n\
+                 let len = params.len().min(width as usize * height as usize * 4);\n\
+                 unsafe { std::ptr::copy_nonoverlapping(params.as_ptr(), rgba_data, len) };",
+            )
+            .build();
+        let json = r#"{"radius": 3}"#;
+        let params = read_params(Path::new("-"), std::io::Cursor::new(json)).unwrap();
+        let stages = [Stage { plugin: library, params }];
+        let mut image = rgba_image(16, 1);
+        let len = image.data.len();
+        apply_stages(&stage_cli(), &stages, &mut HashMap::new(), &mut image, len, None).unwrap();
+        let expected = inject_source_color_type(json, SourceColorType::Rgba8);
+        assert_eq!(&image.data[..expected.len()], expected.as_bytes());
+        assert!(image.data[expected.len()..].iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn test_decoded_buffer_len() {
+        let path = std::env::temp_dir().join("image_processor_buffer_len.png");
+        RgbaImage::from_pixel(7, 3, image::Rgba([1, 2, 3, 255])).save(&path).unwrap();
+        let image = load_rgba(&path).unwrap();
+        let len = rgba_len(image.width, image.height).unwrap();
+        assert_eq!(len, 7 * 3 * 4);
+        assert!(check_buffer_len(&image.data, len).is_ok());
+    }
+
+    #[test]
+    fn test_output_required_without_no_save() {
+        let result = Cli::try_parse_from([
+            "image-processor",
+            "--input",
+            "images.jpeg",
+            "--plugin",
+            "libblur_plugin",
+            "--params",
+            "blur-plugin-config.json",
+            "--plugin-path",
+            "target/debug",
+        ]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_repeat_must_be_positive() {
+        let args = |repeat: &str| {
+            Cli::try_parse_from([
+                "image-processor",
+                "--input",
+                "images.jpeg",
+                "--no-save",
+                "--plugin",
+                "libblur_plugin",
+                "--params",
+                "blur-plugin-config.json",
+                "--plugin-path",
+                "target/debug",
+                "--repeat",
+                repeat,
+            ])
+        };
+        assert!(args("0").is_err());
+        assert_eq!(args("3").unwrap().repeat, 3);
+    }
+
+    /// `--repeat 2` совпадает с двумя последовательными запусками плагина размытия
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_run_repeat() {
+        let plugin_path = workspace_plugins();
+        let dir = std::env::temp_dir().join("image_processor_repeat");
+        fs::create_dir_all(&dir).unwrap();
+        RgbaImage::from_fn(5, 3, |x, y| image::Rgba([(x * 50) as u8, (y * 90) as u8, 7, 255]))
+            .save(dir.join("input.png"))
+            .unwrap();
+        fs::write(dir.join("params.json"), r#"{"radius": 1, "step": 1}"#).unwrap();
+        let cli = |input: &str, output: &str, repeat: &str| {
+            let args: Vec<OsString> = vec![
+                "image-processor".into(),
+                "--input".into(),
+                dir.join(input).into(),
+                "--output".into(),
+                dir.join(output).into(),
+                "--plugin".into(),
+                "libblur_plugin".into(),
+                "--params".into(),
+                dir.join("params.json").into(),
+                "--plugin-path".into(),
+                plugin_path.into(),
+                "--repeat".into(),
+                repeat.into(),
+            ];
+            Cli::parse_from(args)
+        };
+        let mut plugins = HashMap::new();
+        for cli in [
+            cli("input.png", "repeat.png", "2"),
+            cli("input.png", "once.png", "1"),
+            cli("once.png", "twice.png", "1"),
+        ] {
+            run(&cli, &mut RunReport::new(&cli), &mut plugins).unwrap();
+        }
+        let repeated = image::open(dir.join("repeat.png")).unwrap().to_rgba8();
+        let twice = image::open(dir.join("twice.png")).unwrap().to_rgba8();
+        let once = image::open(dir.join("once.png")).unwrap().to_rgba8();
+        assert_eq!(repeated, twice);
+        assert_ne!(repeated, once);
+    }
+
+    /// `--animate` с `--repeat 3` для плагина размытия записывает APNG из трех кадров,
+    /// последний кадр совпадает с выходным изображением
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_run_animate() {
+        let plugin_path = workspace_plugins();
+        let dir = std::env::temp_dir().join("image_processor_animate");
+        fs::create_dir_all(&dir).unwrap();
+        RgbaImage::from_fn(5, 3, |x, y| image::Rgba([(x * 50) as u8, (y * 90) as u8, 7, 255]))
+            .save(dir.join("input.png"))
+            .unwrap();
+        fs::write(dir.join("params.json"), r#"{"radius": 1, "step": 1}"#).unwrap();
+        let args: Vec<OsString> = vec![
+            "image-processor".into(),
+            "--input".into(),
+            dir.join("input.png").into(),
+            "--output".into(),
+            dir.join("output.png").into(),
+            "--plugin".into(),
+            "libblur_plugin".into(),
+            "--params".into(),
+            dir.join("params.json").into(),
+            "--plugin-path".into(),
+            plugin_path.into(),
+            "--repeat".into(),
+            "3".into(),
+            "--animate".into(),
+            dir.join("animation.png").into(),
+            "--frame-delay".into(),
+            "100".into(),
+        ];
+        let cli = Cli::parse_from(args);
+        run(&cli, &mut RunReport::new(&cli), &mut HashMap::new()).unwrap();
+        let decoder = png::Decoder::new(fs::File::open(dir.join("animation.png")).unwrap());
+        let mut reader = decoder.read_info().unwrap();
+        assert_eq!(reader.info().animation_control.unwrap().num_frames, 3);
+        let mut frame = vec![0; reader.output_buffer_size()];
+        for _ in 0..3 {
+            reader.next_frame(&mut frame).unwrap();
+        }
+        let output = image::open(dir.join("output.png")).unwrap().to_rgba8();
+        assert_eq!(frame, output.into_raw());
+    }
+
+    /// `--skip-unchanged`: тождественный плагин не создает выходной файл, инвертирующий - создает
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_run_skip_unchanged() {
+        let dir = std::env::temp_dir().join("image_processor_skip_unchanged");
+        fs::create_dir_all(&dir).unwrap();
+        RgbaImage::from_pixel(3, 2, image::Rgba([10, 20, 30, 255])).save(dir.join("input.png")).unwrap();
+        fs::write(dir.join("params.json"), "{}").unwrap();
+        let identity = MockPlugin::new("mock_identity").process_body("").build();
+        let invert = MockPlugin::new("mock_skip_invert").build();
+        for (library, written) in [(identity, false), (invert, true)] {
+            let output = dir.join(format!("{}.png", library.file_stem().unwrap().to_string_lossy()));
+            let _ = fs::remove_file(&output);
+            let args: Vec<OsString> = vec![
+                "image-processor".into(),
+                "--input".into(),
+                dir.join("input.png").into(),
+                "--output".into(),
+                output.clone().into(),
+                "--plugin".into(),
+                library.file_stem().unwrap().into(),
+                "--params".into(),
+                dir.join("params.json").into(),
+                "--plugin-path".into(),
+                library.parent().unwrap().into(),
+                "--skip-unchanged".into(),
+            ];
+            let cli = Cli::parse_from(args);
+            run(&cli, &mut RunReport::new(&cli), &mut HashMap::new()).unwrap();
+            assert_eq!(output.exists(), written, "{}", output.display());
+        }
+        let conflicting = [
+            "image-processor",
+            "--input",
+            "a.png",
+            "--plugin",
+            "plugin",
+            "--params",
+            "p.json",
+            "--plugin-path",
+            "plugins",
+            "--no-save",
+        ];
+        assert!(Cli::try_parse_from(conflicting).is_ok());
+        assert!(Cli::try_parse_from(conflicting.iter().chain(&["--skip-unchanged"])).is_err());
+    }
+
+    /// `--max-process-pixels`: плагин получает уменьшенное изображение (и записывает его размер
+    /// в пиксели), `--restore-size` возвращает результату исходный размер
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_run_max_process_pixels() {
+        let dir = std::env::temp_dir().join("image_processor_max_process_pixels");
+        fs::create_dir_all(&dir).unwrap();
+        RgbaImage::from_pixel(40, 30, image::Rgba([10, 20, 30, 255])).save(dir.join("input.png")).unwrap();
+        fs::write(dir.join("params.json"), "{}").unwrap();
+        let library = MockPlugin::new("mock_record_size")
+            .process_body(
+                "
+    let rgba = unsafe { std::slice::from_raw_parts_mut(rgba_data, width as usize * height as usize * 4) };
+    for pixel in rgba.chunks_exact_mut(4) {
+        pixel.copy_from_slice(&[width as u8, height as u8, 0, 255]);
+    }",
+            )
+            .build();
+        for (restore, size) in [(false, (20, 15)), (true, (40, 30))] {
+            let output = dir.join(format!("output_{}.png", restore));
+            let mut args: Vec<OsString> = vec![
+                "image-processor".into(),
+                "--input".into(),
+                dir.join("input.png").into(),
+                "--output".into(),
+                output.clone().into(),
+                "--plugin".into(),
+                library.file_stem().unwrap().into(),
+                "--params".into(),
+                dir.join("params.json").into(),
+                "--plugin-path".into(),
+                library.parent().unwrap().into(),
+                "--max-process-pixels".into(),
+                "300".into(),
+            ];
+            if restore {
+                args.push("--restore-size".into());
+            }
+            let cli = Cli::parse_from(args);
+            let mut report = RunReport::new(&cli);
+            run(&cli, &mut report, &mut HashMap::new()).unwrap();
+            assert_eq!((report.width, report.height), (Some(40), Some(30)));
+            let output = image::open(&output).unwrap().to_rgba8();
+            assert_eq!(output.dimensions(), size, "restore {}", restore);
+            // Плагин обработал изображение 20x15
+            assert!(output.pixels().all(|pixel| pixel.0 == [20, 15, 0, 255]));
+        }
+        let base = ["image-processor", "--input", "a.png", "--params", "p.json"];
+        assert!(Cli::try_parse_from(base.iter().chain(&["--restore-size"])).is_err());
+        assert!(Cli::try_parse_from(base.iter().chain(&["--max-process-pixels", "0"])).is_err());
+    }
+
+    /// Несжатый RGBA буфер обрабатывается плагином отражения и сохраняется в PNG
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_run_raw_rgba() {
+        let plugin_path = workspace_plugins();
+        let dir = std::env::temp_dir().join("image_processor_raw");
+        fs::create_dir_all(&dir).unwrap();
+        let source = RgbaImage::from_fn(3, 2, |x, y| image::Rgba([(x * 80) as u8, (y * 100) as u8, 9, 200]));
+        fs::write(dir.join("input.rgba"), source.as_raw()).unwrap();
+        fs::write(dir.join("params.json"), r#"{"horizontal_flip": true}"#).unwrap();
+        let args: Vec<OsString> = vec![
+            "image-processor".into(),
+            "--input".into(),
+            dir.join("input.rgba").into(),
+            "--raw".into(),
+            "3x2".into(),
+            "--output".into(),
+            dir.join("output.png").into(),
+            "--plugin".into(),
+            "libmirror_plugin".into(),
+            "--params".into(),
+            dir.join("params.json").into(),
+            "--plugin-path".into(),
+            plugin_path.into(),
+        ];
+        let cli = Cli::parse_from(args);
+        run(&cli, &mut RunReport::new(&cli), &mut HashMap::new()).unwrap();
+        let output = image::open(dir.join("output.png")).unwrap().to_rgba8();
+        assert_eq!(output, image::imageops::flip_horizontal(&source));
+    }
+
+    #[test]
+    fn test_partial_path() {
+        assert_eq!(partial_path(Path::new("out/blur.png")), PathBuf::from("out/blur.partial.png"));
+        assert_eq!(partial_path(Path::new("blur.jpeg")), PathBuf::from("blur.partial.png"));
+    }
+
+    /// Параметры второго этапа пресета не проходят проверку схемы: с `--save-on-error`
+    /// сохраняется результат первого этапа в `.partial.png`, основной вывод не создается
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_run_save_on_error() {
+        let plugin_path = workspace_plugins();
+        let dir = std::env::temp_dir().join("image_processor_save_on_error");
+        fs::create_dir_all(&dir).unwrap();
+        let _ = fs::remove_file(dir.join("output.png"));
+        let _ = fs::remove_file(dir.join("output.partial.png"));
+        let source = RgbaImage::from_fn(5, 3, |x, y| image::Rgba([(x * 50) as u8, (y * 90) as u8, 7, 255]));
+        source.save(dir.join("input.png")).unwrap();
+        fs::write(
+            dir.join("presets.toml"),
+            r#"
+            [[fails_second]]
+            plugin = "libblur_plugin"
+            params = { radius = 1, step = 1 }
+
+            [[fails_second]]
+            plugin = "libblur_plugin"
+            params = { radius = 0, step = 1 }
+            "#,
+        )
+        .unwrap();
+        let args: Vec<OsString> = vec![
+            "image-processor".into(),
+            "--input".into(),
+            dir.join("input.png").into(),
+            "--output".into(),
+            dir.join("output.png").into(),
+            "--preset".into(),
+            "fails_second".into(),
+            "--preset-file".into(),
+            dir.join("presets.toml").into(),
+            "--plugin-path".into(),
+            plugin_path.into(),
+            "--validate-schema".into(),
+            "--save-on-error".into(),
+        ];
+        let cli = Cli::parse_from(args);
+        let result = run(&cli, &mut RunReport::new(&cli), &mut HashMap::new());
+        assert!(matches!(result, Err(ImageProcessorError::SchemaValidationError(_))));
+        assert!(!dir.join("output.png").exists());
+        let partial = image::open(dir.join("output.partial.png")).unwrap().to_rgba8();
+        assert_eq!(partial.dimensions(), (5, 3));
+        assert_ne!(partial, source);
+    }
+
+    /// Пресет из двух этапов совпадает с последовательным запуском размытия и отражения
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_run_preset() {
+        let plugin_path = workspace_plugins();
+        let dir = std::env::temp_dir().join("image_processor_preset");
+        fs::create_dir_all(&dir).unwrap();
+        RgbaImage::from_fn(5, 3, |x, y| image::Rgba([(x * 50) as u8, (y * 90) as u8, 7, 255]))
+            .save(dir.join("input.png"))
+            .unwrap();
+        fs::write(
+            dir.join("presets.toml"),
+            r#"
+            [[soft_flip]]
+            plugin = "libblur_plugin"
+            params = { radius = 1, step = 1 }
+
+            [[soft_flip]]
+            plugin = "libmirror_plugin"
+            params = { horizontal_flip = true }
+            "#,
+        )
+        .unwrap();
+        fs::write(dir.join("blur.json"), r#"{"radius": 1, "step": 1}"#).unwrap();
+        fs::write(dir.join("mirror.json"), r#"{"horizontal_flip": true}"#).unwrap();
+        let run_cli = |args: &[OsString]| {
+            let mut full: Vec<OsString> =
+                vec!["image-processor".into(), "--plugin-path".into(), plugin_path.into()];
+            full.extend_from_slice(args);
+            let cli = Cli::parse_from(full);
+            run(&cli, &mut RunReport::new(&cli), &mut HashMap::new()).unwrap();
+        };
+        run_cli(&[
+            "--input".into(),
+            dir.join("input.png").into(),
+            "--output".into(),
+            dir.join("preset.png").into(),
+            "--preset".into(),
+            "soft_flip".into(),
+            "--preset-file".into(),
+            dir.join("presets.toml").into(),
+        ]);
+        for (input, output, plugin, params) in [
+            ("input.png", "blurred.png", "libblur_plugin", "blur.json"),
+            ("blurred.png", "manual.png", "libmirror_plugin", "mirror.json"),
+        ] {
+            run_cli(&[
+                "--input".into(),
+                dir.join(input).into(),
+                "--output".into(),
+                dir.join(output).into(),
+                "--plugin".into(),
+                plugin.into(),
+                "--params".into(),
+                dir.join(params).into(),
+            ]);
+        }
+        let preset = image::open(dir.join("preset.png")).unwrap().to_rgba8();
+        let manual = image::open(dir.join("manual.png")).unwrap().to_rgba8();
+        assert_eq!(preset, manual);
+    }
+
+    /// Отчет `--manifest` с `--report` - массив отчетов заданий, включая неуспешные
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_run_manifest_reports() {
+        let library = MockPlugin::new("mock_manifest_report").build();
+        let dir = std::env::temp_dir().join("image_processor_manifest_report");
+        fs::create_dir_all(&dir).unwrap();
+        RgbaImage::from_pixel(3, 2, image::Rgba([10, 20, 30, 255])).save(dir.join("a.png")).unwrap();
+        fs::write(dir.join("params.json"), "{}").unwrap();
+        let _ = fs::remove_file(dir.join("missing.png"));
+        let path = |name: &str| dir.join(name).display().to_string();
+        let plugin = library.file_stem().unwrap().to_string_lossy();
+        let manifest = format!(
+            "input,output,plugin,params\n{},{},{},{}\n{},{},{},{}\n",
+            path("a.png"),
+            path("a_out.png"),
+            plugin,
+            path("params.json"),
+            path("missing.png"),
+            path("missing_out.png"),
+            plugin,
+            path("params.json"),
+        );
+        fs::write(dir.join("jobs.csv"), manifest).unwrap();
+        let args: Vec<OsString> = vec![
+            "image-processor".into(),
+            "--manifest".into(),
+            dir.join("jobs.csv").into(),
+            "--plugin-path".into(),
+            library.parent().unwrap().into(),
+            "--report".into(),
+            dir.join("report.json").into(),
+        ];
+        let _ = fs::remove_file(dir.join("report.json"));
+        let cli = Cli::try_parse_from(args).unwrap();
+        let result = run_with_report(&cli);
+        assert!(matches!(result, Err(ImageProcessorError::ManifestJobsFailed(1, 2))));
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(dir.join("report.json")).unwrap()).unwrap();
+        let jobs = json.as_array().unwrap();
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[0]["success"], true);
+        assert_eq!(jobs[0]["width"], 3);
+        assert_eq!(jobs[0]["plugin"], plugin.as_ref());
+        assert_eq!(jobs[1]["success"], false);
+        assert!(jobs[1]["error"].is_string());
+    }
+
+    /// Пакетная обработка манифестом: размытие одного файла и отражение другого
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_run_manifest() {
+        let plugin_path = workspace_plugins();
+        let dir = std::env::temp_dir().join("image_processor_manifest");
+        fs::create_dir_all(&dir).unwrap();
+        let source = RgbaImage::from_fn(4, 2, |x, y| image::Rgba([(x * 60) as u8, (y * 100) as u8, 0, 255]));
+        source.save(dir.join("a.png")).unwrap();
+        source.save(dir.join("b.png")).unwrap();
+        fs::write(dir.join("blur.json"), r#"{"radius": 1, "step": 1}"#).unwrap();
+        fs::write(dir.join("mirror.json"), r#"{"horizontal_flip": true}"#).unwrap();
+        let _ = fs::remove_file(dir.join("a_out.png"));
+        let _ = fs::remove_file(dir.join("b_out.png"));
+        let path = |name: &str| dir.join(name).display().to_string();
+        let manifest = format!(
+            "input,output,plugin,params\n{},{},libblur_plugin,{}\n{},{},libmirror_plugin,{}\n",
+            path("a.png"),
+            path("a_out.png"),
+            path("blur.json"),
+            path("b.png"),
+            path("b_out.png"),
+            path("mirror.json"),
+        );
+        fs::write(dir.join("jobs.csv"), manifest).unwrap();
+        let args: Vec<OsString> = vec![
+            "image-processor".into(),
+            "--manifest".into(),
+            dir.join("jobs.csv").into(),
+            "--plugin-path".into(),
+            plugin_path.into(),
+        ];
+        let cli = Cli::parse_from(args);
+        run_manifest(&cli, cli.manifest.as_ref().unwrap(), &mut Vec::new()).unwrap();
+        let blurred = image::open(dir.join("a_out.png")).unwrap().to_rgba8();
+        assert_eq!(blurred.dimensions(), (4, 2));
+        let mirrored = image::open(dir.join("b_out.png")).unwrap().to_rgba8();
+        assert_eq!(mirrored.get_pixel(0, 0), source.get_pixel(3, 0));
+    }
+}
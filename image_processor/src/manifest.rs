@@ -0,0 +1,152 @@
+//! Модуль для пакетной обработки по CSV манифесту
+//!
+//! Предоставляет функциональность чтения CSV манифеста, в котором каждая строка задает
+//! входное и выходное изображение, плагин и файл параметров
+
+use crate::cli::Cli;
+use crate::error::ImageProcessorError;
+use crate::job_config::resolve_path;
+use std::path::{Path, PathBuf};
+
+/// Обязательные колонки манифеста
+const COLUMNS: [&str; 4] = ["input", "output", "plugin", "params"];
+
+/// Задание обработки из строки манифеста
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ManifestJob {
+    /// Номер строки в файле манифеста
+    pub(crate) line: usize,
+    /// Путь к входному изображению
+    pub(crate) input: PathBuf,
+    /// Путь к выходному изображению
+    pub(crate) output: PathBuf,
+    /// Имя плагина
+    pub(crate) plugin: PathBuf,
+    /// Путь к файлу параметров плагина
+    pub(crate) params: PathBuf,
+}
+
+impl ManifestJob {
+    /// Разрешение относительных путей `input`, `output`, `params` от директории манифеста.
+    /// `plugin` - имя библиотеки в `--plugin-path` и не изменяется
+    pub(crate) fn resolve_paths(&mut self, base: &Path) {
+        for path in [&mut self.input, &mut self.output, &mut self.params] {
+            *path = resolve_path(base, path);
+        }
+    }
+
+    /// Параметры командной строки для задания (остальные параметры берутся из `cli`)
+    pub(crate) fn cli(&self, cli: &Cli) -> Cli {
+        Cli {
+            input: Some(self.input.clone()),
+            output: (!cli.no_save).then(|| self.output.clone()),
+            plugin: Some(self.plugin.clone()),
+            params: Some(self.params.clone()),
+            manifest: None,
+            report: None,
+            batch_job: true,
+            ..cli.clone()
+        }
+    }
+}
+
+fn manifest_error(line: usize, message: &str) -> ImageProcessorError {
+    log::error!("Manifest line {}: {}", line, message);
+    ImageProcessorError::ManifestError(format!("line {}: {}", line, message))
+}
+
+///
+/// Разбор CSV манифеста
+///
+/// Первая непустая строка - заголовок с колонками `input`, `output`, `plugin`, `params`
+/// в любом порядке. Значения разделяются запятой, пробелы по краям отбрасываются,
+/// пустые строки и строки, начинающиеся с `#`, пропускаются
+///
+/// # Параметры
+///
+/// * `text` - содержимое файла манифеста
+///
+/// # Возращает
+/// Список заданий или `ImageProcessorError::ManifestError` с номером ошибочной строки
+///
+pub(crate) fn parse_manifest(text: &str) -> Result<Vec<ManifestJob>, ImageProcessorError> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+    let Some((header_line, header)) = lines.next() else {
+        return Err(manifest_error(1, "empty manifest"));
+    };
+    let header: Vec<&str> = header.split(',').map(str::trim).collect();
+    let mut positions = [0; COLUMNS.len()];
+    for (position, column) in positions.iter_mut().zip(COLUMNS) {
+        *position = header
+            .iter()
+            .position(|name| *name == column)
+            .ok_or_else(|| manifest_error(header_line, &format!("missing column `{}`", column)))?;
+    }
+    let mut jobs = Vec::new();
+    for (line, row) in lines {
+        let fields: Vec<&str> = row.split(',').map(str::trim).collect();
+        if fields.len() != header.len() {
+            return Err(manifest_error(
+                line,
+                &format!("expected {} columns, found {}", header.len(), fields.len()),
+            ));
+        }
+        let [input, output, plugin, params] = positions.map(|position| fields[position]);
+        if [input, output, plugin, params].iter().any(|field| field.is_empty()) {
+            return Err(manifest_error(line, "empty value"));
+        }
+        jobs.push(ManifestJob {
+            line,
+            input: input.into(),
+            output: output.into(),
+            plugin: plugin.into(),
+            params: params.into(),
+        });
+    }
+    Ok(jobs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_manifest() {
+        let text = "# jobs\nplugin, input, output, params\n\nlibblur_plugin, a.png, a_out.png, blur.json\nlibmirror_plugin,b.png,b_out.png,mirror.json\n";
+        let jobs = parse_manifest(text).unwrap();
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[0].line, 4);
+        assert_eq!(jobs[0].plugin, PathBuf::from("libblur_plugin"));
+        assert_eq!(jobs[0].input, PathBuf::from("a.png"));
+        assert_eq!(jobs[1].output, PathBuf::from("b_out.png"));
+        assert_eq!(jobs[1].params, PathBuf::from("mirror.json"));
+    }
+
+    #[test]
+    fn test_parse_manifest_missing_column() {
+        let error = parse_manifest("input,output,plugin\na,b,c\n").unwrap_err();
+        assert!(error.to_string().contains("missing column `params`"));
+    }
+
+    #[test]
+    fn test_parse_manifest_bad_row() {
+        let error = parse_manifest("input,output,plugin,params\na,b,c\n").unwrap_err();
+        assert!(error.to_string().contains("line 2"));
+        assert!(parse_manifest("").is_err());
+    }
+
+    #[test]
+    fn test_manifest_relative_paths() {
+        let text = "input,output,plugin,params\nimages/a.png,/out/a.png,libblur_plugin,-\n";
+        let mut job = parse_manifest(text).unwrap().remove(0);
+        job.resolve_paths(Path::new("batches"));
+        assert_eq!(job.input, PathBuf::from("batches/images/a.png"));
+        assert_eq!(job.output, PathBuf::from("/out/a.png"));
+        assert_eq!(job.plugin, PathBuf::from("libblur_plugin"));
+        assert_eq!(job.params, PathBuf::from("-"));
+    }
+}
@@ -0,0 +1,139 @@
+//! Модуль для записи сведений об обработке в выходной PNG
+//!
+//! Предоставляет функциональность `--embed-metadata`: плагины, параметры и время обработки
+//! записываются текстовыми чанками PNG (`tEXt` и `iTXt`)
+
+use crate::error::ImageProcessorError;
+use image::RgbaImage;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Ключ чанка `tEXt` с программой обработки
+pub(crate) const SOFTWARE_KEYWORD: &str = "Software";
+/// Ключ чанка `tEXt` со списком плагинов
+pub(crate) const PLUGINS_KEYWORD: &str = "Plugins";
+/// Ключ чанка `iTXt` с полными сведениями об обработке (JSON)
+pub(crate) const PROCESSING_KEYWORD: &str = "Processing";
+
+/// Сведения об обработке
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub(crate) struct ProcessingMetadata {
+    /// Имена библиотек плагинов в порядке применения
+    pub(crate) plugins: Vec<String>,
+    /// Параметры плагинов (JSON, либо исходная строка)
+    pub(crate) params: Vec<serde_json::Value>,
+    /// Время обработки, RFC 3339
+    pub(crate) timestamp: String,
+}
+
+impl ProcessingMetadata {
+    ///
+    /// Сведения об обработке
+    ///
+    /// # Параметры
+    ///
+    /// * `stages` - пары (путь к библиотеке плагина, параметры) в порядке применения
+    ///
+    pub(crate) fn new<'a>(stages: impl IntoIterator<Item = (&'a Path, &'a str)>) -> Self {
+        let (plugins, params) = stages
+            .into_iter()
+            .map(|(plugin, params)| {
+                let plugin = plugin
+                    .file_name()
+                    .unwrap_or(plugin.as_os_str())
+                    .to_string_lossy()
+                    .into_owned();
+                let params = serde_json::from_str(params)
+                    .unwrap_or_else(|_| serde_json::Value::String(params.to_string()));
+                (plugin, params)
+            })
+            .unzip();
+        ProcessingMetadata {
+            plugins,
+            params,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    ///
+    /// Добавление текстовых чанков в кодировщик PNG
+    ///
+    /// Имена плагинов записываются в `tEXt` (Latin-1), полные сведения - в `iTXt` (UTF-8)
+    ///
+    pub(crate) fn add_chunks<W: Write>(&self, encoder: &mut png::Encoder<W>) -> Result<(), ImageProcessorError> {
+        encoder.add_text_chunk(SOFTWARE_KEYWORD.to_string(), env!("CARGO_PKG_NAME").to_string())?;
+        encoder.add_text_chunk(PLUGINS_KEYWORD.to_string(), self.plugins.join(", "))?;
+        encoder.add_itxt_chunk(PROCESSING_KEYWORD.to_string(), serde_json::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+/// Является ли путь файлом PNG (по расширению)
+pub(crate) fn is_png(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("png"))
+}
+
+///
+/// Запись RGBA изображения в PNG со сведениями об обработке
+///
+/// # Параметры
+///
+/// * `image` - обработанное изображение
+/// * `path` - путь к выходному файлу
+/// * `metadata` - сведения об обработке
+///
+pub(crate) fn write_png_with_metadata(
+    image: &RgbaImage,
+    path: &Path,
+    metadata: &ProcessingMetadata,
+) -> Result<(), ImageProcessorError> {
+    let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), image.width(), image.height());
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    metadata.add_chunks(&mut encoder)?;
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(image.as_raw())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_png_metadata_round_trip() {
+        let image = RgbaImage::from_pixel(3, 2, image::Rgba([10, 20, 30, 255]));
+        let path = std::env::temp_dir().join("image_processor_metadata.png");
+        let metadata = ProcessingMetadata::new([(
+            Path::new("target/debug/libblur_plugin.so"),
+            r#"{"radius": 1, "step": 1}"#,
+        )]);
+        write_png_with_metadata(&image, &path, &metadata).unwrap();
+        let decoder = png::Decoder::new(File::open(&path).unwrap());
+        let reader = decoder.read_info().unwrap();
+        let text = &reader.info().uncompressed_latin1_text;
+        let plugins = text.iter().find(|chunk| chunk.keyword == PLUGINS_KEYWORD).unwrap();
+        assert_eq!(plugins.text, "libblur_plugin.so");
+        assert!(text.iter().any(|chunk| chunk.keyword == SOFTWARE_KEYWORD));
+        let processing = reader
+            .info()
+            .utf8_text
+            .iter()
+            .find(|chunk| chunk.keyword == PROCESSING_KEYWORD)
+            .unwrap()
+            .get_text()
+            .unwrap();
+        let processing: serde_json::Value = serde_json::from_str(&processing).unwrap();
+        assert_eq!(processing["params"][0]["radius"], 1);
+        assert_eq!(image::open(&path).unwrap().to_rgba8(), image);
+    }
+
+    #[test]
+    fn test_is_png() {
+        assert!(is_png(Path::new("out.PNG")));
+        assert!(!is_png(Path::new("out.jpeg")));
+    }
+}
@@ -0,0 +1,179 @@
+//! Модуль для сборки тестовых плагинов
+//!
+//! Предоставляет построитель `MockPlugin`: исходный код крошечной cdylib с заданным
+//! набором экспортируемых символов компилируется `rustc` во время теста, чтобы проверять
+//! `Plugin::new`/`interface` на контролируемых библиотеках без сборки реальных плагинов.
+//! Тесты, которым нужны настоящие плагины размытия и отражения, получают их из
+//! [`workspace_plugins`]
+
+use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+
+/// Тело `process_image` по умолчанию: инверсия RGB
+const INVERT_BODY: &str = "
+    let rgba = unsafe { std::slice::from_raw_parts_mut(rgba_data, width as usize * height as usize * 4) };
+    for pixel in rgba.chunks_exact_mut(4) {
+        for channel in &mut pixel[..3] {
+            *channel = 255 - *channel;
+        }
+    }";
+
+/// Построитель тестового плагина
+#[derive(Debug, Clone)]
+pub(crate) struct MockPlugin {
+    name: String,
+    /// Тело `process_image`, `None` - символ не экспортируется
+    process_image: Option<String>,
+    /// Значение `plugin_warning_count`, `None` - символ не экспортируется
+    warning_count: Option<u32>,
+    /// Строка `plugin_params_schema`, `None` - символ не экспортируется
+    params_schema: Option<String>,
+    /// Дополнительные элементы исходного кода (свои экспортируемые символы)
+    items: Vec<String>,
+}
+
+impl MockPlugin {
+    ///
+    /// Тестовый плагин, экспортирующий только `process_image` (инверсия RGB)
+    ///
+    /// # Параметры
+    ///
+    /// * `name` - имя библиотеки (уникальное для теста, тесты выполняются параллельно)
+    ///
+    pub(crate) fn new(name: &str) -> Self {
+        MockPlugin {
+            name: name.to_owned(),
+            process_image: Some(INVERT_BODY.to_owned()),
+            warning_count: None,
+            params_schema: None,
+            items: Vec::new(),
+        }
+    }
+
+    /// Тело `process_image` с параметрами `width`, `height`, `rgba_data`, `params`
+    pub(crate) fn process_body(mut self, body: &str) -> Self {
+        self.process_image = Some(body.to_owned());
+        self
+    }
+
+    /// Не экспортировать `process_image`
+    pub(crate) fn without_process_image(mut self) -> Self {
+        self.process_image = None;
+        self
+    }
+
+    /// Экспортировать `plugin_warning_count`, возвращающий `count`
+    pub(crate) fn warning_count(mut self, count: u32) -> Self {
+        self.warning_count = Some(count);
+        self
+    }
+
+    /// Экспортировать `plugin_params_schema`, возвращающий `schema`
+    pub(crate) fn params_schema(mut self, schema: &str) -> Self {
+        self.params_schema = Some(schema.to_owned());
+        self
+    }
+
+    /// Добавить произвольный элемент исходного кода, например свой `#[unsafe(no_mangle)]` символ
+    pub(crate) fn item(mut self, source: &str) -> Self {
+        self.items.push(source.to_owned());
+        self
+    }
+
+    /// Исходный код библиотеки
+    fn source(&self) -> String {
+        let mut source = String::from("use std::ffi::{c_char, c_uint};\n");
+        if let Some(body) = &self.process_image {
+            let _ = writeln!(
+                source,
+                "#[unsafe(no_mangle)]\n#[allow(unused_variables)]\npub unsafe extern \"C\" fn process_image(\
+                 width: c_uint, height: c_uint, rgba_data: *mut u8, params: *const c_char) {{{}\n}}",
+                body
+            );
+        }
+        if let Some(count) = self.warning_count {
+            let _ = writeln!(
+                source,
+                "#[unsafe(no_mangle)]\npub extern \"C\" fn plugin_warning_count() -> c_uint {{ {} }}",
+                count
+            );
+        }
+        if let Some(schema) = &self.params_schema {
+            let _ = writeln!(
+                source,
+                "#[unsafe(no_mangle)]\npub extern \"C\" fn plugin_params_schema() -> *const c_char {{ \
+                 c{:?}.as_ptr() }}",
+                schema
+            );
+        }
+        for item in &self.items {
+            let _ = writeln!(source, "{}", item);
+        }
+        source
+    }
+
+    ///
+    /// Компиляция библиотеки `rustc` (переменная окружения `RUSTC`, как у cargo)
+    ///
+    /// # Возращает
+    /// Путь к собранной библиотеке во временной директории
+    ///
+    pub(crate) fn build(&self) -> PathBuf {
+        let dir = std::env::temp_dir().join("image_processor_mock_plugins");
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join(format!("{}.rs", self.name));
+        fs::write(&source, self.source()).unwrap();
+        let library = dir.join(format!("{}{}{}", DLL_PREFIX, self.name, DLL_SUFFIX));
+        let rustc = std::env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+        let output = Command::new(rustc)
+            .args(["--edition", "2024", "--crate-type", "cdylib", "--crate-name"])
+            .arg(&self.name)
+            .arg("-o")
+            .arg(&library)
+            .arg(&source)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "mock plugin {} not built:\n{}",
+            self.name,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        library
+    }
+}
+
+///
+/// Каталог с собранными плагинами workspace `blur-plugin` и `mirror-plugin`
+///
+/// Плагины собираются `cargo build` один раз на процесс тестов в каталог профиля, из которого
+/// запущен тест (`target/<профиль>`). Если сборка не удалась, тест завершается ошибкой
+///
+pub(crate) fn workspace_plugins() -> &'static Path {
+    static DIR: OnceLock<PathBuf> = OnceLock::new();
+    DIR.get_or_init(|| {
+        // Тест запускается из `target/<профиль>/deps`
+        let exe = std::env::current_exe().unwrap();
+        let dir = exe.parent().and_then(Path::parent).expect("profile directory").to_path_buf();
+        let workspace = Path::new(env!("CARGO_MANIFEST_DIR")).parent().expect("workspace directory");
+        let mut cargo = Command::new(std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into()));
+        cargo.current_dir(workspace).args(["build", "-p", "blur-plugin", "-p", "mirror-plugin"]);
+        match dir.file_name().and_then(|name| name.to_str()) {
+            Some("debug") | None => {}
+            Some(profile) => {
+                cargo.args(["--profile", profile]);
+            }
+        }
+        let status = cargo.status().expect("run cargo build");
+        assert!(status.success(), "cargo build of blur-plugin and mirror-plugin failed: {}", status);
+        for name in ["blur_plugin", "mirror_plugin"] {
+            let library = dir.join(format!("{}{}{}", DLL_PREFIX, name, DLL_SUFFIX));
+            assert!(library.exists(), "{} not found after cargo build", library.display());
+        }
+        dir
+    })
+}
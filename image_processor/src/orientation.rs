@@ -0,0 +1,175 @@
+//! Модуль для поворота изображения по EXIF
+//!
+//! Предоставляет функциональность чтения тега ориентации (0x0112) из EXIF блока
+//! и приведения RGBA буфера к нормальной ориентации (`--auto-orient`). Все восемь
+//! значений EXIF - повороты на кратный 90° угол и отражения, поэтому преобразование
+//! только переставляет пиксели без интерполяции и не снижает качество изображения
+
+use crate::error::ImageProcessorError;
+use crate::io::RgbaBuffer;
+
+/// Тег ориентации в IFD0
+const ORIENTATION_TAG: u16 = 0x0112;
+/// Тип значения TIFF `SHORT`
+const TYPE_SHORT: u16 = 3;
+
+///
+/// Значение тега ориентации из EXIF блока (TIFF заголовок и IFD0)
+///
+/// # Параметры
+///
+/// * `exif` - EXIF блок без префикса `Exif\0\0`, как его возвращает декодер
+///
+/// # Возращает
+/// Значение тега как записано в файле (без проверки диапазона), `None`, если тега нет
+/// или блок поврежден
+///
+pub(crate) fn exif_orientation(exif: &[u8]) -> Option<u16> {
+    let big_endian = match exif.get(..4)? {
+        [b'I', b'I', 42, 0] => false,
+        [b'M', b'M', 0, 42] => true,
+        _ => return None,
+    };
+    let u16_at = |offset: usize| {
+        let bytes = exif.get(offset..offset.checked_add(2)?)?.try_into().ok()?;
+        Some(if big_endian { u16::from_be_bytes(bytes) } else { u16::from_le_bytes(bytes) })
+    };
+    let u32_at = |offset: usize| {
+        let bytes = exif.get(offset..offset.checked_add(4)?)?.try_into().ok()?;
+        Some(if big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) })
+    };
+    let ifd = usize::try_from(u32_at(4)?).ok()?;
+    let entries = u16_at(ifd)?;
+    (0..usize::from(entries))
+        .map(|index| ifd + 2 + index * 12)
+        .find(|&entry| u16_at(entry) == Some(ORIENTATION_TAG))
+        .and_then(|entry| (u16_at(entry + 2)? == TYPE_SHORT).then(|| u16_at(entry + 8))?)
+}
+
+///
+/// Приведение изображения к нормальной ориентации
+///
+/// # Параметры
+///
+/// * `image` - декодированное изображение
+/// * `orientation` - значение тега ориентации EXIF:
+///   1 - без изменений, 2 - отражение по горизонтали, 3 - поворот на 180°,
+///   4 - отражение по вертикали, 5 - транспонирование, 6 - поворот на 90° по часовой,
+///   7 - поперечное транспонирование, 8 - поворот на 90° против часовой
+///
+/// Каждый пиксель результата - копия ровно одного пикселя исходного изображения.
+///
+/// # Возращает
+/// Изображение в нормальной ориентации (для 5-8 ширина и высота меняются местами)
+/// или `UnsupportedOrientation` для значений вне `1..=8`
+///
+pub(crate) fn reorient(image: RgbaBuffer, orientation: u16) -> Result<RgbaBuffer, ImageProcessorError> {
+    if !(1..=8).contains(&orientation) {
+        log::error!("Unsupported EXIF orientation {}", orientation);
+        return Err(ImageProcessorError::UnsupportedOrientation(orientation));
+    }
+    if orientation == 1 {
+        return Ok(image);
+    }
+    let (width, height) = (image.width as usize, image.height as usize);
+    let transposed = orientation >= 5;
+    let (out_width, out_height) = if transposed { (height, width) } else { (width, height) };
+    // Координаты исходного пикселя для пикселя результата (x, y)
+    let source = |x: usize, y: usize| match orientation {
+        2 => (width - 1 - x, y),
+        3 => (width - 1 - x, height - 1 - y),
+        4 => (x, height - 1 - y),
+        5 => (y, x),
+        6 => (y, height - 1 - x),
+        7 => (width - 1 - y, height - 1 - x),
+        _ => (width - 1 - y, x),
+    };
+    let mut data = Vec::with_capacity(image.data.len());
+    for y in 0..out_height {
+        for x in 0..out_width {
+            let (source_x, source_y) = source(x, y);
+            let index = (source_y * width + source_x) * 4;
+            data.extend_from_slice(&image.data[index..index + 4]);
+        }
+    }
+    log::debug!("EXIF orientation {} applied", orientation);
+    Ok(RgbaBuffer {
+        width: out_width as u32,
+        height: out_height as u32,
+        data,
+        ..image
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::ColorType;
+
+    /// Изображение 3x2 с пикселями `a b c / d e f`, красный канал - буква
+    fn letters() -> RgbaBuffer {
+        RgbaBuffer {
+            width: 3,
+            height: 2,
+            color: ColorType::Rgba8,
+            exif_orientation: None,
+            data: b"abcdef".iter().flat_map(|&letter| [letter, 0, 0, 255]).collect(),
+        }
+    }
+
+    /// Строки результата по красному каналу
+    fn rows(image: &RgbaBuffer) -> Vec<String> {
+        image
+            .data
+            .chunks_exact(image.width as usize * 4)
+            .map(|row| row.chunks_exact(4).map(|pixel| pixel[0] as char).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_reorient_all_values() {
+        let expected: [(u16, &[&str]); 8] = [
+            (1, &["abc", "def"]),
+            (2, &["cba", "fed"]),
+            (3, &["fed", "cba"]),
+            (4, &["def", "abc"]),
+            (5, &["ad", "be", "cf"]),
+            (6, &["da", "eb", "fc"]),
+            (7, &["fc", "eb", "da"]),
+            (8, &["cf", "be", "ad"]),
+        ];
+        for (orientation, expected) in expected {
+            let image = reorient(letters(), orientation).unwrap();
+            assert_eq!(rows(&image), expected, "orientation {}", orientation);
+            assert_eq!(image.data.len(), letters().data.len());
+            // Без интерполяции: набор пикселей не меняется
+            let mut pixels: Vec<&[u8]> = image.data.chunks_exact(4).collect();
+            pixels.sort();
+            assert_eq!(pixels, letters().data.chunks_exact(4).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_reorient_unsupported() {
+        for orientation in [0, 9, 0xFFFF] {
+            let error = reorient(letters(), orientation).unwrap_err();
+            assert!(matches!(error, ImageProcessorError::UnsupportedOrientation(value) if value == orientation));
+        }
+    }
+
+    #[test]
+    fn test_exif_orientation() {
+        // Порядок байт Intel: IFD0 со смещением 8, две записи, ориентация 6
+        let mut exif = b"II*\0\x08\0\0\0\x02\0".to_vec();
+        exif.extend_from_slice(&[0x0F, 0x01, 2, 0, 6, 0, 0, 0, 26, 0, 0, 0]);
+        exif.extend_from_slice(&[0x12, 0x01, 3, 0, 1, 0, 0, 0, 6, 0, 0, 0]);
+        assert_eq!(exif_orientation(&exif), Some(6));
+        // Порядок байт Motorola, ориентация 8
+        let mut exif = b"MM\0*\0\0\0\x08\0\x01".to_vec();
+        exif.extend_from_slice(&[0x01, 0x12, 0, 3, 0, 0, 0, 1, 0, 8, 0, 0]);
+        assert_eq!(exif_orientation(&exif), Some(8));
+        assert_eq!(exif_orientation(&exif[..15]), None);
+        assert_eq!(exif_orientation(b"II*\0\x08\0\0\0\0\0"), None);
+        assert_eq!(exif_orientation(b"JFIF"), None);
+    }
+}
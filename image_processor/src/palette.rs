@@ -0,0 +1,151 @@
+//! Модуль для сохранения изображения в индексированный PNG
+//!
+//! Предоставляет функциональность построения палитры методом медианного сечения
+//! и записи PNG с палитрой
+
+use crate::error::ImageProcessorError;
+use crate::metadata::ProcessingMetadata;
+use image::RgbaImage;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+/// Канал с наибольшим разбросом значений и величина разброса
+fn widest_channel(pixels: &[[u8; 4]]) -> (usize, u8) {
+    (0..4)
+        .map(|channel| {
+            let min = pixels.iter().map(|pixel| pixel[channel]).min().unwrap_or(0);
+            let max = pixels.iter().map(|pixel| pixel[channel]).max().unwrap_or(0);
+            (channel, max - min)
+        })
+        .max_by_key(|(_, range)| *range)
+        .unwrap_or((0, 0))
+}
+
+/// Средний цвет группы пикселей
+fn average(pixels: &[[u8; 4]]) -> [u8; 4] {
+    let mut sum = [0u64; 4];
+    for pixel in pixels {
+        for (sum, value) in sum.iter_mut().zip(pixel) {
+            *sum += *value as u64;
+        }
+    }
+    let len = pixels.len().max(1) as u64;
+    sum.map(|sum| ((sum + len / 2) / len) as u8)
+}
+
+///
+/// Построение палитры методом медианного сечения
+///
+/// # Параметры
+///
+/// * `pixels` - пиксели RGBA
+/// * `colors` - максимальное количество цветов палитры
+///
+/// # Возращает
+/// Палитру не более чем из `colors` цветов (меньше, если в изображении меньше различных цветов)
+///
+pub(crate) fn median_cut(pixels: &[[u8; 4]], colors: usize) -> Vec<[u8; 4]> {
+    let mut boxes = vec![pixels.to_vec()];
+    while boxes.len() < colors {
+        // Делится группа с наибольшим разбросом канала
+        let widest = boxes
+            .iter()
+            .enumerate()
+            .map(|(index, group)| (index, widest_channel(group)))
+            .max_by_key(|(_, (_, range))| *range);
+        let Some((index, (channel, range))) = widest else {
+            break;
+        };
+        if range == 0 {
+            break;
+        }
+        let mut group = boxes.swap_remove(index);
+        group.sort_unstable_by_key(|pixel| pixel[channel]);
+        // Граница сечения не разделяет пиксели с одинаковым значением канала
+        let median = group[group.len() / 2][channel];
+        let mut split = group.partition_point(|pixel| pixel[channel] < median);
+        if split == 0 {
+            split = group.partition_point(|pixel| pixel[channel] <= median);
+        }
+        let upper = group.split_off(split);
+        boxes.push(group);
+        boxes.push(upper);
+    }
+    boxes.iter().map(|group| average(group)).collect()
+}
+
+/// Индекс ближайшего цвета палитры
+fn nearest(palette: &[[u8; 4]], pixel: &[u8; 4]) -> u8 {
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, color)| {
+            color
+                .iter()
+                .zip(pixel)
+                .map(|(a, b)| (*a as i32 - *b as i32).pow(2))
+                .sum::<i32>()
+        })
+        .map(|(index, _)| index as u8)
+        .unwrap_or(0)
+}
+
+///
+/// Запись изображения в индексированный PNG
+///
+/// # Параметры
+///
+/// * `image` - обработанное изображение
+/// * `colors` - количество цветов палитры (2..=256)
+/// * `path` - путь к выходному файлу
+///
+pub(crate) fn write_indexed_png(
+    image: &RgbaImage,
+    colors: usize,
+    path: &Path,
+    metadata: Option<&ProcessingMetadata>,
+) -> Result<(), ImageProcessorError> {
+    let pixels: Vec<[u8; 4]> = image.pixels().map(|pixel| pixel.0).collect();
+    let palette = median_cut(&pixels, colors);
+    log::info!("Palette of {} colors (requested {})", palette.len(), colors);
+    let indices: Vec<u8> = pixels.iter().map(|pixel| nearest(&palette, pixel)).collect();
+    let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), image.width(), image.height());
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_palette(palette.iter().flat_map(|color| [color[0], color[1], color[2]]).collect::<Vec<u8>>());
+    encoder.set_trns(palette.iter().map(|color| color[3]).collect::<Vec<u8>>());
+    if let Some(metadata) = metadata {
+        metadata.add_chunks(&mut encoder)?;
+    }
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&indices)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_indexed_png_four_colors() {
+        let image = RgbaImage::from_fn(16, 16, |x, y| image::Rgba([(x * 16) as u8, (y * 16) as u8, 128, 255]));
+        let path = std::env::temp_dir().join("image_processor_palette.png");
+        write_indexed_png(&image, 4, &path, None).unwrap();
+        let decoded = image::open(&path).unwrap().to_rgba8();
+        assert_eq!(decoded.dimensions(), (16, 16));
+        let colors: HashSet<[u8; 4]> = decoded.pixels().map(|pixel| pixel.0).collect();
+        assert!(colors.len() <= 4, "{} colors", colors.len());
+        assert!(colors.len() > 1);
+    }
+
+    #[test]
+    fn test_median_cut_few_colors() {
+        let pixels = [[0, 0, 0, 255], [255, 255, 255, 255], [0, 0, 0, 255]];
+        let palette = median_cut(&pixels, 16);
+        assert_eq!(palette.len(), 2);
+        assert!(palette.contains(&[0, 0, 0, 255]));
+        assert!(palette.contains(&[255, 255, 255, 255]));
+    }
+}
@@ -0,0 +1,184 @@
+//! Модуль для чтения параметров плагина
+//!
+//! Предоставляет функциональность чтения JSON параметров плагина из файла или стандартного ввода
+
+use crate::error::ImageProcessorError;
+use image::ColorType;
+use plugins_support::color_type::{SOURCE_COLOR_TYPE_FIELD, SourceColorType};
+use std::ffi::{CString, NulError, c_char};
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+///
+/// Параметры плагина в виде нуль терминированной строки для передачи через FFI
+///
+/// Плагин читает строку по указателю во время вызова `process_image`, поэтому строка
+/// должна жить дольше вызова. `PluginInterface::process_image` принимает `&ParamsHandle`,
+/// а не сырой указатель: заимствование гарантирует, что строка не будет освобождена
+/// до возврата из плагина, в том числе при переносе вызова в цикл этапов
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ParamsHandle(CString);
+
+impl ParamsHandle {
+    ///
+    /// Владеющая обертка строки параметров
+    ///
+    /// # Возращает
+    /// `NulError`, если строка содержит нулевой байт
+    ///
+    pub(crate) fn new(params: impl Into<Vec<u8>>) -> Result<Self, NulError> {
+        Ok(ParamsHandle(CString::new(params)?))
+    }
+
+    /// Указатель на строку, действителен, пока жив `self`
+    pub(crate) fn as_ptr(&self) -> *const c_char {
+        self.0.as_ptr()
+    }
+}
+
+/// Путь, означающий чтение параметров из стандартного ввода
+pub(crate) const STDIN_PATH: &str = "-";
+
+/// Задан ли путь чтения из стандартного ввода
+pub(crate) fn is_stdin(path: &Path) -> bool {
+    path.as_os_str() == STDIN_PATH
+}
+
+///
+/// Чтение параметров плагина
+///
+/// # Параметры
+///
+/// * `path` - путь к файлу параметров, `-` для чтения из `stdin`
+/// * `stdin` - источник стандартного ввода
+///
+/// # Возращает
+/// Строку параметров
+///
+pub(crate) fn read_params(path: &Path, mut stdin: impl Read) -> Result<String, ImageProcessorError> {
+    if is_stdin(path) {
+        log::info!("Read params from stdin");
+        let mut params = String::new();
+        stdin.read_to_string(&mut params)?;
+        Ok(params)
+    } else {
+        Ok(fs::read_to_string(path)?)
+    }
+}
+
+///
+/// Исходный тип цвета изображения для передачи плагину
+///
+/// # Параметры
+///
+/// * `color` - тип цвета декодированного изображения
+///
+pub(crate) fn source_color_type(color: ColorType) -> SourceColorType {
+    match color {
+        ColorType::L8 => SourceColorType::L8,
+        ColorType::La8 => SourceColorType::La8,
+        ColorType::Rgb8 => SourceColorType::Rgb8,
+        ColorType::Rgba8 => SourceColorType::Rgba8,
+        ColorType::L16 => SourceColorType::L16,
+        ColorType::La16 => SourceColorType::La16,
+        ColorType::Rgb16 => SourceColorType::Rgb16,
+        ColorType::Rgba16 => SourceColorType::Rgba16,
+        ColorType::Rgb32F => SourceColorType::Rgb32F,
+        ColorType::Rgba32F => SourceColorType::Rgba32F,
+        _ => SourceColorType::Unknown,
+    }
+}
+
+///
+/// Добавление исходного типа цвета в зарезервированное поле параметров
+///
+/// # Параметры
+///
+/// * `params` - JSON параметры плагина
+/// * `color_type` - исходный тип цвета
+///
+/// # Возращает
+/// Параметры с полем `source_color_type`. Если параметры не являются JSON объектом,
+/// они возвращаются без изменений
+///
+pub(crate) fn inject_source_color_type(params: &str, color_type: SourceColorType) -> String {
+    match serde_json::from_str::<serde_json::Value>(params) {
+        Ok(serde_json::Value::Object(mut object)) => {
+            object.insert(SOURCE_COLOR_TYPE_FIELD.to_string(), color_type.code().into());
+            serde_json::Value::Object(object).to_string()
+        }
+        _ => {
+            log::warn!("Params are not a JSON object, source color type not passed");
+            params.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use plugins_support::config_parse::ConfigReader;
+    use std::ffi::{CStr, CString};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_read_params_stdin() {
+        let json = r#"{"step": 1, "radius": 2}"#;
+        let params = read_params(Path::new("-"), Cursor::new(json)).unwrap();
+        assert_eq!(params, json);
+        let params_cstring = CString::new(params).unwrap();
+        assert_eq!(params_cstring.to_str().unwrap(), json);
+    }
+
+    #[test]
+    fn test_read_params_file() {
+        let path = std::env::temp_dir().join("image_processor_params_file.json");
+        fs::write(&path, r#"{"vertical_flip": true}"#).unwrap();
+        let params = read_params(&path, Cursor::new("ignored")).unwrap();
+        assert_eq!(params, r#"{"vertical_flip": true}"#);
+    }
+
+    #[test]
+    fn test_inject_source_color_type_grayscale() {
+        #[derive(serde::Deserialize)]
+        struct ConfigTransform {
+            radius: usize,
+            source_color_type: SourceColorType,
+        }
+        let gray = image::DynamicImage::ImageLuma8(image::GrayImage::new(2, 2));
+        let rgba = gray.to_rgba8();
+        assert_eq!(rgba.len(), 2 * 2 * 4);
+        let params = inject_source_color_type(r#"{"radius": 1}"#, source_color_type(gray.color()));
+        let config: ConfigReader<ConfigTransform> = ConfigReader::try_from(params.as_str()).unwrap();
+        assert_eq!(config.config.radius, 1);
+        assert_eq!(config.config.source_color_type, SourceColorType::L8);
+    }
+
+    #[test]
+    fn test_inject_source_color_type_not_object() {
+        assert_eq!(inject_source_color_type("[1]", SourceColorType::Rgb8), "[1]");
+    }
+
+    /// Функция с сигнатурой параметра `process_image`: длина строки по указателю
+    unsafe extern "C" fn params_len(params: *const c_char) -> usize {
+        unsafe { CStr::from_ptr(params) }.to_bytes().len()
+    }
+
+    #[test]
+    fn test_params_handle() {
+        let json = r#"{"radius": 3}"#;
+        let params = inject_source_color_type(json, SourceColorType::Rgba8);
+        let expected = params.len();
+        let handle = ParamsHandle::new(params).unwrap();
+        let pointer = handle.as_ptr();
+        for _ in 0..3 {
+            assert_eq!(unsafe { params_len(handle.as_ptr()) }, expected);
+        }
+        assert_eq!(handle.as_ptr(), pointer);
+        let params = unsafe { CStr::from_ptr(pointer) }.to_str().unwrap();
+        assert_eq!(params, r#"{"radius":3,"source_color_type":4}"#);
+        assert!(ParamsHandle::new("{\0}").is_err());
+    }
+}
@@ -1,29 +1,304 @@
 //! Модуль для реализации интрфейса плагина
 //!
 //! Предоставляет функциональность по взаимодействию с плагинами
-use libloading::{Library, Symbol};
-use std::ffi::{c_char, c_uint};
+use crate::error::ImageProcessorError;
+use crate::params::ParamsHandle;
+use libloading::{AsFilename, Library};
+use plugins_support::abi::{
+    ABI_VERSION, AbiProbeFn, PROBE_HEIGHT, PROBE_PARAMS, PROBE_WIDTH, ProcessImageFn, probe_pattern,
+};
+use plugins_support::progress::ProgressCallback;
+use std::cell::{Cell, OnceCell};
+use std::ffi::{CStr, c_char, c_uint};
+/// Сигнатура функции обработки изображения с уведомлениями о прогрессе
+type ProcessImageWithProgressFn = unsafe extern "C" fn(
+    width: c_uint,
+    height: c_uint,
+    rgba_data: *mut u8,
+    params: *const c_char,
+    progress: Option<ProgressCallback>,
+);
+/// Сигнатура функции счетчика предупреждений плагина
+type WarningCountFn = extern "C" fn() -> c_uint;
+/// Сигнатура функции JSON Schema параметров плагина
+type ParamsSchemaFn = extern "C" fn() -> *const c_char;
 
 pub(crate) struct Plugin {
     plugin: Library,
+    /// Символы плагина, разрешенные при первом вызове `interface`
+    interface: OnceCell<PluginInterface>,
+    /// Количество разрешений символов (для проверки кеширования)
+    resolved: Cell<usize>,
 }
-pub(crate) struct PluginInterface<'a> {
-    pub process_image: Symbol<
-        'a,
-        unsafe extern "C" fn(width: c_uint, height: c_uint, rgba_data: *mut u8, params: *const c_char),
-    >,
+
+/// Функции плагина. Доступны только по ссылке, заимствованной у `Plugin`,
+/// поэтому не переживают выгрузку библиотеки
+pub(crate) struct PluginInterface {
+    process_image: ProcessImageFn,
+    /// Обработка с уведомлениями о прогрессе (опционально)
+    process_image_with_progress: Option<ProcessImageWithProgressFn>,
+    /// Счетчик предупреждений и ошибок плагина (опционально)
+    warning_count: Option<WarningCountFn>,
+    /// JSON Schema параметров плагина (опционально)
+    params_schema: Option<ParamsSchemaFn>,
+}
+
+impl PluginInterface {
+    /// Вызов `process_image` плагина. Строка `params` заимствована на время вызова
+    ///
+    /// # Safety
+    /// `rgba_data` должен указывать на буфер `width * height * 4` байт
+    pub(crate) unsafe fn process_image(
+        &self,
+        width: c_uint,
+        height: c_uint,
+        rgba_data: *mut u8,
+        params: &ParamsHandle,
+    ) {
+        unsafe { (self.process_image)(width, height, rgba_data, params.as_ptr()) }
+    }
+
+    /// Вызов `process_image_with_progress` плагина с функцией прогресса `progress`.
+    /// Если плагин ее не экспортирует, вызывается `process_image` без уведомлений
+    ///
+    /// # Safety
+    /// `rgba_data` должен указывать на буфер `width * height * 4` байт
+    pub(crate) unsafe fn process_image_with_progress(
+        &self,
+        width: c_uint,
+        height: c_uint,
+        rgba_data: *mut u8,
+        params: &ParamsHandle,
+        progress: ProgressCallback,
+    ) {
+        match self.process_image_with_progress {
+            Some(process) => unsafe { process(width, height, rgba_data, params.as_ptr(), Some(progress)) },
+            None => unsafe { self.process_image(width, height, rgba_data, params) },
+        }
+    }
+
+    /// Значение счетчика предупреждений плагина, `None` если плагин его не экспортирует
+    pub(crate) fn warning_count(&self) -> Option<c_uint> {
+        self.warning_count.map(|count| count())
+    }
+
+    /// JSON Schema параметров плагина, `None` если плагин ее не экспортирует
+    pub(crate) fn params_schema(&self) -> Option<String> {
+        self.params_schema.map(|schema| {
+            // Плагин возвращает указатель на статическую нуль терминированную строку
+            unsafe { CStr::from_ptr(schema()) }.to_string_lossy().into_owned()
+        })
+    }
 }
 
 impl Plugin {
-    pub(crate) fn new(filename: &str) -> Result<Self, libloading::Error> {
+    pub(crate) fn new(filename: impl AsFilename) -> Result<Self, libloading::Error> {
         Ok(Plugin {
             plugin: unsafe { Library::new(filename) }?,
+            interface: OnceCell::new(),
+            resolved: Cell::new(0),
         })
     }
-    pub(crate) fn interface(&self) -> Result<PluginInterface<'_>, libloading::Error> {
-        Ok(PluginInterface {
+
+    /// Интерфейс плагина. Символы разрешаются один раз, повторные вызовы возвращают кеш.
+    /// В отладочной сборке при первом вызове выполняется проба ABI (см. [`Plugin::probe_abi`])
+    pub(crate) fn interface(&self) -> Result<&PluginInterface, ImageProcessorError> {
+        if let Some(interface) = self.interface.get() {
+            return Ok(interface);
+        }
+        if cfg!(debug_assertions) {
+            self.probe_abi()?;
+        }
+        let interface = PluginInterface {
             // подгрузка функции по символу `trade`
-            process_image: unsafe { self.plugin.get("process_image") }?,
-        })
+            process_image: *unsafe { self.plugin.get::<ProcessImageFn>("process_image") }?,
+            process_image_with_progress: unsafe {
+                self.plugin.get::<ProcessImageWithProgressFn>("process_image_with_progress")
+            }
+            .ok()
+            .map(|symbol| *symbol),
+            warning_count: unsafe { self.plugin.get::<WarningCountFn>("plugin_warning_count") }
+                .ok()
+                .map(|symbol| *symbol),
+            params_schema: unsafe { self.plugin.get::<ParamsSchemaFn>("plugin_params_schema") }
+                .ok()
+                .map(|symbol| *symbol),
+        };
+        self.resolved.set(self.resolved.get() + 1);
+        Ok(self.interface.get_or_init(|| interface))
+    }
+
+    ///
+    /// Проба ABI плагина
+    ///
+    /// Вызывает экспортированную плагином `abi_probe` с сигнатурой `process_image` на буфере
+    /// `PROBE_WIDTH x PROBE_HEIGHT` и проверяет версию ABI и содержимое буфера. Расхождение
+    /// типов или порядка параметров между хостом и плагином проявляется как неверная версия
+    /// или неизмененный буфер, и загрузка завершается `ImageProcessorError::AbiMismatch`
+    /// вместо неопределенного поведения при обработке. Плагины без `abi_probe` пропускаются
+    ///
+    fn probe_abi(&self) -> Result<(), ImageProcessorError> {
+        let Ok(probe) = (unsafe { self.plugin.get::<AbiProbeFn>("abi_probe") }) else {
+            log::debug!("Plugin does not export abi_probe, ABI check skipped");
+            return Ok(());
+        };
+        let mut buf = vec![0; (PROBE_WIDTH * PROBE_HEIGHT * 4) as usize];
+        let version = unsafe { probe(PROBE_WIDTH, PROBE_HEIGHT, buf.as_mut_ptr(), PROBE_PARAMS.as_ptr()) };
+        if version != ABI_VERSION {
+            return Err(ImageProcessorError::AbiMismatch(format!(
+                "abi_probe returned version {}, expected {}",
+                version, ABI_VERSION
+            )));
+        }
+        if buf != probe_pattern(buf.len()) {
+            return Err(ImageProcessorError::AbiMismatch(
+                "abi_probe left unexpected buffer contents".to_owned(),
+            ));
+        }
+        log::debug!("Plugin ABI version {} verified", version);
+        Ok(())
+    }
+
+    /// Количество разрешений символов
+    #[cfg(test)]
+    pub(crate) fn resolve_count(&self) -> usize {
+        self.resolved.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_plugin::{MockPlugin, workspace_plugins};
+    use plugins_support::testkit::check_plugin;
+
+    /// Повторный вызов `interface` для собранного плагина размытия
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_interface_cached() {
+        let library = workspace_plugins().join("libblur_plugin.so");
+        let plugin = Plugin::new(&library).unwrap();
+        let first = plugin.interface().unwrap();
+        let second = plugin.interface().unwrap();
+        assert!(std::ptr::eq(first, second));
+        assert_eq!(plugin.resolve_count(), 1);
+        let params = ParamsHandle::new(r#"{"radius": 1, "step": 1}"#).unwrap();
+        let outputs = [first, second].map(|interface| {
+            let mut buf: Vec<u8> = (0..16).map(|value| value * 10).collect();
+            unsafe { interface.process_image(2, 2, buf.as_mut_ptr(), &params) };
+            buf
+        });
+        assert_eq!(outputs[0], outputs[1]);
+        assert!(first.warning_count().is_some());
+        assert!(first.params_schema().unwrap().contains("radius"));
+    }
+
+    #[test]
+    fn test_mock_plugin_symbols() {
+        let library = MockPlugin::new("mock_full")
+            .warning_count(3)
+            .params_schema(r#"{"type": "object"}"#)
+            .build();
+        let plugin = Plugin::new(&library).unwrap();
+        let interface = plugin.interface().unwrap();
+        let params = ParamsHandle::new("{}").unwrap();
+        let mut buf = vec![0, 100, 255, 7];
+        unsafe { interface.process_image(1, 1, buf.as_mut_ptr(), &params) };
+        assert_eq!(buf, [255, 155, 0, 7]);
+        assert_eq!(interface.warning_count(), Some(3));
+        assert_eq!(interface.params_schema().as_deref(), Some(r#"{"type": "object"}"#));
+    }
+
+    #[test]
+    fn test_mock_plugin_custom_body() {
+        let library = MockPlugin::new("mock_custom_body")
+            .item("const FILL: u8 = 42;")
+            .process_body("unsafe { std::ptr::write_bytes(rgba_data, FILL, width as usize * height as usize * 4) };")
+            .build();
+        let plugin = Plugin::new(&library).unwrap();
+        let params = ParamsHandle::new("{}").unwrap();
+        let mut buf = vec![0; 2 * 4];
+        unsafe { plugin.interface().unwrap().process_image(2, 1, buf.as_mut_ptr(), &params) };
+        assert_eq!(buf, [42; 8]);
+    }
+
+    #[test]
+    fn test_mock_plugin_optional_symbols() {
+        let plugin = Plugin::new(MockPlugin::new("mock_minimal").build()).unwrap();
+        let interface = plugin.interface().unwrap();
+        assert_eq!(interface.warning_count(), None);
+        assert_eq!(interface.params_schema(), None);
+        let plugin = Plugin::new(MockPlugin::new("mock_no_process").without_process_image().build()).unwrap();
+        assert!(plugin.interface().is_err());
+    }
+
+    /// Проба ABI тестового плагина с версией `VERSION`, повторяющая `plugins_support::abi::probe_response`
+    const ABI_PROBE: &str = r#"
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn abi_probe(width: c_uint, height: c_uint, rgba_data: *mut u8, params: *const c_char) -> c_uint {
+    if width != 3 || height != 2 || unsafe { std::ffi::CStr::from_ptr(params) } != c"abi_probe" {
+        return 0;
+    }
+    let rgba = unsafe { std::slice::from_raw_parts_mut(rgba_data, 24) };
+    for (index, byte) in rgba.iter_mut().enumerate() {
+        *byte = !(index as u8);
+    }
+    VERSION
+}"#;
+
+    #[test]
+    fn test_abi_probe_success() {
+        let probe = ABI_PROBE.replace("VERSION", &ABI_VERSION.to_string());
+        let plugin = Plugin::new(MockPlugin::new("mock_abi_ok").item(&probe).build()).unwrap();
+        let interface = plugin.interface().unwrap();
+        let params = ParamsHandle::new("{}").unwrap();
+        let mut buf = vec![0, 100, 255, 7];
+        unsafe { interface.process_image(1, 1, buf.as_mut_ptr(), &params) };
+        assert_eq!(buf, [255, 155, 0, 7]);
+    }
+
+    /// Расхождение ABI: другая версия и переставленные параметры (`height`, `width`).
+    /// Проба выполняется только в отладочной сборке
+    #[cfg(debug_assertions)]
+    #[test]
+    fn test_abi_probe_mismatch() {
+        let probe = ABI_PROBE.replace("VERSION", &(ABI_VERSION + 1).to_string());
+        let plugin = Plugin::new(MockPlugin::new("mock_abi_version").item(&probe).build()).unwrap();
+        let error = plugin.interface().err().unwrap();
+        assert!(matches!(error, ImageProcessorError::AbiMismatch(_)));
+        assert!(error.to_string().contains(&format!("version {}", ABI_VERSION + 1)));
+
+        let probe = ABI_PROBE
+            .replace("width: c_uint, height: c_uint", "height: c_uint, width: c_uint")
+            .replace("VERSION", &ABI_VERSION.to_string());
+        let plugin = Plugin::new(MockPlugin::new("mock_abi_swapped").item(&probe).build()).unwrap();
+        assert!(plugin.interface().err().unwrap().to_string().contains("version 0"));
+        assert_eq!(plugin.resolve_count(), 0);
+    }
+
+    /// Проверки `plugins_support::testkit` вызывают плагин и не проходят для плагина,
+    /// который молча игнорирует нулевые указатели, не отвечает на пробу ABI
+    /// и пишет за пределы буфера
+    #[test]
+    fn test_conformance_mock_plugin() {
+        let library = MockPlugin::new("mock_nonconforming")
+            .process_body(
+                "
+    if rgba_data.is_null() || params.is_null() {
+        return;
+    }
+    let len = width as usize * height as usize * 4;
+    let rgba = unsafe { std::slice::from_raw_parts_mut(rgba_data, len + 1) };
+    rgba[len] = 0;",
+            )
+            .params_schema("not json")
+            .build();
+        let report = check_plugin(&library, "{}");
+        assert!(!report.passed(), "{}", report);
+        let passed = |name: &str| report.check(name).unwrap_or_else(|| panic!("{} not run", name)).passed;
+        assert!(passed("load") && passed("symbol process_image"));
+        for name in ["abi_probe", "metadata plugin_params_schema", "null rgba_data", "null params", "2x2 call bounds"] {
+            assert!(!passed(name), "{}", name);
+        }
     }
 }
@@ -0,0 +1,100 @@
+//! Модуль для именованных пресетов обработки
+//!
+//! Предоставляет функциональность чтения TOML файла пресетов, в котором имени пресета
+//! соответствует упорядоченный список этапов (плагин и его параметры)
+//!
+//! ```toml
+//! [[vintage]]
+//! plugin = "libcolor_vision_plugin"
+//! params = { mode = "sepia" }
+//!
+//! [[vintage]]
+//! plugin = "libtexture_noise_plugin"
+//! params = { scale = 2.0, octaves = 1, opacity = 0.2 }
+//! ```
+
+use crate::error::ImageProcessorError;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Этап пресета
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub(crate) struct PresetStage {
+    /// Имя плагина без расширения
+    pub(crate) plugin: PathBuf,
+    /// Параметры плагина
+    #[serde(default)]
+    pub(crate) params: toml::Table,
+}
+
+impl PresetStage {
+    /// Параметры плагина в формате JSON
+    pub(crate) fn params_json(&self) -> Result<String, ImageProcessorError> {
+        Ok(serde_json::to_string(&self.params)?)
+    }
+}
+
+fn preset_error(message: String) -> ImageProcessorError {
+    log::error!("Preset: {}", message);
+    ImageProcessorError::PresetError(message)
+}
+
+///
+/// Этапы пресета из TOML файла пресетов
+///
+/// # Параметры
+///
+/// * `text` - содержимое файла пресетов
+/// * `name` - имя пресета
+///
+/// # Возращает
+/// Этапы в порядке файла или `ImageProcessorError::PresetError`, если файл не разобран,
+/// пресет не найден или не содержит этапов
+///
+pub(crate) fn preset_stages(text: &str, name: &str) -> Result<Vec<PresetStage>, ImageProcessorError> {
+    let mut presets: HashMap<String, Vec<PresetStage>> =
+        toml::from_str(text).map_err(|e| preset_error(e.to_string()))?;
+    let stages = presets
+        .remove(name)
+        .ok_or_else(|| preset_error(format!("preset {} not found", name)))?;
+    if stages.is_empty() {
+        return Err(preset_error(format!("preset {} has no stages", name)));
+    }
+    Ok(stages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRESETS: &str = r#"
+        [[vintage]]
+        plugin = "libblur_plugin"
+        params = { radius = 1, step = 2 }
+
+        [[vintage]]
+        plugin = "libmirror_plugin"
+        params = { horizontal_flip = true }
+
+        [[plain]]
+        plugin = "libfingerprint_plugin"
+    "#;
+
+    #[test]
+    fn test_preset_stages_in_order() {
+        let stages = preset_stages(PRESETS, "vintage").unwrap();
+        let plugins: Vec<_> = stages.iter().map(|stage| stage.plugin.clone()).collect();
+        assert_eq!(plugins, [PathBuf::from("libblur_plugin"), PathBuf::from("libmirror_plugin")]);
+        let params: serde_json::Value = serde_json::from_str(&stages[0].params_json().unwrap()).unwrap();
+        assert_eq!(params, serde_json::json!({"radius": 1, "step": 2}));
+        assert_eq!(preset_stages(PRESETS, "plain").unwrap()[0].params_json().unwrap(), "{}");
+    }
+
+    #[test]
+    fn test_preset_unknown() {
+        let error = preset_stages(PRESETS, "missing").unwrap_err();
+        assert!(error.to_string().contains("preset missing not found"));
+        assert!(preset_stages("[[broken]", "broken").is_err());
+    }
+}
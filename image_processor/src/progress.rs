@@ -0,0 +1,236 @@
+//! Модуль для отображения прогресса обработки
+//!
+//! Предоставляет полосу прогресса в stderr: для одного изображения ее продвигает функция
+//! обратного вызова плагина (`process_image_with_progress`), для манифеста - количество
+//! выполненных заданий. Полоса не выводится, если stderr не терминал или задан `--quiet`,
+//! чтобы не засорять логи и конвейеры
+
+use std::ffi::c_uint;
+use std::io::{IsTerminal, Write};
+use std::sync::{Mutex, PoisonError};
+
+/// Ширина полосы в символах
+const BAR_WIDTH: usize = 30;
+
+/// Полоса прогресса
+pub(crate) struct ProgressBar {
+    /// Подпись перед полосой
+    label: String,
+    /// Значение, соответствующее 100%
+    total: u64,
+    /// Последний выведенный процент
+    drawn: Option<u64>,
+    /// Длина последней выведенной строки (для очистки)
+    drawn_len: usize,
+    out: Box<dyn Write + Send>,
+}
+
+/// Нужно ли выводить полосу прогресса
+pub(crate) fn draw_enabled(quiet: bool, is_terminal: bool) -> bool {
+    !quiet && is_terminal
+}
+
+impl ProgressBar {
+    ///
+    /// Полоса прогресса, выводимая в `out`
+    ///
+    /// # Параметры
+    ///
+    /// * `label` - подпись перед полосой
+    /// * `total` - значение, соответствующее 100%
+    /// * `out` - поток вывода
+    ///
+    pub(crate) fn new(label: impl Into<String>, total: u64, out: Box<dyn Write + Send>) -> Self {
+        ProgressBar {
+            label: label.into(),
+            total,
+            drawn: None,
+            drawn_len: 0,
+            out,
+        }
+    }
+
+    /// Полоса прогресса в stderr, `None`, если stderr не терминал или задан `quiet`.
+    /// В тестах stderr не считается терминалом: полоса одна на процесс, а тесты параллельны
+    pub(crate) fn for_stderr(label: impl Into<String>, total: u64, quiet: bool) -> Option<Self> {
+        draw_enabled(quiet, !cfg!(test) && std::io::stderr().is_terminal())
+            .then(|| ProgressBar::new(label, total, Box::new(std::io::stderr())))
+    }
+
+    /// Установка позиции; строка перерисовывается, только если изменился процент
+    pub(crate) fn set(&mut self, position: u64) {
+        let percent = match self.total {
+            0 => 100,
+            total => position.min(total) * 100 / total,
+        };
+        if self.drawn == Some(percent) {
+            return;
+        }
+        let filled = percent as usize * BAR_WIDTH / 100;
+        let line = format!(
+            "{} [{}{}] {:>3}%",
+            self.label,
+            "#".repeat(filled),
+            "-".repeat(BAR_WIDTH - filled),
+            percent
+        );
+        // Ошибки вывода прогресса не влияют на обработку
+        let _ = write!(self.out, "\r{}", line);
+        let _ = self.out.flush();
+        self.drawn = Some(percent);
+        self.drawn_len = line.chars().count();
+    }
+
+    /// Очистка строки полосы перед выводом другого текста, следующий `set` нарисует ее заново
+    pub(crate) fn clear(&mut self) {
+        if self.drawn.take().is_some() {
+            let _ = write!(self.out, "\r{}\r", " ".repeat(self.drawn_len));
+            let _ = self.out.flush();
+        }
+    }
+
+    /// Завершение: перевод строки после выведенной полосы
+    pub(crate) fn finish(mut self) {
+        if self.drawn.is_some() {
+            let _ = writeln!(self.out);
+            let _ = self.out.flush();
+        }
+    }
+}
+
+/// Полоса прогресса текущего вызова плагина. Функция обратного вызова плагина не принимает
+/// контекст, поэтому полоса хранится глобально
+static PLUGIN_BAR: Mutex<Option<ProgressBar>> = Mutex::new(None);
+
+///
+/// Функция обратного вызова прогресса, передаваемая плагину
+///
+/// # Параметры
+///
+/// * `percent` - процент выполнения (0..=100)
+///
+pub(crate) extern "C" fn plugin_progress(percent: c_uint) {
+    let mut bar = PLUGIN_BAR.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(bar) = bar.as_mut() {
+        bar.set(u64::from(percent));
+    }
+}
+
+/// Полоса прогресса, установленная для [`plugin_progress`]; при удалении снимается и завершается
+pub(crate) struct PluginProgress(());
+
+impl PluginProgress {
+    /// Установка полосы для вызовов [`plugin_progress`] до удаления результата
+    pub(crate) fn install(bar: ProgressBar) -> Self {
+        *PLUGIN_BAR.lock().unwrap_or_else(PoisonError::into_inner) = Some(bar);
+        PluginProgress(())
+    }
+}
+
+impl Drop for PluginProgress {
+    fn drop(&mut self) {
+        let bar = PLUGIN_BAR.lock().unwrap_or_else(PoisonError::into_inner).take();
+        if let Some(bar) = bar {
+            bar.finish();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_plugin::MockPlugin;
+    use crate::params::ParamsHandle;
+    use crate::plugin_loader::Plugin;
+    use std::sync::Arc;
+
+    /// Поток вывода в общий буфер
+    #[derive(Clone, Default)]
+    struct SharedOutput(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedOutput {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedOutput {
+        fn text(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    #[test]
+    fn test_progress_suppressed() {
+        assert!(!draw_enabled(false, false));
+        assert!(!draw_enabled(true, true));
+        assert!(draw_enabled(false, true));
+        assert!(ProgressBar::for_stderr("quiet", 10, true).is_none());
+    }
+
+    #[test]
+    fn test_progress_bar_draw() {
+        let output = SharedOutput::default();
+        let mut bar = ProgressBar::new("Batch", 4, Box::new(output.clone()));
+        bar.set(1);
+        bar.set(1);
+        bar.clear();
+        bar.set(4);
+        bar.finish();
+        let text = output.text();
+        assert_eq!(text.matches("25%").count(), 1);
+        assert!(text.contains(&format!("Batch [{}] 100%", "#".repeat(BAR_WIDTH))));
+        assert!(text.ends_with("100%\n"));
+    }
+
+    /// Тестовый плагин сообщает прогресс 25, 50, 100 через функцию обратного вызова хоста
+    #[test]
+    fn test_plugin_progress_callback() {
+        let library = MockPlugin::new("mock_progress")
+            .item(
+                "#[unsafe(no_mangle)]
+#[allow(unused_variables)]
+pub unsafe extern \"C\" fn process_image_with_progress(width: c_uint, height: c_uint, rgba_data: *mut u8, \
+                 params: *const c_char, progress: Option<extern \"C\" fn(c_uint)>) {
+    if let Some(progress) = progress {
+        for percent in [25, 50, 50, 100] {
+            progress(percent);
+        }
+    }
+}",
+            )
+            .build();
+        let plugin = Plugin::new(&library).unwrap();
+        let interface = plugin.interface().unwrap();
+        let output = SharedOutput::default();
+        let params = ParamsHandle::new("{}").unwrap();
+        let mut buf = vec![0; 4];
+        {
+            let _progress = PluginProgress::install(ProgressBar::new("mock", 100, Box::new(output.clone())));
+            unsafe { interface.process_image_with_progress(1, 1, buf.as_mut_ptr(), &params, plugin_progress) };
+        }
+        let text = output.text();
+        assert_eq!(text.matches('\r').count(), 3, "{:?}", text);
+        assert!(text.contains(" 25%") && text.contains(" 50%") && text.ends_with("100%\n"));
+        // После снятия полосы вызовы не выводятся
+        plugin_progress(70);
+        assert_eq!(output.text(), text);
+
+        // Плагин без `process_image_with_progress`: вызывается `process_image` без уведомлений
+        let plugin = Plugin::new(MockPlugin::new("mock_no_progress").build()).unwrap();
+        let interface = plugin.interface().unwrap();
+        let output = SharedOutput::default();
+        let mut buf = vec![0, 100, 255, 7];
+        {
+            let _progress = PluginProgress::install(ProgressBar::new("mock", 100, Box::new(output.clone())));
+            unsafe { interface.process_image_with_progress(1, 1, buf.as_mut_ptr(), &params, plugin_progress) };
+        }
+        assert_eq!(buf, [255, 155, 0, 7]);
+        assert_eq!(output.text(), "");
+    }
+}
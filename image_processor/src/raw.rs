@@ -0,0 +1,129 @@
+//! Модуль для чтения несжатых пикселей без декодера
+//!
+//! Предоставляет функциональность `--raw WxH --raw-format rgba8|rgb8|gray8`: байты пикселей
+//! читаются из файла или стандартного ввода построчно без заголовка
+
+use crate::canvas::CanvasSize;
+use crate::error::ImageProcessorError;
+use crate::params::is_stdin;
+use clap::ValueEnum;
+use image::{DynamicImage, GrayImage, RgbImage, RgbaImage};
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+/// Формат несжатых пикселей
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum RawFormat {
+    /// 4 байта на пиксель: R, G, B, A
+    #[default]
+    Rgba8,
+    /// 3 байта на пиксель: R, G, B
+    Rgb8,
+    /// 1 байт на пиксель: яркость
+    Gray8,
+}
+
+impl RawFormat {
+    /// Количество байт на пиксель
+    pub(crate) fn bytes_per_pixel(self) -> usize {
+        match self {
+            RawFormat::Rgba8 => 4,
+            RawFormat::Rgb8 => 3,
+            RawFormat::Gray8 => 1,
+        }
+    }
+}
+
+fn raw_error(message: String) -> ImageProcessorError {
+    log::error!("Raw input: {}", message);
+    ImageProcessorError::RawInputError(message)
+}
+
+///
+/// Изображение из несжатых пикселей
+///
+/// # Параметры
+///
+/// * `bytes` - пиксели построчно, без заголовка
+/// * `size` - размер изображения
+/// * `format` - формат пикселей
+///
+/// # Возращает
+/// Изображение или `ImageProcessorError::RawInputError`, если длина данных
+/// не равна `W * H * bpp`
+///
+pub(crate) fn decode_raw(bytes: Vec<u8>, size: CanvasSize, format: RawFormat) -> Result<DynamicImage, ImageProcessorError> {
+    let expected = (size.width as usize)
+        .checked_mul(size.height as usize)
+        .and_then(|len| len.checked_mul(format.bytes_per_pixel()))
+        .ok_or_else(|| raw_error(format!("size {}x{} overflows", size.width, size.height)))?;
+    if bytes.len() != expected {
+        return Err(raw_error(format!(
+            "{} bytes, expected {} for {}x{} {:?}",
+            bytes.len(),
+            expected,
+            size.width,
+            size.height,
+            format
+        )));
+    }
+    let (width, height) = (size.width, size.height);
+    let image = match format {
+        RawFormat::Rgba8 => RgbaImage::from_raw(width, height, bytes).map(DynamicImage::ImageRgba8),
+        RawFormat::Rgb8 => RgbImage::from_raw(width, height, bytes).map(DynamicImage::ImageRgb8),
+        RawFormat::Gray8 => GrayImage::from_raw(width, height, bytes).map(DynamicImage::ImageLuma8),
+    };
+    image.ok_or(ImageProcessorError::ConvertFromRawError)
+}
+
+///
+/// Чтение несжатых пикселей из файла или стандартного ввода
+///
+/// # Параметры
+///
+/// * `input` - путь к файлу, `-` - стандартный ввод
+/// * `size` - размер изображения
+/// * `format` - формат пикселей
+/// * `stdin` - источник стандартного ввода
+///
+pub(crate) fn read_raw(
+    input: &Path,
+    size: CanvasSize,
+    format: RawFormat,
+    mut stdin: impl Read,
+) -> Result<DynamicImage, ImageProcessorError> {
+    let bytes = if is_stdin(input) {
+        log::info!("Read raw pixels from stdin");
+        let mut bytes = Vec::new();
+        stdin.read_to_end(&mut bytes)?;
+        bytes
+    } else {
+        fs::read(input)?
+    };
+    decode_raw(bytes, size, format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::ColorType;
+
+    const SIZE: CanvasSize = CanvasSize { width: 2, height: 1 };
+
+    #[test]
+    fn test_read_raw_formats() {
+        let image = read_raw(Path::new("-"), SIZE, RawFormat::Rgb8, &[1u8, 2, 3, 4, 5, 6][..]).unwrap();
+        assert_eq!(image.color(), ColorType::Rgb8);
+        assert_eq!(image.to_rgba8().into_raw(), [1, 2, 3, 255, 4, 5, 6, 255]);
+        let image = decode_raw(vec![10, 20], SIZE, RawFormat::Gray8).unwrap();
+        assert_eq!(image.to_rgba8().into_raw(), [10, 10, 10, 255, 20, 20, 20, 255]);
+    }
+
+    #[test]
+    fn test_raw_length_mismatch() {
+        let error = decode_raw(vec![0; 7], SIZE, RawFormat::Rgba8).unwrap_err();
+        assert!(matches!(error, ImageProcessorError::RawInputError(_)));
+        assert!(error.to_string().contains("7 bytes, expected 8"));
+    }
+}
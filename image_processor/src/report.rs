@@ -0,0 +1,161 @@
+//! Модуль для формирования отчета о запуске
+//!
+//! Предоставляет функциональность записи JSON отчета: пути, размеры изображения,
+//! плагин, параметры, время выполнения этапов и результат
+
+use crate::cli::Cli;
+use crate::error::ImageProcessorError;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Время выполнения этапов обработки в миллисекундах
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+pub(crate) struct Timings {
+    /// Чтение и декодирование изображения
+    pub(crate) decode_ms: f64,
+    /// Обработка плагином
+    pub(crate) process_ms: f64,
+    /// Кодирование и сохранение изображения
+    pub(crate) encode_ms: f64,
+    /// Общее время
+    pub(crate) total_ms: f64,
+}
+
+/// Отчет о запуске обработки
+#[derive(Serialize, Debug, Clone)]
+pub(crate) struct RunReport {
+    /// Путь к входному изображению
+    pub(crate) input: Option<PathBuf>,
+    /// Путь к выходному изображению
+    pub(crate) output: Option<PathBuf>,
+    /// Ширина изображения
+    pub(crate) width: Option<u32>,
+    /// Высота изображения
+    pub(crate) height: Option<u32>,
+    /// Имя плагина
+    pub(crate) plugin: Option<String>,
+    /// Параметры плагина (JSON, либо исходная строка, если она не является JSON)
+    pub(crate) params: Option<serde_json::Value>,
+    /// Время выполнения этапов
+    pub(crate) timings: Timings,
+    /// Успешность обработки
+    pub(crate) success: bool,
+    /// Текст ошибки
+    pub(crate) error: Option<String>,
+}
+
+/// Перевод длительности в миллисекунды
+pub(crate) fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+impl RunReport {
+    /// Создание отчета по параметрам командной строки
+    pub(crate) fn new(cli: &Cli) -> Self {
+        RunReport {
+            input: cli.input.clone(),
+            output: cli.output.clone(),
+            width: None,
+            height: None,
+            plugin: cli.plugin.as_ref().map(|plugin| plugin.to_string_lossy().into_owned()),
+            params: None,
+            timings: Timings::default(),
+            success: false,
+            error: None,
+        }
+    }
+
+    /// Сохранение параметров плагина в отчете
+    pub(crate) fn set_params(&mut self, params: &str) {
+        self.params = Some(
+            serde_json::from_str(params)
+                .unwrap_or_else(|_| serde_json::Value::String(params.to_string())),
+        );
+    }
+
+    /// Фиксация результата обработки
+    pub(crate) fn finish(&mut self, result: &Result<(), ImageProcessorError>, total: Duration) {
+        self.timings.total_ms = millis(total);
+        self.success = result.is_ok();
+        self.error = result.as_ref().err().map(|e| e.to_string());
+    }
+
+    /// Запись отчета в файл
+    pub(crate) fn write(&self, path: &Path) -> Result<(), ImageProcessorError> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        log::info!("Report written to {}", path.display());
+        Ok(())
+    }
+}
+
+///
+/// Запись отчетов заданий пакетной обработки массивом JSON
+///
+/// # Параметры
+///
+/// * `reports` - отчеты заданий в порядке манифеста
+/// * `path` - файл отчета
+///
+pub(crate) fn write_reports(reports: &[RunReport], path: &Path) -> Result<(), ImageProcessorError> {
+    fs::write(path, serde_json::to_string_pretty(reports)?)?;
+    log::info!("Report of {} jobs written to {}", reports.len(), path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use std::io;
+
+    fn cli() -> Cli {
+        Cli::parse_from([
+            "image-processor",
+            "--input",
+            "images.jpeg",
+            "--output",
+            "out.png",
+            "--plugin",
+            "libblur_plugin",
+            "--params",
+            "blur-plugin-config.json",
+            "--plugin-path",
+            "target/debug",
+        ])
+    }
+
+    #[test]
+    fn test_report_json() {
+        let mut report = RunReport::new(&cli());
+        report.width = Some(4);
+        report.height = Some(2);
+        report.set_params(r#"{"radius": 15, "step": 2}"#);
+        report.finish(&Ok(()), Duration::from_millis(5));
+        let path = std::env::temp_dir().join("image_processor_report.json");
+        report.write(&path).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json["plugin"], "libblur_plugin");
+        assert_eq!(json["width"], 4);
+        assert_eq!(json["height"], 2);
+        assert_eq!(json["params"]["radius"], 15);
+        assert_eq!(json["success"], true);
+        assert_eq!(json["timings"]["total_ms"], 5.0);
+    }
+
+    #[test]
+    fn test_report_failure() {
+        let mut report = RunReport::new(&cli());
+        report.set_params("not json");
+        let result = Err(ImageProcessorError::PathNotExist(io::Error::new(
+            io::ErrorKind::NotFound,
+            "Image not exists",
+        )));
+        report.finish(&result, Duration::ZERO);
+        assert!(!report.success);
+        assert!(report.error.unwrap().contains("Image not exists"));
+        assert_eq!(report.params, Some(serde_json::Value::String("not json".to_string())));
+    }
+}
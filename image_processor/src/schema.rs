@@ -0,0 +1,83 @@
+//! Модуль для проверки параметров плагина по JSON Schema
+//!
+//! Предоставляет функциональность `--validate-schema`: параметры проверяются по схеме,
+//! которую экспортирует плагин (`plugin_params_schema`), до вызова плагина
+
+use crate::error::ImageProcessorError;
+
+fn schema_error(message: String) -> ImageProcessorError {
+    log::error!("Params schema: {}", message);
+    ImageProcessorError::SchemaValidationError(message)
+}
+
+///
+/// Проверка параметров плагина по JSON Schema
+///
+/// # Параметры
+///
+/// * `schema` - JSON Schema параметров, `None` - плагин не экспортирует схему
+/// * `params` - параметры плагина (JSON)
+///
+/// # Возращает
+/// `SchemaUnsupported`, если схемы нет, или `SchemaValidationError` со списком ошибок
+/// вида `/radius: ...` (путь к полю и описание нарушения), по одной на строку
+///
+pub(crate) fn validate_params(schema: Option<&str>, params: &str) -> Result<(), ImageProcessorError> {
+    let Some(schema) = schema else {
+        log::error!("Params schema: plugin does not export plugin_params_schema");
+        return Err(ImageProcessorError::SchemaUnsupported);
+    };
+    let schema: serde_json::Value =
+        serde_json::from_str(schema).map_err(|e| schema_error(format!("invalid schema: {}", e)))?;
+    let validator =
+        jsonschema::validator_for(&schema).map_err(|e| schema_error(format!("invalid schema: {}", e)))?;
+    let params: serde_json::Value =
+        serde_json::from_str(params).map_err(|e| schema_error(format!("params are not JSON: {}", e)))?;
+    let errors: Vec<String> = validator
+        .iter_errors(&params)
+        .map(|error| {
+            let path = error.instance_path.to_string();
+            let path = if path.is_empty() { "/".to_string() } else { path };
+            format!("{}: {}", path, error)
+        })
+        .collect();
+    if errors.is_empty() {
+        return Ok(());
+    }
+    Err(schema_error(errors.join("\n")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLUR_SCHEMA: &str = include_str!("../../blur_plugin/params-schema.json");
+
+    #[test]
+    fn test_blur_schema_radius_violation() {
+        let path = std::env::temp_dir().join("image_processor_schema_params.json");
+        std::fs::write(&path, r#"{"radius": 0, "step": 1}"#).unwrap();
+        let params = std::fs::read_to_string(&path).unwrap();
+        let error = validate_params(Some(BLUR_SCHEMA), &params).unwrap_err();
+        assert!(matches!(error, ImageProcessorError::SchemaValidationError(_)));
+        let message = error.to_string();
+        assert!(message.contains("/radius: "), "{}", message);
+        let error = validate_params(Some(BLUR_SCHEMA), r#"{"radius": 3}"#).unwrap_err();
+        assert!(error.to_string().contains("step"), "{}", error);
+    }
+
+    #[test]
+    fn test_blur_schema_valid_params() {
+        for params in [
+            r#"{"radius": 3, "step": 2, "log_level": "debug"}"#,
+            r#"{"radius": {"r": 2, "g": 0, "b": 1}, "step": 1, "linear_light": true}"#,
+            r#"{"radius": [1, 2, 3], "step": 1, "regions": [{"x": 0, "y": 0, "width": 4, "height": 4}]}"#,
+        ] {
+            assert!(validate_params(Some(BLUR_SCHEMA), params).is_ok(), "{}", params);
+        }
+        assert!(matches!(
+            validate_params(None, "{}"),
+            Err(ImageProcessorError::SchemaUnsupported)
+        ));
+    }
+}
@@ -0,0 +1,58 @@
+//! Модуль для реализации строгого режима
+//!
+//! Предоставляет функциональность проверки, что плагин не записал в лог предупреждений и ошибок
+
+use crate::error::ImageProcessorError;
+use std::ffi::c_uint;
+
+///
+/// Проверка счетчика предупреждений плагина в строгом режиме
+///
+/// # Параметры
+///
+/// * `before` - значение `plugin_warning_count` до вызова плагина (`None` - символ не экспортирован)
+/// * `after` - значение `plugin_warning_count` после вызова плагина
+///
+/// # Возращает
+/// `StrictModeViolation`, если за время вызова плагин записал предупреждения или ошибки,
+/// `StrictModeUnsupported`, если плагин не экспортирует счетчик
+///
+pub(crate) fn check_strict(
+    before: Option<c_uint>,
+    after: Option<c_uint>,
+) -> Result<(), ImageProcessorError> {
+    match (before, after) {
+        (Some(before), Some(after)) if after > before => {
+            let count = (after - before) as usize;
+            log::error!("Strict mode: plugin logged {} warnings or errors", count);
+            Err(ImageProcessorError::StrictModeViolation(count))
+        }
+        (Some(_), Some(_)) => Ok(()),
+        _ => {
+            log::error!("Strict mode: plugin does not export plugin_warning_count");
+            Err(ImageProcessorError::StrictModeUnsupported)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strict_warning_fails() {
+        let result = check_strict(Some(0), Some(1));
+        assert!(matches!(result, Err(ImageProcessorError::StrictModeViolation(1))));
+    }
+
+    #[test]
+    fn test_strict_no_new_warnings() {
+        assert!(check_strict(Some(3), Some(3)).is_ok());
+    }
+
+    #[test]
+    fn test_strict_unsupported_plugin() {
+        let result = check_strict(None, None);
+        assert!(matches!(result, Err(ImageProcessorError::StrictModeUnsupported)));
+    }
+}
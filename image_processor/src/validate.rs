@@ -0,0 +1,268 @@
+//! Модуль для проверки параметров командной строки
+//!
+//! Предоставляет функциональность проверки существования путей перед обработкой изображения
+
+use crate::cli::Cli;
+use crate::error::ImageProcessorError;
+use crate::params::is_stdin;
+use std::ffi::OsString;
+use std::io;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Проверенные пути, необходимые для обработки изображения
+#[derive(Debug)]
+pub(crate) struct ValidatedPaths {
+    /// Путь к входному изображению
+    pub(crate) input: PathBuf,
+    /// Полный путь к библиотеке плагина (с расширением платформы)
+    pub(crate) plugin: PathBuf,
+    /// Путь к файлу параметров плагина (`-` - стандартный ввод)
+    pub(crate) params: PathBuf,
+}
+
+///
+/// Имя файла библиотеки плагина с расширением платформы
+///
+/// # Параметры
+///
+/// * `plugin` - имя плагина без расширения
+///
+pub(crate) fn plugin_library_name(plugin: &Path) -> OsString {
+    let mut plugin_lib = plugin.as_os_str().to_owned();
+    #[cfg(target_os = "windows")]
+    plugin_lib.push(".dll");
+    #[cfg(target_os = "linux")]
+    plugin_lib.push(".so");
+    plugin_lib
+}
+
+fn not_exist(message: &str) -> ImageProcessorError {
+    ImageProcessorError::PathNotExist(io::Error::new(ErrorKind::NotFound, message.to_owned()))
+}
+
+fn required<'a>(value: &'a Option<PathBuf>, name: &str) -> Result<&'a PathBuf, ImageProcessorError> {
+    value.as_ref().ok_or_else(|| {
+        log::error!("Missing argument {}", name);
+        ImageProcessorError::MissingArgument(name.to_owned())
+    })
+}
+
+fn check_input(cli: &Cli, input: &Path) -> Result<(), ImageProcessorError> {
+    if cli.raw.is_some() && is_stdin(input) {
+        return Ok(());
+    }
+    if !input.exists() {
+        log::error!("Could not find image {}", input.to_string_lossy());
+        return Err(not_exist("Image not exists"));
+    }
+    Ok(())
+}
+
+///
+/// Проверка входного изображения из параметров командной строки
+///
+/// # Возращает
+/// Путь к входному изображению или ошибку, если он не задан или не существует
+///
+pub(crate) fn validate_input(cli: &Cli) -> Result<PathBuf, ImageProcessorError> {
+    let input = required(&cli.input, "--input")?;
+    check_input(cli, input)?;
+    Ok(input.clone())
+}
+
+///
+/// Полный путь к библиотеке плагина в директории `--plugin-path`
+///
+/// # Параметры
+///
+/// * `cli` - разобранные параметры командной строки
+/// * `plugin_name` - имя плагина без расширения
+///
+/// # Возращает
+/// Путь к существующей библиотеке плагина
+///
+pub(crate) fn plugin_library(cli: &Cli, plugin_name: &Path) -> Result<PathBuf, ImageProcessorError> {
+    let plugin_path = required(&cli.plugin_path, "--plugin-path")?;
+    if !plugin_path.exists() {
+        log::error!("Could not find plugin {}", plugin_path.to_string_lossy());
+        return Err(not_exist("Path plugin not exists"));
+    }
+    let plugin = plugin_path.join(plugin_library_name(plugin_name));
+    log::info!("Plugin: {}", plugin.display());
+    if !plugin.exists() {
+        log::error!("Could not find plugin path {}", plugin.display());
+        return Err(not_exist("Lib plugin not exists"));
+    }
+    Ok(plugin)
+}
+
+///
+/// Проверка путей из параметров командной строки
+///
+/// # Параметры
+///
+/// * `cli` - разобранные параметры командной строки
+///
+/// # Возращает
+/// `ValidatedPaths` с путями, существование которых проверено,
+/// или `ImageProcessorError::PathNotExist` для первого отсутствующего пути
+///
+pub(crate) fn validate_cli(cli: &Cli) -> Result<ValidatedPaths, ImageProcessorError> {
+    let input = required(&cli.input, "--input")?;
+    let plugin_name = cli.plugin.as_ref().ok_or_else(|| {
+        log::error!("No plugin specified");
+        ImageProcessorError::PluginNotSpecified
+    })?;
+    let params = required(&cli.params, "--params")?;
+    let plugin = plugin_library(cli, plugin_name)?;
+    check_input(cli, input)?;
+    if is_stdin(input) && is_stdin(params) {
+        log::error!("Image and params cannot both be read from stdin");
+        return Err(ImageProcessorError::StdinConflict);
+    }
+    if !is_stdin(params) && !params.exists() {
+        log::error!("Could not find params file {}", params.to_string_lossy());
+        return Err(not_exist("Params file not exists"));
+    }
+    Ok(ValidatedPaths {
+        input: input.clone(),
+        plugin,
+        params: params.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use std::fs;
+
+    fn setup(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("image_processor_validate_{}", name));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("input.png"), b"").unwrap();
+        fs::write(dir.join("params.json"), b"{}").unwrap();
+        fs::write(dir.join(plugin_library_name(Path::new("libtest"))), b"").unwrap();
+        dir
+    }
+
+    fn cli(dir: &Path, plugin_path: &Path, input: &str, params: &str) -> Cli {
+        let args: Vec<OsString> = vec![
+            "image-processor".into(),
+            "--input".into(),
+            dir.join(input).into(),
+            "--output".into(),
+            dir.join("output.png").into(),
+            "--plugin".into(),
+            "libtest".into(),
+            "--params".into(),
+            dir.join(params).into(),
+            "--plugin-path".into(),
+            plugin_path.into(),
+        ];
+        Cli::parse_from(args)
+    }
+
+    fn error_message(cli: &Cli) -> String {
+        validate_cli(cli).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_validate_cli_ok() {
+        let dir = setup("ok");
+        let paths = validate_cli(&cli(&dir, &dir, "input.png", "params.json")).unwrap();
+        assert_eq!(paths.plugin, dir.join(plugin_library_name(Path::new("libtest"))));
+        assert_eq!(paths.input, dir.join("input.png"));
+    }
+
+    /// Имя плагина, не являющееся UTF-8, дает ошибку вместо паники
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_validate_cli_non_utf8_plugin() {
+        use std::os::unix::ffi::OsStrExt;
+        let dir = setup("non_utf8");
+        let mut cli = cli(&dir, &dir, "input.png", "params.json");
+        let name = std::ffi::OsStr::from_bytes(b"lib\xffplugin");
+        cli.plugin = Some(PathBuf::from(name));
+        assert!(error_message(&cli).contains("Lib plugin not exists"));
+        let mut expected = name.to_owned();
+        expected.push(".so");
+        assert_eq!(plugin_library_name(Path::new(name)), expected);
+    }
+
+    #[test]
+    fn test_validate_cli_missing_argument() {
+        let dir = setup("missing_argument");
+        let mut cli = cli(&dir, &dir, "input.png", "params.json");
+        cli.input = None;
+        assert!(error_message(&cli).contains("Missing argument --input"));
+    }
+
+    #[test]
+    fn test_validate_cli_no_plugin() {
+        let dir = setup("no_plugin");
+        let args: Vec<OsString> = vec![
+            "image-processor".into(),
+            "--input".into(),
+            dir.join("input.png").into(),
+            "--output".into(),
+            dir.join("output.png").into(),
+            "--params".into(),
+            dir.join("params.json").into(),
+            "--plugin-path".into(),
+            dir.clone().into(),
+        ];
+        let cli = Cli::parse_from(args);
+        let message = error_message(&cli);
+        assert!(message.contains("No plugin specified"));
+        assert!(message.contains("--info"));
+    }
+
+    #[test]
+    fn test_info_without_plugin() {
+        let cli = Cli::try_parse_from(["image-processor", "--input", "images.jpeg", "--info"]).unwrap();
+        assert!(cli.info);
+        assert!(cli.plugin.is_none());
+    }
+
+    #[test]
+    fn test_validate_cli_missing_plugin_dir() {
+        let dir = setup("plugin_dir");
+        let cli = cli(&dir, &dir.join("missing"), "input.png", "params.json");
+        assert!(error_message(&cli).contains("Path plugin not exists"));
+    }
+
+    #[test]
+    fn test_validate_cli_missing_plugin_lib() {
+        let dir = setup("plugin_lib");
+        let mut cli = cli(&dir, &dir, "input.png", "params.json");
+        cli.plugin = Some(PathBuf::from("libmissing"));
+        assert!(error_message(&cli).contains("Lib plugin not exists"));
+    }
+
+    #[test]
+    fn test_validate_cli_missing_input() {
+        let dir = setup("input");
+        let cli = cli(&dir, &dir, "missing.png", "params.json");
+        assert!(error_message(&cli).contains("Image not exists"));
+    }
+
+    #[test]
+    fn test_validate_cli_missing_params() {
+        let dir = setup("params");
+        let cli = cli(&dir, &dir, "input.png", "missing.json");
+        assert!(error_message(&cli).contains("Params file not exists"));
+    }
+
+    #[test]
+    fn test_validate_cli_params_stdin() {
+        let dir = setup("params_stdin");
+        let mut cli = cli(&dir, &dir, "input.png", "params.json");
+        cli.params = Some(PathBuf::from("-"));
+        assert!(validate_cli(&cli).is_ok());
+        cli.input = Some(PathBuf::from("-"));
+        cli.raw = Some(crate::canvas::CanvasSize { width: 2, height: 1 });
+        assert!(matches!(validate_cli(&cli), Err(ImageProcessorError::StdinConflict)));
+    }
+}
@@ -0,0 +1,21 @@
+//! Тесты глобального логгера хоста
+//!
+//! [`setup_logger`] устанавливает логгер на весь процесс, поэтому тест выполняется
+//! в отдельном тестовом бинарном файле и не влияет на модульные тесты `image-processor`
+
+use plugins_support::logger::{FileLogger, setup_logger};
+use std::fs;
+
+/// Глобальный логгер хоста и логгер-дескриптор плагина работают одновременно
+#[test]
+fn test_host_and_plugin_loggers() {
+    let host_file = std::env::temp_dir().join("image_processor_host_logger.log");
+    let plugin_file = std::env::temp_dir().join("image_processor_plugin_logger.log");
+    setup_logger(log::LevelFilter::Info, host_file.to_str().unwrap()).unwrap();
+    let plugin = FileLogger::open(log::LevelFilter::Info, plugin_file.to_str().unwrap()).unwrap();
+    log::info!("host message");
+    plugin.info("plugin message");
+    log::logger().flush();
+    assert!(fs::read_to_string(&host_file).unwrap().contains("host message"));
+    assert!(fs::read_to_string(plugin.path()).unwrap().contains("plugin message"));
+}
@@ -0,0 +1,173 @@
+//! Сквозные тесты: сборка плагинов workspace и запуск `image-processor` с ними
+//!
+//! Плагины собираются `cargo build` в каталог, где лежит исполняемый файл `image-processor`
+//! (тот же профиль и `CARGO_TARGET_DIR`), результат сравнивается с эталоном из `tests/golden`.
+//! Эталоны - изображения 16x12, полученные из [`source`]
+
+#![cfg(any(target_os = "linux", target_os = "windows"))]
+
+use image::{Rgba, RgbaImage};
+use plugins_support::testkit::check_plugin;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+
+/// Исполняемый файл `image-processor`, собранный cargo для интеграционных тестов
+const BINARY: &str = env!("CARGO_BIN_EXE_image-processor");
+/// Ширина тестового изображения
+const WIDTH: u32 = 16;
+/// Высота тестового изображения
+const HEIGHT: u32 = 12;
+
+/// Тестовое изображение: градиенты по R и G, произведение координат в B
+fn source() -> RgbaImage {
+    RgbaImage::from_fn(WIDTH, HEIGHT, |x, y| Rgba([(x * 16) as u8, (y * 20) as u8, (x * y * 7 % 256) as u8, 255]))
+}
+
+/// Каталог собранных плагинов; плагины собираются один раз на процесс тестов
+fn plugin_dir() -> &'static Path {
+    static DIR: OnceLock<PathBuf> = OnceLock::new();
+    DIR.get_or_init(|| {
+        let dir = Path::new(BINARY).parent().expect("binary directory").to_path_buf();
+        let workspace = Path::new(env!("CARGO_MANIFEST_DIR")).parent().expect("workspace directory");
+        let mut cargo = Command::new(std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into()));
+        cargo.current_dir(workspace).args(["build", "-p", "blur-plugin", "-p", "mirror-plugin"]);
+        // Каталог профиля dev называется debug, остальных - по имени профиля
+        match dir.file_name().and_then(|name| name.to_str()) {
+            Some("debug") | None => {}
+            Some(profile) => {
+                cargo.args(["--profile", profile]);
+            }
+        }
+        let status = cargo.status().expect("run cargo build");
+        assert!(status.success(), "cargo build of plugins failed: {}", status);
+        dir
+    })
+}
+
+///
+/// Имя плагина для `--plugin`: имя библиотеки без расширения, расширение добавляет `image-processor`
+///
+/// # Параметры
+///
+/// * `package` - имя пакета плагина
+///
+fn plugin_name(package: &str) -> String {
+    let name = package.replace('-', "_");
+    if cfg!(target_os = "windows") {
+        name
+    } else {
+        format!("lib{}", name)
+    }
+}
+
+/// Путь к собранной библиотеке плагина `package`
+fn library(package: &str) -> PathBuf {
+    let library = plugin_dir().join(format!("{}{}", plugin_name(package), std::env::consts::DLL_SUFFIX));
+    assert!(library.exists(), "{} not built", library.display());
+    library
+}
+
+///
+/// Запуск `image-processor` с плагином на тестовом изображении
+///
+/// # Параметры
+///
+/// * `package` - имя пакета плагина
+/// * `params` - JSON параметры плагина
+///
+/// # Возращает
+/// Выходное изображение
+///
+fn run_plugin(package: &str, params: &str) -> RgbaImage {
+    let plugin_dir = plugin_dir();
+    let plugin = plugin_name(package);
+    // Библиотека должна быть собрана до запуска
+    library(package);
+    // Отдельный каталог на тест: тесты выполняются параллельно, логи пишутся в текущий каталог
+    let dir = std::env::temp_dir().join(format!("image_processor_e2e_{}", package));
+    fs::create_dir_all(&dir).unwrap();
+    source().save(dir.join("input.png")).unwrap();
+    fs::write(dir.join("params.json"), params).unwrap();
+    let output = dir.join("output.png");
+    let _ = fs::remove_file(&output);
+    let result = Command::new(BINARY)
+        .current_dir(&dir)
+        .arg("--input")
+        .arg(dir.join("input.png"))
+        .arg("--output")
+        .arg(&output)
+        .arg("--plugin")
+        .arg(&plugin)
+        .arg("--params")
+        .arg(dir.join("params.json"))
+        .arg("--plugin-path")
+        .arg(plugin_dir)
+        .output()
+        .expect("run image-processor");
+    assert!(
+        result.status.success(),
+        "image-processor failed: {}\n{}",
+        result.status,
+        String::from_utf8_lossy(&result.stderr)
+    );
+    image::open(&output).unwrap().to_rgba8()
+}
+
+///
+/// Сравнение с эталоном
+///
+/// # Параметры
+///
+/// * `output` - выходное изображение
+/// * `golden` - имя файла эталона в `tests/golden`
+/// * `tolerance` - допустимое отличие значения канала
+///
+fn assert_golden(output: &RgbaImage, golden: &str, tolerance: u8) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(golden);
+    let golden = image::open(&path).unwrap().to_rgba8();
+    assert_eq!(output.dimensions(), golden.dimensions(), "{}", path.display());
+    for (x, y, pixel) in output.enumerate_pixels() {
+        let expected = golden.get_pixel(x, y);
+        let diff = pixel.0.iter().zip(expected.0).map(|(&value, golden)| value.abs_diff(golden)).max();
+        let diff = diff.unwrap_or(0);
+        assert!(
+            diff <= tolerance,
+            "pixel ({}, {}) {:?} differs from {} {:?}",
+            x,
+            y,
+            pixel.0,
+            path.display(),
+            expected.0
+        );
+    }
+}
+
+#[test]
+fn test_blur_plugin_end_to_end() {
+    let output = run_plugin("blur-plugin", r#"{"radius": 2, "step": 1}"#);
+    assert_ne!(output, source());
+    assert_golden(&output, "blur.png", 1);
+}
+
+#[test]
+fn test_mirror_plugin_end_to_end() {
+    let output = run_plugin("mirror-plugin", r#"{"horizontal_flip": true, "vertical_flip": true}"#);
+    assert_golden(&output, "mirror.png", 0);
+    // Двойное отражение - поворот на 180°
+    assert_eq!(output, image::imageops::rotate180(&source()));
+}
+
+/// Собранные плагины проходят все проверки `plugins_support::testkit`
+#[test]
+fn test_plugins_conformance() {
+    for (package, params) in [
+        ("blur-plugin", r#"{"radius": 1, "step": 1}"#),
+        ("mirror-plugin", r#"{"horizontal_flip": true}"#),
+    ] {
+        let report = check_plugin(&library(package), params);
+        assert!(report.passed(), "{}", report);
+        assert!(report.check("abi_probe").is_some_and(|check| check.passed));
+    }
+}
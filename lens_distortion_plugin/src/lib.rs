@@ -0,0 +1,321 @@
+#![warn(missing_docs)]
+//! Модуль для реализации обработки изображения - радиальная дисторсия объектива
+//!
+//! Предоставляет функциональность бочкообразной и подушкообразной дисторсии (и ее коррекции)
+//! по полиномиальной модели `r' = r * (1 + k1 * r^2 + k2 * r^4)`
+
+use log::LevelFilter;
+use plugins_support::config_parse::warn_ignored_fields;
+use plugins_support::image_size::rgba_len;
+use plugins_support::logger::{
+    LogTimezone, get_log_level, set_call_level, set_log_timezone, setup_logger_once,
+};
+use plugins_support::pixels::pixels_mut;
+use plugins_support::sampler::{Interpolation, sample};
+use plugins_support::{config_parse::ConfigReader, error::Error};
+use serde::Deserialize;
+use std::ffi::{CStr, c_char, c_uint};
+use std::slice;
+
+const PKG_NAME: &str = env!("CARGO_PKG_NAME");
+const BYTE_PER_PIXEL: usize = 4;
+
+plugins_support::export_warning_count!();
+plugins_support::export_abi_probe!();
+
+/// Поля конфигурации, которые использует плагин
+const KNOWN_FIELDS: &[&str] = &["k1", "k2", "center_x", "center_y", "edge", "interpolation"];
+
+/// Заполнение точек, которые берутся за пределами исходного изображения
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Edge {
+    /// Повтор крайних пикселей
+    #[default]
+    Clamp,
+    /// Прозрачный пиксель
+    Transparent,
+}
+
+#[derive(Deserialize, Debug)]
+struct ConfigTransform {
+    k1: f64,
+    k2: Option<f64>,
+    center_x: Option<f64>,
+    center_y: Option<f64>,
+    edge: Option<Edge>,
+    interpolation: Option<Interpolation>,
+    log_level: Option<String>,
+    log_tz: Option<LogTimezone>,
+}
+
+/// Радиальная дисторсия RGBA буфера
+///
+/// Для каждого пикселя на расстоянии `r` от центра (в долях половины диагонали) берется
+/// точка исходного изображения на расстоянии `r * (1 + k1 * r^2 + k2 * r^4)`.
+/// Положительный `k1` дает бочкообразную дисторсию, отрицательный - подушкообразную,
+/// для коррекции дисторсии объектива задаются коэффициенты противоположного знака
+///
+/// # Аргументы
+/// * `buf`    – изменяемый буфер RGBA (длина = width * height * 4)
+/// * `width`  – ширина в пикселях
+/// * `height` – высота в пикселях
+/// * `k1`, `k2` – коэффициенты дисторсии
+/// * `center` – оптический центр в долях ширины и высоты (0..=1)
+/// * `edge`   – заполнение точек за пределами изображения
+/// * `interpolation` – способ выборки точек между пикселями
+///
+#[allow(clippy::too_many_arguments)]
+pub fn lens_distortion(
+    buf: &mut [u8],
+    width: usize,
+    height: usize,
+    k1: f64,
+    k2: f64,
+    center: (f64, f64),
+    edge: Edge,
+    interpolation: Interpolation,
+) -> Result<(), Error> {
+    if !k1.is_finite() || !k2.is_finite() {
+        return Err(Error::ErrorValue("Coefficients must be finite".to_string()));
+    }
+    if !(0.0..=1.0).contains(&center.0) || !(0.0..=1.0).contains(&center.1) {
+        return Err(Error::ErrorValue("Center must be in 0..=1".to_string()));
+    }
+    let center_x = center.0 * (width - 1) as f64;
+    let center_y = center.1 * (height - 1) as f64;
+    let half_diagonal = 0.5 * (((width - 1) as f64).powi(2) + ((height - 1) as f64).powi(2)).sqrt();
+    let norm = half_diagonal.max(1.0);
+    let source = buf.to_vec();
+    let (max_x, max_y) = ((width - 1) as f64, (height - 1) as f64);
+    for (x, y, pixel) in pixels_mut(buf, width, height, BYTE_PER_PIXEL)? {
+        let dx = (x as f64 - center_x) / norm;
+        let dy = (y as f64 - center_y) / norm;
+        let r2 = dx * dx + dy * dy;
+        let factor = 1.0 + k1 * r2 + k2 * r2 * r2;
+        let source_x = center_x + dx * factor * norm;
+        let source_y = center_y + dy * factor * norm;
+        // Допуск на погрешность вычислений у самой границы
+        let outside = source_x < -0.5
+            || source_y < -0.5
+            || source_x > max_x + 0.5
+            || source_y > max_y + 0.5;
+        if edge == Edge::Transparent && outside {
+            pixel.fill(0);
+        } else {
+            pixel.copy_from_slice(&sample(&source, width, height, source_x, source_y, interpolation));
+        }
+    }
+    Ok(())
+}
+
+/// ```rust
+///
+///  Трансформация RGBA буффера радиальной дисторсией объектива
+///
+///
+///  # Параметры
+///   - `width` (`c_uint`):  ширина изображения в пикселях
+///   - `height` (`c_uint`): высота изображения в пикселях
+///   - `rgba_data` (`*mut u8`): Мутабельный сырой указатель на RGBA буфер. Размер буфера `width * height * 4` bytes.
+///   - `params` (`*const c_char`): Сырой указатель на нуль терменированноу строку  конфигурации JSON-формата.
+///
+///  # Параметры конфигурации (JSON формат)
+///   - `log_level` (optional, string): уровень логирования ("Debug", "Info").
+///   - `log_tz` (optional, string): часовой пояс меток времени лога ("local", "utc"), по умолчанию "local"
+///   - `k1` (required, float): коэффициент дисторсии при `r^2`: > 0 - бочкообразная, < 0 - подушкообразная
+///   - `k2` (optional, float): коэффициент дисторсии при `r^4`, по умолчанию 0
+///   - `center_x` (optional, float): оптический центр по горизонтали в долях ширины, 0..=1, по умолчанию 0.5
+///   - `center_y` (optional, float): оптический центр по вертикали в долях высоты, 0..=1, по умолчанию 0.5
+///   - `edge` (optional, string): заполнение за пределами изображения ("clamp", "transparent"), по умолчанию "clamp"
+///   - `interpolation` (optional, string): выборка точек ("nearest", "bilinear"), по умолчанию "bilinear"
+///
+///  # Пример JSON:
+///  ```json
+///  {
+///     "log_level": "Debug",
+///     "k1": -0.15,
+///     "k2": 0.02,
+///     "edge": "transparent"
+///  }
+///  ```
+///
+///
+///   # Error Handling
+///   - Ошибки фиксируются в логе
+///   - Поля конфигурации, которые плагин не использует, фиксируются в логе предупреждением
+///
+///
+///   # Пример С
+///  ```c
+///  // In C code
+///   unsigned int width = 1920;
+///   unsigned int height = 1080;
+///   unsigned char *image_data = ...; // RGBA buffer, allocated elsewhere
+///   const char *config = "{\"k1\": -0.15, \"edge\": \"clamp\"}";
+///   process_image(width, height, image_data, config);
+///   ```
+/// # Safety
+///  Данная функция  помечена `unsafe`:
+///   - Работа напрямую с сырыми указателями (`rgba_data`, `params`) предстаялет external C code
+///   - Использования недопустимого указателя или парметров width, height undefined behavior.
+/// ```
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn process_image(
+    width: c_uint,
+    height: c_uint,
+    rgba_data: *mut u8,
+    params: *const c_char,
+) {
+    let file = PKG_NAME.to_owned() + ".log";
+    if let Err(e) = setup_logger_once(LevelFilter::Debug, &file) {
+        match e {
+            Error::LoggerInitError(_) => {
+                log::warn!("Logger init error: {}", e);
+            }
+            _ => {
+                log::error!("Logger init error: {}", e);
+                return;
+            }
+        }
+    }
+    log::info!("Start plugin {}", &file);
+    if params.is_null() {
+        log::error!("Pointer params is_null");
+        return;
+    }
+    let config = unsafe { CStr::from_ptr(params) };
+    let params_config = match config.to_str() {
+        Ok(config) => {
+            warn_ignored_fields(config, KNOWN_FIELDS);
+            let config: Result<ConfigReader<ConfigTransform>, Error> =
+                ConfigReader::try_from(config);
+            if let Ok(config) = config {
+                config
+            } else {
+                log::error!("Error converting config to string");
+                return;
+            }
+        }
+        _ => {
+            log::error!("Invalid config file");
+            return;
+        }
+    };
+    // Уровень из конфигурации действует только в потоке вызова до его завершения
+    let _log_level = params_config
+        .config
+        .log_level
+        .as_deref()
+        .map(|log_level| set_call_level(get_log_level(log_level)));
+    if let Some(log_tz) = params_config.config.log_tz {
+        set_log_timezone(log_tz);
+    }
+    if rgba_data.is_null() {
+        log::error!("Null pointer rgba_data");
+        return;
+    }
+    if width == 0 {
+        log::error!("width cannot be 0");
+        return;
+    }
+    if height == 0 {
+        log::error!("height cannot be 0");
+        return;
+    }
+    let len_in_pixel = match rgba_len(width, height) {
+        Ok(len) => len,
+        Err(e) => {
+            log::error!("Length calculation failed: {}", e);
+            return;
+        }
+    };
+    let height: usize = match height.try_into() {
+        Ok(h) => h,
+        Err(_) => {
+            log::error!("Height conversion failed");
+            return;
+        }
+    };
+    let width: usize = match width.try_into() {
+        Ok(w) => w,
+        Err(_) => {
+            log::error!("Width conversion failed");
+            return;
+        }
+    };
+    log::info!("Start converting image");
+    let buf = unsafe { slice::from_raw_parts_mut(rgba_data, len_in_pixel) };
+    let config = &params_config.config;
+    if let Err(e) = lens_distortion(
+        buf,
+        width,
+        height,
+        config.k1,
+        config.k2.unwrap_or(0.0),
+        (config.center_x.unwrap_or(0.5), config.center_y.unwrap_or(0.5)),
+        config.edge.unwrap_or_default(),
+        config.interpolation.unwrap_or_default(),
+    ) {
+        log::error!("Lens distortion error: {}", e);
+        return;
+    }
+    log::info!("Finish converting image");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    const SIZE: usize = 21;
+
+    /// Белое изображение с черной горизонтальной линией в строке 3
+    fn line() -> Vec<u8> {
+        let mut buf = vec![255; SIZE * SIZE * BYTE_PER_PIXEL];
+        for (index, pixel) in buf.chunks_exact_mut(BYTE_PER_PIXEL).enumerate() {
+            if index / SIZE == 3 {
+                pixel[..3].fill(0);
+            }
+        }
+        buf
+    }
+
+    /// Строка верхней половины, где столбец `x` темнее всего
+    fn darkest_row(buf: &[u8], x: usize) -> usize {
+        (0..SIZE / 2)
+            .min_by_key(|y| buf[(y * SIZE + x) * BYTE_PER_PIXEL])
+            .unwrap()
+    }
+
+    #[test]
+    fn test_lens_distortion_identity() {
+        let mut buf = line();
+        let json = r#"{"k1": 0.0}"#;
+        let params_cstring = CString::new(json).unwrap();
+        let size = SIZE as c_uint;
+        unsafe { process_image(size, size, buf.as_mut_ptr(), params_cstring.as_ptr()) };
+        assert_eq!(buf, line());
+    }
+
+    #[test]
+    fn test_lens_distortion_barrel_bends_line() {
+        let mut buf = line();
+        let center = (0.5, 0.5);
+        lens_distortion(&mut buf, SIZE, SIZE, 0.3, 0.0, center, Edge::Clamp, Interpolation::Bilinear).unwrap();
+        // В центре линия остается на месте, у края смещается к центру изображения
+        assert_eq!(darkest_row(&buf, SIZE / 2), 3);
+        assert!(darkest_row(&buf, 2) > 3);
+    }
+
+    #[test]
+    fn test_lens_distortion_transparent_edge() {
+        let mut buf = vec![255; SIZE * SIZE * BYTE_PER_PIXEL];
+        let center = (0.5, 0.5);
+        lens_distortion(&mut buf, SIZE, SIZE, 0.3, 0.0, center, Edge::Transparent, Interpolation::Nearest)
+            .unwrap();
+        assert_eq!(&buf[..BYTE_PER_PIXEL], [0, 0, 0, 0]);
+        let middle = (SIZE / 2 * SIZE + SIZE / 2) * BYTE_PER_PIXEL;
+        assert_eq!(&buf[middle..middle + BYTE_PER_PIXEL], [255, 255, 255, 255]);
+    }
+}
@@ -3,7 +3,11 @@
 //! Предоставляет функциональность по транформации изображения - вертикальное, горизотальное отражение
 #![warn(missing_docs)]
 use log::LevelFilter;
-use plugins_support::logger::{get_log_level, setup_logger};
+use plugins_support::logger::{
+    LogTimezone, get_log_level, set_call_level, set_log_timezone, setup_logger_once,
+};
+use plugins_support::config_parse::warn_ignored_fields;
+use plugins_support::geometry::Geometry;
 use plugins_support::{config_parse::ConfigReader, error::Error};
 use serde::Deserialize;
 use std::ffi::{CStr, c_char, c_uint};
@@ -12,11 +16,18 @@ use std::ptr;
 const PKG_NAME: &str = env!("CARGO_PKG_NAME");
 const BYTE_PER_PIXEL: usize = 4;
 
+plugins_support::export_warning_count!();
+plugins_support::export_abi_probe!();
+
+/// Поля конфигурации, которые использует плагин
+const KNOWN_FIELDS: &[&str] = &["vertical_flip", "horizontal_flip"];
+
 #[derive(Deserialize, Debug)]
 struct ConfigTransform {
     vertical_flip: Option<bool>,
     horizontal_flip: Option<bool>,
     log_level: Option<String>,
+    log_tz: Option<LogTimezone>,
 }
 
 /// ```rust
@@ -32,6 +43,7 @@ struct ConfigTransform {
 ///
 ///  # Параметры конфигурации (JSON формат)
 ///   - `log_level` (optional, string): уровень логирования ("Debug", "Info").
+///   - `log_tz` (optional, string): часовой пояс меток времени лога ("local", "utc"), по умолчанию "local"
 ///   - `vertical_flip` (optional, bool): Вертикальное отражение
 ///   - `horizontal_flip` (optional, bool): Горизонтальное отражение
 ///
@@ -45,8 +57,21 @@ struct ConfigTransform {
 ///  ```
 ///
 ///
+///  # Изображения толщиной в один пиксель
+///   - Для изображения высотой 1 пиксель (например, 4x1) вертикальное отражение не выполняет
+///     ни одной перестановки, горизонтальное переставляет пиксели единственной строки
+///   - Для изображения шириной 1 пиксель (например, 1x4) горизонтальное отражение не выполняет
+///     ни одной перестановки, вертикальное переставляет пиксели единственного столбца
+///
+///  # Нечетная ширина и высота
+///   - Горизонтальное отражение переставляет столбцы `x` и `width - 1 - x` для `x < width / 2`,
+///     поэтому при нечетной ширине центральный столбец не затрагивается (и не переставляется
+///     сам с собой дважды); при ширине 1 цикл не выполняется, `width - 1 - x` не вычисляется
+///   - Вертикальное отражение аналогично оставляет на месте центральную строку при нечетной высоте
+///
 ///   # Error Handling
 ///   - Ошибки фиксируются в логе
+///   - Поля конфигурации, которые плагин не использует, фиксируются в логе предупреждением
 ///
 ///
 ///   # Пример С
@@ -72,7 +97,7 @@ pub unsafe extern "C" fn process_image(
     params: *const c_char,
 ) {
     let file = PKG_NAME.to_owned() + ".log";
-    if let Err(e) = setup_logger(LevelFilter::Debug, &file){
+    if let Err(e) = setup_logger_once(LevelFilter::Debug, &file){
         match e {
             Error::LoggerInitError(_) => {
                 log::warn!("Logger init error: {}", e);
@@ -91,6 +116,7 @@ pub unsafe extern "C" fn process_image(
     let config = unsafe { CStr::from_ptr(params) };
     let params_config = match config.to_str() {
         Ok(config) => {
+            warn_ignored_fields(config, KNOWN_FIELDS);
             let config: Result<ConfigReader<ConfigTransform>, Error> =
                 ConfigReader::try_from(config);
             if let Ok(config) = config {
@@ -105,67 +131,46 @@ pub unsafe extern "C" fn process_image(
             return;
         }
     };
-    if let Some(log_level) = params_config.config.log_level {
-        let log_level_filter = get_log_level(&log_level);
-        log::set_max_level(log_level_filter);
+    // Уровень из конфигурации действует только в потоке вызова до его завершения
+    let _log_level = params_config
+        .config
+        .log_level
+        .as_deref()
+        .map(|log_level| set_call_level(get_log_level(log_level)));
+    if let Some(log_tz) = params_config.config.log_tz {
+        set_log_timezone(log_tz);
     }
     if rgba_data.is_null() {
         log::error!("Null pointer rgba_data");
         return;
     }
-    if width == 0 {
-        log::error!("width cannot be 0");
-        return;
-    }
-    if height == 0 {
-        log::error!("height cannot be 0");
-        return;
-    }
-    let width: usize = match  width.try_into(){
-        Ok(w) => w,
-        Err(_) => {
-            log::error!("Width conversion failed");
-            return;
-        }
-    };
-    let height: usize = match height.try_into(){
-       Ok(h) => h,
-        Err(_) => {
-            log::error!("Height conversion failed");
+    let geometry = match Geometry::new(width, height, BYTE_PER_PIXEL, None) {
+        Ok(geometry) => geometry,
+        Err(e) => {
+            log::error!("Invalid image size: {}", e);
             return;
         }
     };
+    let (width, height) = (geometry.width(), geometry.height());
     if let Some(vertical) = params_config.config.vertical_flip
         && vertical
     {
         log::info!("Flipped vertical");
-        let row_size = match width.checked_mul(BYTE_PER_PIXEL) {
-            Some(size) => size,
-            None => {
-                log::error!("Width out of bounds");
-                return;
-            }
-        };
         for i in 0..(height / 2) {
-            let top_offset =  match i.checked_mul(row_size)
-            {
-                Some(top_offset) => top_offset,
-                None => {
-                    log::error!("Top offset out of bounds");
-                    return;
-                }
-            };
-            let bottom_offset = match (height - 1 - i).checked_mul(row_size){
-                Some(bottom_offset) => bottom_offset,
-                None => {
-                    log::error!("Bottom offset out of bounds");
+            let offsets = geometry
+                .row_offset(i)
+                .and_then(|top| Ok((top, geometry.row_offset(height - 1 - i)?)));
+            let (top_offset, bottom_offset) = match offsets {
+                Ok(offsets) => offsets,
+                Err(e) => {
+                    log::error!("Row offset out of bounds: {}", e);
                     return;
                 }
             };
             unsafe {
                 let top_ptr = rgba_data.add(top_offset);
                 let bottom_ptr = rgba_data.add(bottom_offset);
-                ptr::swap_nonoverlapping(top_ptr, bottom_ptr, row_size);
+                ptr::swap_nonoverlapping(top_ptr, bottom_ptr, geometry.row_len());
             }
         }
     }
@@ -173,29 +178,23 @@ pub unsafe extern "C" fn process_image(
         && horizontal
     {
         log::info!("Flipped horizontal");
-        let row_size = match width.checked_mul(BYTE_PER_PIXEL)
-        {
-            Some(row_size) => row_size,
-            None => {
-                log::error!("Row size out of bounds");
-                return;
-            }
-        };
-
         for y in 0..height {
-            let row_start = y * row_size;
             for x in 0..width / 2 {
-                let left_offset = row_start + match x.checked_mul(BYTE_PER_PIXEL){
-                    Some(left_offset) => left_offset,
-                    None => {
-                        log::error!("Left offset out of bounds");
+                // x < width / 2, поэтому width >= 2 и правый столбец строго правее левого
+                let right_x = match width.checked_sub(1 + x) {
+                    Some(right_x) if right_x > x => right_x,
+                    _ => {
+                        log::error!("Right column out of bounds");
                         return;
                     }
                 };
-                let right_offset = row_start + match (width - 1 - x).checked_mul(BYTE_PER_PIXEL){
-                    Some(right_offset) => right_offset,
-                    None => {
-                        log::error!("Right offset out of bounds");
+                let offsets = geometry
+                    .pixel_index(x, y)
+                    .and_then(|left| Ok((left, geometry.pixel_index(right_x, y)?)));
+                let (left_offset, right_offset) = match offsets {
+                    Ok(offsets) => offsets,
+                    Err(e) => {
+                        log::error!("Pixel offset out of bounds: {}", e);
                         return;
                     }
                 };
@@ -232,4 +231,98 @@ mod tests {
         unsafe { process_image(2, 2, buf.as_mut_ptr(), params_cstring.as_ptr()) };
         assert_eq!(buf, vec![4, 5, 6, 7, 0, 1, 2, 3, 12, 13, 14, 15, 8, 9, 10, 11]);
     }
+
+    #[test]
+    fn test_mirror_ignored_field() {
+        let mut buf = (0..16).collect::<Vec<u8>>();
+        let json = r#"{"vertical_flip": true, "radius": 5}"#;
+        assert_eq!(warn_ignored_fields(json, KNOWN_FIELDS), vec!["radius".to_string()]);
+        let params_cstring = CString::new(json).unwrap();
+        unsafe { process_image(2, 2, buf.as_mut_ptr(), params_cstring.as_ptr()) };
+        assert_eq!(buf, vec![8, 9, 10, 11, 12, 13, 14, 15, 0, 1, 2, 3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn test_mirror_single_row_horizontal() {
+        let mut buf = (0..16).collect::<Vec<_>>();
+        let json = r#"{"horizontal_flip": true}"#;
+        let params_cstring = CString::new(json).unwrap();
+        unsafe { process_image(4, 1, buf.as_mut_ptr(), params_cstring.as_ptr()) };
+        assert_eq!(buf, vec![12, 13, 14, 15, 8, 9, 10, 11, 4, 5, 6, 7, 0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_mirror_single_row_vertical_unchanged() {
+        let mut buf = (0..16).collect::<Vec<_>>();
+        let json = r#"{"vertical_flip": true}"#;
+        let params_cstring = CString::new(json).unwrap();
+        unsafe { process_image(4, 1, buf.as_mut_ptr(), params_cstring.as_ptr()) };
+        assert_eq!(buf, (0..16).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn test_mirror_single_column_vertical() {
+        let mut buf = (0..16).collect::<Vec<_>>();
+        let json = r#"{"vertical_flip": true}"#;
+        let params_cstring = CString::new(json).unwrap();
+        unsafe { process_image(1, 4, buf.as_mut_ptr(), params_cstring.as_ptr()) };
+        assert_eq!(buf, vec![12, 13, 14, 15, 8, 9, 10, 11, 4, 5, 6, 7, 0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_mirror_single_column_horizontal_unchanged() {
+        let mut buf = (0..16).collect::<Vec<_>>();
+        let json = r#"{"horizontal_flip": true}"#;
+        let params_cstring = CString::new(json).unwrap();
+        unsafe { process_image(1, 4, buf.as_mut_ptr(), params_cstring.as_ptr()) };
+        assert_eq!(buf, (0..16).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn test_mirror_horizontal_widths_1_2_3() {
+        let json = r#"{"horizontal_flip": true}"#;
+        let params_cstring = CString::new(json).unwrap();
+        for (width, expected) in [
+            (1, vec![0, 1, 2, 3]),
+            (2, vec![4, 5, 6, 7, 0, 1, 2, 3]),
+            // Центральный столбец остается на месте
+            (3, vec![8, 9, 10, 11, 4, 5, 6, 7, 0, 1, 2, 3]),
+        ] {
+            let mut buf = (0..(width * BYTE_PER_PIXEL) as u8).collect::<Vec<_>>();
+            unsafe { process_image(width as c_uint, 1, buf.as_mut_ptr(), params_cstring.as_ptr()) };
+            assert_eq!(buf, expected, "width {}", width);
+        }
+    }
+
+    #[test]
+    fn test_mirror_horizontal_odd_width_twice_is_identity() {
+        let json = r#"{"horizontal_flip": true}"#;
+        let params_cstring = CString::new(json).unwrap();
+        let original = (0..(3 * 2 * BYTE_PER_PIXEL) as u8).collect::<Vec<_>>();
+        let mut buf = original.clone();
+        unsafe { process_image(3, 2, buf.as_mut_ptr(), params_cstring.as_ptr()) };
+        assert_eq!(&buf[4..8], &original[4..8]);
+        assert_eq!(&buf[16..20], &original[16..20]);
+        unsafe { process_image(3, 2, buf.as_mut_ptr(), params_cstring.as_ptr()) };
+        assert_eq!(buf, original);
+    }
+
+    #[test]
+    fn test_mirror_concurrent_calls() {
+        let levels = ["error", "trace"];
+        std::thread::scope(|scope| {
+            for level in levels {
+                scope.spawn(move || {
+                    let json = format!(r#"{{"vertical_flip": true, "log_level": "{}"}}"#, level);
+                    let params_cstring = CString::new(json).unwrap();
+                    for _ in 0..50 {
+                        let mut buf = (0..16).collect::<Vec<u8>>();
+                        unsafe { process_image(2, 2, buf.as_mut_ptr(), params_cstring.as_ptr()) };
+                        assert_eq!(buf, vec![8, 9, 10, 11, 12, 13, 14, 15, 0, 1, 2, 3, 4, 5, 6, 7]);
+                        assert_eq!(plugins_support::logger::call_level(), None);
+                    }
+                });
+            }
+        });
+    }
 }
@@ -0,0 +1,101 @@
+//! Модуль для проверки ABI плагина
+//!
+//! Предоставляет каноническую сигнатуру `process_image` и функцию `abi_probe`,
+//! которую хост вызывает при загрузке плагина в отладочной сборке
+
+use std::ffi::{CStr, c_char, c_uint};
+
+/// Версия ABI плагинов. Увеличивается при любом изменении сигнатуры экспортируемых функций
+pub const ABI_VERSION: c_uint = 1;
+
+/// Ширина тестового изображения пробы
+pub const PROBE_WIDTH: c_uint = 3;
+/// Высота тестового изображения пробы
+pub const PROBE_HEIGHT: c_uint = 2;
+/// Строка параметров пробы
+pub const PROBE_PARAMS: &CStr = c"abi_probe";
+
+/// Сигнатура функции обработки изображения плагина
+pub type ProcessImageFn =
+    unsafe extern "C" fn(width: c_uint, height: c_uint, rgba_data: *mut u8, params: *const c_char);
+
+/// Сигнатура функции пробы ABI: те же параметры, что у `process_image`, и версия ABI в результате
+pub type AbiProbeFn = unsafe extern "C" fn(
+    width: c_uint,
+    height: c_uint,
+    rgba_data: *mut u8,
+    params: *const c_char,
+) -> c_uint;
+
+///
+/// Ожидаемое содержимое буфера после успешной пробы: байт `i` равен `!i`
+///
+/// # Параметры
+///
+/// * `len` - длина буфера
+///
+pub fn probe_pattern(len: usize) -> Vec<u8> {
+    (0..len).map(|index| !(index as u8)).collect()
+}
+
+///
+/// Ответ плагина на пробу ABI
+///
+/// Если параметры совпадают с [`PROBE_WIDTH`], [`PROBE_HEIGHT`] и [`PROBE_PARAMS`],
+/// буфер заполняется [`probe_pattern`] и возвращается [`ABI_VERSION`], иначе буфер
+/// не изменяется и возвращается `0`
+///
+/// # Safety
+/// `rgba_data` должен указывать на буфер `width * height * 4` байт,
+/// `params` - на нуль терминированную строку
+///
+pub unsafe fn probe_response(
+    width: c_uint,
+    height: c_uint,
+    rgba_data: *mut u8,
+    params: *const c_char,
+) -> c_uint {
+    if width != PROBE_WIDTH || height != PROBE_HEIGHT || rgba_data.is_null() || params.is_null() {
+        return 0;
+    }
+    if unsafe { CStr::from_ptr(params) } != PROBE_PARAMS {
+        return 0;
+    }
+    let len = (width * height * 4) as usize;
+    let buf = unsafe { std::slice::from_raw_parts_mut(rgba_data, len) };
+    buf.copy_from_slice(&probe_pattern(len));
+    ABI_VERSION
+}
+
+///
+/// Экспорт из плагина функции `abi_probe`, отвечающей [`probe_response`]
+///
+/// Также на этапе компиляции проверяет, что `process_image` плагина имеет сигнатуру
+/// [`ProcessImageFn`]. Макрос вызывается в корне крейта плагина рядом с `process_image`
+///
+/// # Пример
+///
+///```ignore
+/// plugins_support::export_abi_probe!();
+///```
+#[macro_export]
+macro_rules! export_abi_probe {
+    () => {
+        const _: $crate::abi::ProcessImageFn = process_image;
+
+        /// Проба ABI плагина, вызывается хостом при загрузке в отладочной сборке
+        ///
+        /// # Safety
+        /// `rgba_data` должен указывать на буфер `width * height * 4` байт,
+        /// `params` - на нуль терминированную строку
+        #[unsafe(no_mangle)]
+        pub unsafe extern "C" fn abi_probe(
+            width: std::ffi::c_uint,
+            height: std::ffi::c_uint,
+            rgba_data: *mut u8,
+            params: *const std::ffi::c_char,
+        ) -> std::ffi::c_uint {
+            unsafe { $crate::abi::probe_response(width, height, rgba_data, params) }
+        }
+    };
+}
@@ -0,0 +1,23 @@
+//! Утилита проверки соответствия плагина контракту хоста
+//!
+//! Запуск: `plugin_conformance <путь к библиотеке> [JSON параметры]`
+
+use plugins_support::testkit::check_plugin;
+use std::path::Path;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let Some(library) = args.next() else {
+        eprintln!("Usage: plugin_conformance <library> [params JSON]");
+        return ExitCode::FAILURE;
+    };
+    let params = args.next().unwrap_or_else(|| "{}".to_string());
+    let report = check_plugin(Path::new(&library), &params);
+    println!("{}", report);
+    if report.passed() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
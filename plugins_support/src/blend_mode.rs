@@ -0,0 +1,55 @@
+//! Модуль для режимов наложения
+//!
+//! Предоставляет функциональность смешивания каналов в режимах наложения
+//! (normal, multiply, screen, overlay, add, difference)
+
+use serde::Deserialize;
+
+/// Режим наложения
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BlendMode {
+    /// Замена цвета
+    #[default]
+    Normal,
+    /// Умножение
+    Multiply,
+    /// Экран
+    Screen,
+    /// Перекрытие
+    Overlay,
+    /// Сложение с ограничением
+    Add,
+    /// Модуль разности
+    Difference,
+}
+
+/// Смешивание одного канала
+///
+/// # Аргументы
+/// * `mode` – режим наложения
+/// * `base` – значение канала исходного изображения
+/// * `top`  – значение канала накладываемого изображения
+///
+/// # Возращает
+/// Результат смешивания, ограниченный диапазоном 0..=255
+///
+pub fn blend_channel(mode: BlendMode, base: u8, top: u8) -> u8 {
+    let a = base as f64 / 255.0;
+    let b = top as f64 / 255.0;
+    let result = match mode {
+        BlendMode::Normal => b,
+        BlendMode::Multiply => a * b,
+        BlendMode::Screen => 1.0 - (1.0 - a) * (1.0 - b),
+        BlendMode::Overlay => {
+            if a < 0.5 {
+                2.0 * a * b
+            } else {
+                1.0 - 2.0 * (1.0 - a) * (1.0 - b)
+            }
+        }
+        BlendMode::Add => a + b,
+        BlendMode::Difference => (a - b).abs(),
+    };
+    (result.clamp(0.0, 1.0) * 255.0).round() as u8
+}
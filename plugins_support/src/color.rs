@@ -0,0 +1,114 @@
+//! Модуль для преобразования цветовых пространств
+//!
+//! Предоставляет функциональность перевода пикселя RGB в HSV и HSL и обратно.
+//! Тон задается в градусах `0..360`, насыщенность, значение и светлота в диапазоне `0..=1`.
+//! Также передаточные функции sRGB для перехода к линейной яркости и обратно
+
+/// Цвет в пространстве HSV
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Hsv {
+    /// Тон, градусы `0..360`
+    pub h: f32,
+    /// Насыщенность `0..=1`
+    pub s: f32,
+    /// Значение `0..=1`
+    pub v: f32,
+}
+
+/// Цвет в пространстве HSL
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Hsl {
+    /// Тон, градусы `0..360`
+    pub h: f32,
+    /// Насыщенность `0..=1`
+    pub s: f32,
+    /// Светлота `0..=1`
+    pub l: f32,
+}
+
+/// Нормализованные каналы, максимум, минимум и тон
+fn hue_components(rgb: [u8; 3]) -> (f32, f32, f32) {
+    let [r, g, b] = rgb.map(|c| c as f32 / 255.0);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+    // Ахроматический цвет: тон не определен, принимается равным 0
+    let hue = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    (max, min, hue)
+}
+
+/// Сборка RGB по тону, хроме и смещению
+fn from_hue(hue: f32, chroma: f32, offset: f32) -> [u8; 3] {
+    let sector = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (sector.rem_euclid(2.0) - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    [r, g, b].map(|c| ((c + offset).clamp(0.0, 1.0) * 255.0).round() as u8)
+}
+
+/// Перевод RGB в HSV
+pub fn rgb_to_hsv(rgb: [u8; 3]) -> Hsv {
+    let (max, min, h) = hue_components(rgb);
+    let s = if max == 0.0 { 0.0 } else { (max - min) / max };
+    Hsv { h, s, v: max }
+}
+
+/// Перевод HSV в RGB
+pub fn hsv_to_rgb(hsv: Hsv) -> [u8; 3] {
+    let chroma = hsv.v * hsv.s;
+    from_hue(hsv.h, chroma, hsv.v - chroma)
+}
+
+/// Перевод RGB в HSL
+pub fn rgb_to_hsl(rgb: [u8; 3]) -> Hsl {
+    let (max, min, h) = hue_components(rgb);
+    let l = (max + min) / 2.0;
+    let delta = max - min;
+    let s = if delta == 0.0 {
+        0.0
+    } else {
+        delta / (1.0 - (2.0 * l - 1.0).abs())
+    };
+    Hsl { h, s, l }
+}
+
+/// Перевод HSL в RGB
+pub fn hsl_to_rgb(hsl: Hsl) -> [u8; 3] {
+    let chroma = (1.0 - (2.0 * hsl.l - 1.0).abs()) * hsl.s;
+    from_hue(hsl.h, chroma, hsl.l - chroma / 2.0)
+}
+
+/// Перевод канала sRGB в линейную яркость `0..=1`
+pub fn srgb_to_linear(value: u8) -> f64 {
+    let c = value as f64 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Перевод линейной яркости `0..=1` в канал sRGB, значения вне диапазона ограничиваются
+pub fn linear_to_srgb(value: f64) -> u8 {
+    let l = value.clamp(0.0, 1.0);
+    let c = if l <= 0.0031308 {
+        l * 12.92
+    } else {
+        1.055 * l.powf(1.0 / 2.4) - 0.055
+    };
+    (c * 255.0).round() as u8
+}
@@ -0,0 +1,92 @@
+//! Модуль для описания исходного типа цвета изображения
+//!
+//! Предоставляет функциональность передачи плагину исходного типа цвета изображения
+//! (до преобразования хостом в RGBA8) через зарезервированное поле конфигурации
+
+use crate::error::Error;
+use serde::Deserialize;
+
+/// Зарезервированное поле конфигурации, которое хост заполняет кодом исходного типа цвета
+pub const SOURCE_COLOR_TYPE_FIELD: &str = "source_color_type";
+
+///
+/// Исходный тип цвета изображения
+///
+/// В конфигурации передается целочисленным кодом (значение дискриминанта).
+/// Плагин получает буфер в RGBA8 независимо от исходного типа
+///
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(try_from = "u32")]
+#[repr(u32)]
+pub enum SourceColorType {
+    /// Неизвестный тип
+    #[default]
+    Unknown = 0,
+    /// Оттенки серого, 8 бит
+    L8 = 1,
+    /// Оттенки серого с альфа-каналом, 8 бит
+    La8 = 2,
+    /// RGB, 8 бит
+    Rgb8 = 3,
+    /// RGBA, 8 бит
+    Rgba8 = 4,
+    /// Оттенки серого, 16 бит
+    L16 = 5,
+    /// Оттенки серого с альфа-каналом, 16 бит
+    La16 = 6,
+    /// RGB, 16 бит
+    Rgb16 = 7,
+    /// RGBA, 16 бит
+    Rgba16 = 8,
+    /// RGB, 32 бита с плавающей точкой
+    Rgb32F = 9,
+    /// RGBA, 32 бита с плавающей точкой
+    Rgba32F = 10,
+}
+
+impl SourceColorType {
+    /// Код типа цвета
+    pub fn code(self) -> u32 {
+        self as u32
+    }
+
+    /// Исходное изображение в оттенках серого
+    pub fn is_grayscale(self) -> bool {
+        matches!(
+            self,
+            SourceColorType::L8 | SourceColorType::La8 | SourceColorType::L16 | SourceColorType::La16
+        )
+    }
+
+    /// Исходное изображение содержит альфа-канал
+    pub fn has_alpha(self) -> bool {
+        matches!(
+            self,
+            SourceColorType::La8
+                | SourceColorType::Rgba8
+                | SourceColorType::La16
+                | SourceColorType::Rgba16
+                | SourceColorType::Rgba32F
+        )
+    }
+}
+
+impl TryFrom<u32> for SourceColorType {
+    type Error = Error;
+    fn try_from(value: u32) -> Result<Self, Error> {
+        Ok(match value {
+            0 => SourceColorType::Unknown,
+            1 => SourceColorType::L8,
+            2 => SourceColorType::La8,
+            3 => SourceColorType::Rgb8,
+            4 => SourceColorType::Rgba8,
+            5 => SourceColorType::L16,
+            6 => SourceColorType::La16,
+            7 => SourceColorType::Rgb16,
+            8 => SourceColorType::Rgba16,
+            9 => SourceColorType::Rgb32F,
+            10 => SourceColorType::Rgba32F,
+            _ => return Err(Error::ErrorValue(format!("Unknown color type code {}", value))),
+        })
+    }
+}
@@ -2,8 +2,11 @@
 //!
 //! Предоставляет функциональность по парсигу парметров модуля JSON
 
+use crate::color_type::SOURCE_COLOR_TYPE_FIELD;
 use crate::error::Error;
 use serde;
+use std::fmt::Display;
+use std::ops::RangeInclusive;
 
 ///
 /// Дженерик структура для конфигурационных данных JSON
@@ -45,9 +48,118 @@ where
     T: serde::de::DeserializeOwned,
 {
     type Error = Error;
+    /// Разбор JSON конфигурации, `EmptyConfig` для пустой строки или строки из пробелов
     fn try_from(value: &str) -> Result<Self, Error> {
+        if value.trim().is_empty() {
+            return Err(Error::EmptyConfig);
+        }
         Ok(Self {
             config: serde_json::from_str(value)?,
         })
     }
 }
+
+impl<T> ConfigReader<T>
+where
+    T: serde::de::DeserializeOwned + Default,
+{
+    ///
+    /// Разбор JSON конфигурации, пустая строка - конфигурация по умолчанию
+    ///
+    /// Для плагинов, у которых все поля конфигурации необязательны
+    ///
+    /// # Параметры
+    ///
+    /// * `value` - строка конфигурации JSON
+    ///
+    /// # Возращает
+    /// `T::default()` для пустой строки или строки из пробелов, иначе результат [`TryFrom`]
+    ///
+    pub fn try_from_or_default(value: &str) -> Result<Self, Error> {
+        if value.trim().is_empty() {
+            return Ok(Self { config: T::default() });
+        }
+        Self::try_from(value)
+    }
+}
+
+/// Поля конфигурации, общие для всех плагинов (уровень логирования и поля, заполняемые хостом)
+pub const COMMON_FIELDS: &[&str] = &["log_level", "log_tz", SOURCE_COLOR_TYPE_FIELD];
+
+///
+/// Поля конфигурации, которые не распознаются плагином
+///
+/// # Параметры
+///
+/// * `value` - строка конфигурации JSON
+/// * `known` - поля, которые использует плагин (кроме `COMMON_FIELDS`)
+///
+/// # Возращает
+/// Имена полей верхнего уровня, которые будут проигнорированы. Если конфигурация
+/// не является JSON объектом, возвращается пустой список
+///
+pub fn ignored_fields(value: &str, known: &[&str]) -> Vec<String> {
+    match serde_json::from_str::<serde_json::Value>(value) {
+        Ok(serde_json::Value::Object(object)) => object
+            .keys()
+            .filter(|key| !known.contains(&key.as_str()) && !COMMON_FIELDS.contains(&key.as_str()))
+            .cloned()
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+///
+/// Запись в лог предупреждения для каждого поля конфигурации, которое не распознается плагином
+///
+/// # Параметры
+///
+/// * `value` - строка конфигурации JSON
+/// * `known` - поля, которые использует плагин (кроме `COMMON_FIELDS`)
+///
+/// # Возращает
+/// Имена проигнорированных полей
+///
+pub fn warn_ignored_fields(value: &str, known: &[&str]) -> Vec<String> {
+    let fields = ignored_fields(value, known);
+    for field in &fields {
+        log::warn!("Config field `{}` is ignored by the plugin", field);
+    }
+    fields
+}
+
+/// Имя поля конфигурации, включающего ограничение значений вместо отказа
+pub const CLAMP_CONFIG_FIELD: &str = "clamp_config";
+
+///
+/// Проверка попадания значения конфигурации в допустимый диапазон
+///
+/// # Параметры
+///
+/// * `name` - имя поля для сообщений
+/// * `value` - значение из конфигурации
+/// * `range` - допустимый диапазон
+/// * `clamp` - режим `clamp_config`: значение вне диапазона заменяется ближайшей границей
+///   с предупреждением в лог
+///
+/// # Возращает
+/// Значение из диапазона, `ErrorValue` для значения вне диапазона без `clamp`
+///
+pub fn check_range<T>(name: &str, value: T, range: RangeInclusive<T>, clamp: bool) -> Result<T, Error>
+where
+    T: PartialOrd + Copy + Display,
+{
+    if range.contains(&value) {
+        return Ok(value);
+    }
+    let (start, end) = range.into_inner();
+    if !clamp {
+        return Err(Error::ErrorValue(format!(
+            "{} {} is out of range {}..={}",
+            name, value, start, end
+        )));
+    }
+    let nearest = if value < start { start } else { end };
+    log::warn!("{} {} is out of range {}..={}, clamped to {}", name, value, start, end, nearest);
+    Ok(nearest)
+}
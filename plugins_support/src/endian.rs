@@ -0,0 +1,80 @@
+//! Модуль для работы с порядком байт 16-битных буферов
+//!
+//! Предоставляет функциональность приведения `u16` буфера к нативному порядку байт и обратно
+
+use crate::error::Error;
+use serde::Deserialize;
+use std::ffi::c_uint;
+
+///
+/// Порядок байт 16-битного буфера, передаваемого через FFI
+///
+/// # Варианты
+///
+/// * `Native` - нативный порядок байт платформы (код `0`)
+/// * `Little` - little-endian (код `1`)
+/// * `Big` - big-endian (код `2`)
+///
+/// В конфигурации JSON задается строкой: `"native"`, `"little"`, `"big"`
+///
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Endianness {
+    /// Нативный порядок байт
+    #[default]
+    Native,
+    /// Little-endian
+    Little,
+    /// Big-endian
+    Big,
+}
+
+impl Endianness {
+    /// Совпадает ли порядок байт с нативным порядком платформы
+    pub fn is_native(self) -> bool {
+        match self {
+            Endianness::Native => true,
+            Endianness::Little => cfg!(target_endian = "little"),
+            Endianness::Big => cfg!(target_endian = "big"),
+        }
+    }
+}
+
+impl TryFrom<c_uint> for Endianness {
+    type Error = Error;
+    fn try_from(value: c_uint) -> Result<Self, Error> {
+        match value {
+            0 => Ok(Endianness::Native),
+            1 => Ok(Endianness::Little),
+            2 => Ok(Endianness::Big),
+            _ => Err(Error::ErrorValue(format!("Unknown endianness code {}", value))),
+        }
+    }
+}
+
+///
+/// Приведение 16-битного буфера к нативному порядку байт
+///
+/// # Параметры
+///
+/// * `buf` - буфер значений каналов
+/// * `endianness` - порядок байт, в котором буфер передан
+///
+pub fn to_native(buf: &mut [u16], endianness: Endianness) {
+    if !endianness.is_native() {
+        buf.iter_mut().for_each(|value| *value = value.swap_bytes());
+    }
+}
+
+///
+/// Приведение 16-битного буфера из нативного порядка байт к заданному
+///
+/// # Параметры
+///
+/// * `buf` - буфер значений каналов в нативном порядке
+/// * `endianness` - порядок байт, который ожидает вызывающая сторона
+///
+pub fn from_native(buf: &mut [u16], endianness: Endianness) {
+    // Перестановка байт симметрична
+    to_native(buf, endianness);
+}
@@ -22,6 +22,10 @@ use thiserror::Error;
 ///     - Ошибка десерилизации JSON параметров
 ///     - Error message: `"Params are not valid JSON {0}"`.
 ///
+/// * `EmptyConfig`
+///     - Пустая строка параметров (или только пробельные символы)
+///     - Error message: `"Params are empty"`.
+///
 ///
 ///
 #[derive(Error, Debug)]
@@ -35,6 +39,9 @@ pub enum Error {
     /// Ошибка парсинга JSON строки параметров
     #[error("Params are not valid JSON {0}")]
     ParamsAreNotValidJSON(#[from] serde_json::Error),
+    /// Пустая строка параметров
+    #[error("Params are empty")]
+    EmptyConfig,
     /// Ошибка создания файла лога
     #[error("File create error: {0}")]
     FileCreateError(#[from] std::io::Error),
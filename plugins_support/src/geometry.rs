@@ -0,0 +1,137 @@
+//! Модуль для проверки размеров буфера изображения
+//!
+//! Предоставляет [`Geometry`]: ширина и высота из `c_uint`, количество байт на пиксель и шаг
+//! строки проверяются один раз, после чего длина буфера и смещения вычисляются
+//! с проверкой переполнения
+
+use crate::error::Error;
+use crate::image_size::BYTES_PER_PIXEL;
+use std::ffi::c_uint;
+
+///
+/// Проверенные размеры буфера изображения
+///
+/// Строки расположены с шагом `stride` байт, шаг не меньше длины строки
+/// `width * bytes_per_pixel`; последняя строка может не иметь выравнивания
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Geometry {
+    width: usize,
+    height: usize,
+    bytes_per_pixel: usize,
+    stride: usize,
+    total_len: usize,
+}
+
+impl Geometry {
+    ///
+    /// Проверка размеров изображения
+    ///
+    /// # Параметры
+    ///
+    /// * `width` - ширина изображения в пикселях
+    /// * `height` - высота изображения в пикселях
+    /// * `bpp` - количество байт на пиксель
+    /// * `stride` - шаг строки в байтах, `None` - строки без выравнивания
+    ///
+    /// # Возращает
+    /// `ErrorValue` для нулевой ширины, высоты, `bpp` или шага меньше длины строки,
+    /// `ConvertTypeError`, если размер не помещается в `usize`, `OverflowError` при переполнении
+    ///
+    pub fn new(width: c_uint, height: c_uint, bpp: usize, stride: Option<usize>) -> Result<Self, Error> {
+        if width == 0 {
+            return Err(Error::ErrorValue("Width cannot be 0".to_string()));
+        }
+        if height == 0 {
+            return Err(Error::ErrorValue("Height cannot be 0".to_string()));
+        }
+        if bpp == 0 {
+            return Err(Error::ErrorValue("Bytes per pixel cannot be 0".to_string()));
+        }
+        let width: usize = width.try_into()?;
+        let height: usize = height.try_into()?;
+        let row_len = width.checked_mul(bpp).ok_or(Error::OverflowError)?;
+        let stride = stride.unwrap_or(row_len);
+        if stride < row_len {
+            return Err(Error::ErrorValue(format!(
+                "Stride {} is less than row length {}",
+                stride, row_len
+            )));
+        }
+        let total_len = stride
+            .checked_mul(height - 1)
+            .and_then(|len| len.checked_add(row_len))
+            .ok_or(Error::OverflowError)?;
+        Ok(Self {
+            width,
+            height,
+            bytes_per_pixel: bpp,
+            stride,
+            total_len,
+        })
+    }
+
+    /// Размеры RGBA буфера без выравнивания строк
+    pub fn rgba(width: c_uint, height: c_uint) -> Result<Self, Error> {
+        Self::new(width, height, BYTES_PER_PIXEL, None)
+    }
+
+    /// Ширина в пикселях
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Высота в пикселях
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Количество байт на пиксель
+    pub fn bytes_per_pixel(&self) -> usize {
+        self.bytes_per_pixel
+    }
+
+    /// Шаг строки в байтах
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// Длина строки пикселей в байтах (без выравнивания)
+    pub fn row_len(&self) -> usize {
+        self.width * self.bytes_per_pixel
+    }
+
+    /// Длина буфера в байтах
+    pub fn total_len(&self) -> usize {
+        self.total_len
+    }
+
+    ///
+    /// Смещение начала строки в байтах
+    ///
+    /// # Возращает
+    /// `ErrorValue`, если строка `y` за пределами изображения
+    ///
+    pub fn row_offset(&self, y: usize) -> Result<usize, Error> {
+        if y >= self.height {
+            return Err(Error::ErrorValue(format!("Row {} is outside height {}", y, self.height)));
+        }
+        y.checked_mul(self.stride).ok_or(Error::OverflowError)
+    }
+
+    ///
+    /// Смещение первого байта пикселя
+    ///
+    /// # Возращает
+    /// `ErrorValue`, если пиксель `(x, y)` за пределами изображения
+    ///
+    pub fn pixel_index(&self, x: usize, y: usize) -> Result<usize, Error> {
+        if x >= self.width {
+            return Err(Error::ErrorValue(format!("Column {} is outside width {}", x, self.width)));
+        }
+        let row_offset = self.row_offset(y)?;
+        x.checked_mul(self.bytes_per_pixel)
+            .and_then(|offset| offset.checked_add(row_offset))
+            .ok_or(Error::OverflowError)
+    }
+}
@@ -0,0 +1,31 @@
+//! Модуль для расчета размера RGBA буфера
+//!
+//! Предоставляет функциональность расчета размера буфера изображения с проверкой переполнения,
+//! общую для хоста и плагинов
+
+use crate::error::Error;
+use std::ffi::c_uint;
+
+/// Количество байт на пиксель RGBA
+pub const BYTES_PER_PIXEL: usize = 4;
+
+///
+/// Размер RGBA буфера в байтах
+///
+/// # Параметры
+///
+/// * `width` - ширина изображения в пикселях
+/// * `height` - высота изображения в пикселях
+///
+/// # Возращает
+/// `width * height * 4`, `ConvertTypeError`, если размер не помещается в `usize`,
+/// или `OverflowError` при переполнении
+///
+pub fn rgba_len(width: c_uint, height: c_uint) -> Result<usize, Error> {
+    let width: usize = width.try_into()?;
+    let height: usize = height.try_into()?;
+    width
+        .checked_mul(height)
+        .and_then(|len| len.checked_mul(BYTES_PER_PIXEL))
+        .ok_or(Error::OverflowError)
+}
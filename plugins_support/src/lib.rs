@@ -3,16 +3,59 @@
 //! Предоставляет общую функциональность: логирование, парсинг конфигурации, ошибки
 
 #![warn(missing_docs)]
+pub mod abi;
+pub mod blend_mode;
+pub mod color;
+pub mod color_type;
 pub mod config_parse;
+pub mod endian;
 pub mod error;
+pub mod geometry;
+pub mod image_size;
 pub mod logger;
+pub mod outcome;
+pub mod pixels;
+pub mod progress;
+pub mod region;
+pub mod rng;
+pub mod sampler;
+#[cfg(feature = "testkit")]
+pub mod testkit;
+pub mod tile;
+pub mod transform;
 
 
 #[cfg(test)]
 mod tests {
     use serde::Deserialize;
-    use crate::config_parse::ConfigReader;
+    use crate::abi::{ABI_VERSION, PROBE_HEIGHT, PROBE_PARAMS, PROBE_WIDTH, probe_pattern, probe_response};
+    use crate::color::{
+        Hsl, Hsv, hsl_to_rgb, hsv_to_rgb, linear_to_srgb, rgb_to_hsl, rgb_to_hsv, srgb_to_linear,
+    };
+    use crate::color_type::SourceColorType;
+    use crate::config_parse::{ConfigReader, check_range, ignored_fields, warn_ignored_fields};
+    use crate::endian::{Endianness, from_native, to_native};
     use crate::error::Error;
+    use crate::geometry::Geometry;
+    use crate::image_size::rgba_len;
+    use crate::logger::{
+        FileLogger, LogTimezone, call_level, format_timestamp, open_log_file, reset_warning_count,
+        set_call_level, setup_logger, setup_logger_once, warning_count,
+    };
+    use crate::outcome::ProcessOutcome;
+    use crate::pixels::pixels_mut;
+    use crate::progress::step_percent;
+    use crate::region::{Region, resolve_regions};
+    use crate::rng::{Rng, mix64};
+    use crate::sampler::{Interpolation, sample};
+    use crate::tile::run_tiled;
+    use crate::transform::{Brightness, FrameCtx, Invert, PixelTransform, run_transform};
+    #[cfg(feature = "testkit")]
+    use crate::testkit::check_plugin;
+    use std::ffi::c_uint;
+    #[cfg(feature = "testkit")]
+    use std::path::Path;
+    use std::sync::Mutex;
     #[test]
     fn test_parse_json()  {
         #[derive(Deserialize, Debug)]
@@ -23,4 +66,512 @@ mod tests {
         let config: Result<ConfigReader<ConfigTransform>, Error> = ConfigReader::try_from(json_str);
         assert_eq!(config.unwrap().config.step, 1);
     }
+
+    #[test]
+    fn test_endian_big_to_native() {
+        let mut buf = vec![u16::from_ne_bytes([0x12, 0x34]), u16::from_ne_bytes([0xff, 0x00])];
+        to_native(&mut buf, Endianness::Big);
+        assert_eq!(buf, vec![0x1234, 0xff00]);
+        from_native(&mut buf, Endianness::Big);
+        assert_eq!(buf, vec![u16::from_ne_bytes([0x12, 0x34]), u16::from_ne_bytes([0xff, 0x00])]);
+    }
+
+    #[test]
+    fn test_endian_from_code() {
+        assert_eq!(Endianness::try_from(0_u32).unwrap(), Endianness::Native);
+        assert_eq!(Endianness::try_from(2_u32).unwrap(), Endianness::Big);
+        assert!(Endianness::try_from(3_u32).is_err());
+    }
+
+    #[test]
+    fn test_rgba_len() {
+        assert_eq!(rgba_len(1920, 1080).unwrap(), 1920 * 1080 * 4);
+        assert!(matches!(rgba_len(c_uint::MAX, c_uint::MAX), Err(Error::OverflowError)));
+    }
+
+    /// Источник 2x2: красный канал 0, 100 / 200, 40, зеленый 10 во всех пикселях
+    const SOURCE_2X2: [u8; 16] = [0, 10, 0, 255, 100, 10, 0, 255, 200, 10, 0, 255, 40, 10, 0, 255];
+
+    #[test]
+    fn test_sample_bilinear() {
+        // Верх: 0 * 0.75 + 100 * 0.25 = 25, низ: 200 * 0.75 + 40 * 0.25 = 160
+        // Итог: 25 * 0.25 + 160 * 0.75 = 126.25
+        let pixel = sample(&SOURCE_2X2, 2, 2, 0.25, 0.75, Interpolation::Bilinear);
+        assert_eq!(pixel, [126, 10, 0, 255]);
+        assert_eq!(sample(&SOURCE_2X2, 2, 2, 0.5, 0.5, Interpolation::default()), [85, 10, 0, 255]);
+    }
+
+    #[test]
+    fn test_sample_nearest() {
+        assert_eq!(sample(&SOURCE_2X2, 2, 2, 0.25, 0.75, Interpolation::Nearest), [200, 10, 0, 255]);
+        assert_eq!(sample(&SOURCE_2X2, 2, 2, 5.0, -1.0, Interpolation::Nearest), [100, 10, 0, 255]);
+    }
+
+    #[test]
+    fn test_step_percent() {
+        assert_eq!(step_percent(1, 3), 33);
+        assert_eq!(step_percent(3, 3), 100);
+        assert_eq!(step_percent(5, 3), 100);
+        assert_eq!(step_percent(0, 0), 100);
+    }
+
+    #[cfg(feature = "testkit")]
+    #[test]
+    fn test_check_missing_library() {
+        let report = check_plugin(Path::new("/nonexistent/libmissing_plugin.so"), "{}");
+        assert!(!report.passed());
+        assert_eq!(report.checks.len(), 1);
+        assert!(report.to_string().contains("FAIL"));
+    }
+
+    #[test]
+    fn test_source_color_type_from_config() {
+        #[derive(Deserialize, Debug)]
+        struct ConfigTransform {
+            source_color_type: Option<SourceColorType>,
+        }
+        let config: ConfigReader<ConfigTransform> =
+            ConfigReader::try_from(r#"{"source_color_type": 1}"#).unwrap();
+        let color_type = config.config.source_color_type.unwrap();
+        assert_eq!(color_type, SourceColorType::L8);
+        assert!(color_type.is_grayscale());
+        let config: Result<ConfigReader<ConfigTransform>, Error> =
+            ConfigReader::try_from(r#"{"source_color_type": 42}"#);
+        assert!(config.is_err());
+    }
+
+    /// Тесты, читающие общий счетчик предупреждений, выполняются по одному
+    static WARNING_COUNT_LOCK: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn test_warning_count() {
+        let _lock = WARNING_COUNT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let file = std::env::temp_dir().join("plugins_support_warning_count.log");
+        // Глобальный логгер мог установить другой тест
+        let _ = setup_logger_once(log::LevelFilter::Trace, file.to_str().unwrap());
+        reset_warning_count();
+        log::info!("info message");
+        log::warn!("warn message");
+        log::error!("error message");
+        assert_eq!(warning_count(), 2);
+    }
+
+    #[test]
+    fn test_format_timestamp_utc() {
+        let time = chrono::DateTime::from_timestamp(1_700_000_000, 123_000_000).unwrap();
+        assert_eq!(format_timestamp(time, LogTimezone::Utc), "2023-11-14 22:13:20.123Z");
+        let local = time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S%.3f").to_string();
+        assert_eq!(format_timestamp(time, LogTimezone::Local), local);
+    }
+
+    #[test]
+    fn test_log_file_fallback() {
+        let (_, path) = open_log_file("/nonexistent_log_dir/plugins_support_fallback.log").unwrap();
+        assert_eq!(path, std::env::temp_dir().join("plugins_support_fallback.log"));
+        assert!(path.exists());
+        let requested = std::env::temp_dir().join("plugins_support_primary.log");
+        let (_, path) = open_log_file(requested.to_str().unwrap()).unwrap();
+        assert_eq!(path, requested);
+    }
+
+    #[test]
+    fn test_ignored_fields() {
+        let json = r#"{"step": 1, "radius": 2, "log_level": "Debug", "source_color_type": 4}"#;
+        assert_eq!(ignored_fields(json, &["step"]), vec!["radius".to_string()]);
+        assert!(ignored_fields(json, &["step", "radius"]).is_empty());
+        assert!(ignored_fields("[1, 2]", &[]).is_empty());
+    }
+
+    #[test]
+    fn test_ignored_field_warning_logged() {
+        let _lock = WARNING_COUNT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let file = std::env::temp_dir().join("plugins_support_ignored_field.log");
+        let _ = setup_logger(log::LevelFilter::Trace, file.to_str().unwrap());
+        let before = warning_count();
+        let fields = warn_ignored_fields(r#"{"vertical_flip": true, "radius": 3}"#, &["vertical_flip"]);
+        assert_eq!(fields, vec!["radius".to_string()]);
+        assert!(warning_count() > before);
+    }
+
+    /// Сетка цветов с шагом 17 по каждому каналу
+    fn color_grid() -> impl Iterator<Item = [u8; 3]> {
+        (0..=255).step_by(17).flat_map(|r| {
+            (0..=255)
+                .step_by(17)
+                .flat_map(move |g| (0..=255).step_by(17).map(move |b| [r, g, b]))
+        })
+    }
+
+    fn assert_within_one(result: [u8; 3], rgb: [u8; 3]) {
+        for channel in 0..3 {
+            assert!(result[channel].abs_diff(rgb[channel]) <= 1, "{:?} -> {:?}", rgb, result);
+        }
+    }
+
+    #[test]
+    fn test_hsl_round_trip() {
+        for rgb in color_grid() {
+            assert_within_one(hsl_to_rgb(rgb_to_hsl(rgb)), rgb);
+        }
+    }
+
+    #[test]
+    fn test_hsv_round_trip() {
+        for rgb in color_grid() {
+            assert_within_one(hsv_to_rgb(rgb_to_hsv(rgb)), rgb);
+        }
+    }
+
+    #[test]
+    fn test_hsl_achromatic() {
+        for value in [0, 128, 255] {
+            let hsl = rgb_to_hsl([value; 3]);
+            assert_eq!(hsl.h, 0.0);
+            assert_eq!(hsl.s, 0.0);
+            assert_eq!(hsl_to_rgb(hsl), [value; 3]);
+        }
+        assert_eq!(rgb_to_hsv([0; 3]), Hsv::default());
+        assert_eq!(hsl_to_rgb(Hsl { h: 120.0, s: 1.0, l: 0.5 }), [0, 255, 0]);
+    }
+
+    #[test]
+    fn test_resolve_regions() {
+        let full = resolve_regions(None, 4, 3).unwrap();
+        assert_eq!(full, vec![Region::full(4, 3)]);
+        let region = Region { x: 1, y: 1, width: 2, height: 2 };
+        assert_eq!(resolve_regions(Some(&[region]), 4, 3).unwrap(), vec![region]);
+        assert_eq!(region.pixels(4).collect::<Vec<_>>(), vec![5, 6, 9, 10]);
+        let outside = Region { x: 4, y: 0, width: 2, height: 1 };
+        assert!(resolve_regions(Some(&[region, outside]), 4, 3).is_err());
+        assert!(resolve_regions(Some(&[]), 4, 3).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_region_zero_area_skipped() {
+        let empty = Region { x: 1, y: 1, width: 0, height: 2 };
+        assert_eq!(empty.clamp(4, 3).unwrap(), None);
+        // Нулевая область вне изображения тоже пропускается, а не дает ошибку
+        let flat = Region { x: 10, y: 10, width: 5, height: 0 };
+        assert!(resolve_regions(Some(&[empty, flat]), 4, 3).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_region_over_wide_clamped() {
+        let wide = Region { x: 3, y: 1, width: 10, height: usize::MAX };
+        let clamped = Region { x: 3, y: 1, width: 1, height: 2 };
+        assert_eq!(resolve_regions(Some(&[wide]), 4, 3).unwrap(), vec![clamped]);
+        assert_eq!(clamped.pixels(4).collect::<Vec<_>>(), vec![7, 11]);
+    }
+
+    #[test]
+    fn test_srgb_linear_round_trip() {
+        for value in 0..=u8::MAX {
+            assert_eq!(linear_to_srgb(srgb_to_linear(value)), value);
+        }
+        assert_eq!(srgb_to_linear(0), 0.0);
+        assert_eq!(srgb_to_linear(255), 1.0);
+        // Средний серый sRGB заметно темнее половины линейной яркости
+        assert!((srgb_to_linear(128) - 0.2158).abs() < 1e-3);
+        assert_eq!(linear_to_srgb(0.5), 188);
+    }
+
+    #[test]
+    fn test_pixels_mut_gradient() {
+        let (width, height) = (5, 3);
+        let mut buf = vec![0; width * height * 4];
+        for (x, y, pixel) in pixels_mut(&mut buf, width, height, 4).unwrap() {
+            pixel.copy_from_slice(&[(x * 60) as u8, (y * 120) as u8, 0, 255]);
+        }
+        let mut expected = vec![0; width * height * 4];
+        for y in 0..height {
+            for x in 0..width {
+                let index = (y * width + x) * 4;
+                expected[index] = (x * 60) as u8;
+                expected[index + 1] = (y * 120) as u8;
+                expected[index + 3] = 255;
+            }
+        }
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn test_pixels_mut_invalid_size() {
+        let mut buf = vec![0; 12];
+        assert!(matches!(pixels_mut(&mut buf, 2, 2, 4), Err(Error::ErrorValue(_))));
+        assert!(matches!(pixels_mut(&mut buf, usize::MAX, 2, 4), Err(Error::OverflowError)));
+        assert!(pixels_mut(&mut buf, 3, 1, 4).is_ok());
+    }
+
+    #[test]
+    fn test_rng_fixed_sequence() {
+        // Эталонная последовательность SplitMix64 для зерна 1234567
+        let mut rng = Rng::new(1234567);
+        let sequence: Vec<u64> = (0..3).map(|_| rng.next_u64()).collect();
+        assert_eq!(sequence, [6457827717110365317, 3203168211198807973, 9817491932198370423]);
+        let mut rng = Rng::new(42);
+        assert_eq!(rng.next_u32(), 3184996902);
+        let mut a = Rng::new(7);
+        let mut b = Rng::new(7);
+        for _ in 0..100 {
+            let value = a.next_f32();
+            assert!((0.0..1.0).contains(&value));
+            assert_eq!(value, b.next_f32());
+        }
+        assert_ne!(mix64(1), mix64(2));
+    }
+
+    #[test]
+    fn test_rng_split_streams_do_not_overlap() {
+        let root = Rng::new(42);
+        assert_eq!(root.split(3), root.split(3));
+        let mut seen = std::collections::HashSet::new();
+        for stream in 0..8 {
+            let mut rng = root.split(stream);
+            // mix64 - биекция: совпадение выходов означает совпадение состояний потоков
+            for _ in 0..10_000 {
+                assert!(seen.insert(rng.next_u64()), "stream {} overlaps", stream);
+            }
+        }
+        assert_eq!(root, Rng::new(42));
+    }
+
+    #[test]
+    fn test_file_logger_handles() {
+        let _lock = WARNING_COUNT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let host_file = std::env::temp_dir().join("plugins_support_file_logger_host.log");
+        let plugin_file = std::env::temp_dir().join("plugins_support_file_logger_plugin.log");
+        let host = FileLogger::open(log::LevelFilter::Info, host_file.to_str().unwrap()).unwrap();
+        let plugin = FileLogger::open(log::LevelFilter::Warn, plugin_file.to_str().unwrap()).unwrap();
+        let before = warning_count();
+        host.info("host message");
+        plugin.info("filtered plugin message");
+        plugin.warn(format_args!("plugin warning {}", 42));
+        assert!(warning_count() - before >= 1);
+        let host_log = std::fs::read_to_string(host.path()).unwrap();
+        let plugin_log = std::fs::read_to_string(plugin.path()).unwrap();
+        assert!(host_log.contains("[INFO]") && host_log.contains("src/lib.rs:"));
+        assert!(host_log.contains("host message"));
+        assert!(!plugin_log.contains("filtered plugin message"));
+        assert!(plugin_log.contains("[WARN]") && plugin_log.contains("plugin warning 42"));
+        assert!(!host_log.contains("plugin warning"));
+    }
+
+    #[test]
+    fn test_config_empty() {
+        #[derive(Deserialize, Debug, Default, PartialEq)]
+        struct OptionalConfig {
+            log_level: Option<String>,
+        }
+        for text in ["", "  \n\t"] {
+            let config: Result<ConfigReader<OptionalConfig>, Error> = ConfigReader::try_from(text);
+            assert!(matches!(config, Err(Error::EmptyConfig)));
+            let config = ConfigReader::<OptionalConfig>::try_from_or_default(text).unwrap();
+            assert_eq!(config.config, OptionalConfig::default());
+        }
+        let config = ConfigReader::<OptionalConfig>::try_from_or_default(r#"{"log_level": "debug"}"#).unwrap();
+        assert_eq!(config.config.log_level.as_deref(), Some("debug"));
+        assert!(matches!(
+            ConfigReader::<OptionalConfig>::try_from_or_default("{"),
+            Err(Error::ParamsAreNotValidJSON(_))
+        ));
+    }
+
+    /// Тонирование по координате: красный канал равен столбцу пикселя
+    struct ColumnRed;
+
+    impl PixelTransform for ColumnRed {
+        fn apply(&self, px: &mut [u8; 4], x: usize, _y: usize, ctx: &FrameCtx) {
+            assert!(x < ctx.width);
+            px[0] = x as u8;
+        }
+    }
+
+    #[test]
+    fn test_run_composed_transforms() {
+        let chain: Vec<Box<dyn PixelTransform>> = vec![Box::new(Invert), Box::new(Brightness { delta: 10 })];
+        let mut buf = [0, 100, 250, 7, 255, 5, 0, 9].repeat(3);
+        run_transform(&mut buf, 2, 3, &chain, None, 1).unwrap();
+        assert_eq!(buf, [255, 165, 15, 7, 10, 255, 255, 9].repeat(3));
+        let mut buf = [0; 2 * 2 * 4];
+        assert!(run_transform(&mut buf[..12], 2, 2, &Invert, None, 1).is_err());
+    }
+
+    #[test]
+    fn test_run_transform_roi_and_threads() {
+        let (width, height) = (5, 7);
+        let roi = Region { x: 1, y: 2, width: 10, height: 4 };
+        let chain: Vec<Box<dyn PixelTransform>> = vec![Box::new(ColumnRed), Box::new(Invert)];
+        let expected = {
+            let mut buf = vec![0; width * height * 4];
+            run_transform(&mut buf, width, height, &chain, Some(roi), 1).unwrap();
+            buf
+        };
+        for (index, px) in expected.chunks_exact(4).enumerate() {
+            let (x, y) = (index % width, index / width);
+            let inside = x >= 1 && (2..6).contains(&y);
+            let red = if inside { 255 - x as u8 } else { 0 };
+            assert_eq!(px, [red, if inside { 255 } else { 0 }, if inside { 255 } else { 0 }, 0]);
+        }
+        for threads in [0, 2, 3, 16] {
+            let mut buf = vec![0; width * height * 4];
+            run_transform(&mut buf, width, height, &chain, Some(roi), threads).unwrap();
+            assert_eq!(buf, expected, "threads {}", threads);
+        }
+    }
+
+    #[test]
+    fn test_call_level_is_per_thread() {
+        assert_eq!(call_level(), None);
+        {
+            let _outer = set_call_level(log::LevelFilter::Warn);
+            {
+                let _inner = set_call_level(log::LevelFilter::Trace);
+                assert_eq!(call_level(), Some(log::LevelFilter::Trace));
+            }
+            assert_eq!(call_level(), Some(log::LevelFilter::Warn));
+            std::thread::spawn(|| assert_eq!(call_level(), None)).join().unwrap();
+        }
+        assert_eq!(call_level(), None);
+    }
+
+    /// Уровень вызова выше уровня инициализации логгера пропускает записи
+    #[test]
+    fn test_call_level_above_logger_level() {
+        let file = std::env::temp_dir().join("plugins_support_call_level.log");
+        // Глобальный логгер мог установить другой тест
+        let _ = setup_logger_once(log::LevelFilter::Debug, file.to_str().unwrap());
+        assert_eq!(log::max_level(), log::LevelFilter::Trace);
+        {
+            let _level = set_call_level(log::LevelFilter::Trace);
+            assert!(log::log_enabled!(log::Level::Trace));
+        }
+        let _level = set_call_level(log::LevelFilter::Warn);
+        assert!(!log::log_enabled!(log::Level::Info));
+        assert!(log::log_enabled!(log::Level::Warn));
+    }
+
+    /// Преобразование, запоминающее уровень логирования потоков
+    struct CallLevelProbe(Mutex<Vec<Option<log::LevelFilter>>>);
+
+    impl PixelTransform for CallLevelProbe {
+        fn apply(&self, _px: &mut [u8; 4], _x: usize, _y: usize, _ctx: &FrameCtx) {
+            self.0.lock().unwrap().push(call_level());
+        }
+    }
+
+    /// Рабочие потоки run_tiled и run_transform получают уровень вызывающего потока
+    #[test]
+    fn test_call_level_in_workers() {
+        let _level = set_call_level(log::LevelFilter::Trace);
+        let source = vec![0; 4 * 4];
+        let mut dst = source.clone();
+        let levels = Mutex::new(Vec::new());
+        run_tiled(&source, &mut dst, 1, 4, 4, 0, 4, |_, _| levels.lock().unwrap().push(call_level())).unwrap();
+        assert_eq!(levels.into_inner().unwrap(), [Some(log::LevelFilter::Trace); 4]);
+        let probe = CallLevelProbe(Mutex::new(Vec::new()));
+        run_transform(&mut dst, 1, 4, &probe, None, 4).unwrap();
+        assert_eq!(probe.0.into_inner().unwrap(), [Some(log::LevelFilter::Trace); 4]);
+    }
+
+    #[test]
+    fn test_check_range() {
+        assert_eq!(check_range("Radius", 5, 1..=10, false).unwrap(), 5);
+        assert!(matches!(check_range("Radius", 11, 1..=10, false), Err(Error::ErrorValue(_))));
+        assert_eq!(check_range("Radius", 11, 1..=10, true).unwrap(), 10);
+        assert_eq!(check_range("Step", 0, 1..=10, true).unwrap(), 1);
+        assert_eq!(check_range("Sigma", -0.5, 0.0..=1.0, true).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn test_geometry_zero_dimensions() {
+        assert!(matches!(Geometry::rgba(0, 5), Err(Error::ErrorValue(_))));
+        assert!(matches!(Geometry::rgba(5, 0), Err(Error::ErrorValue(_))));
+        assert!(matches!(Geometry::new(5, 5, 0, None), Err(Error::ErrorValue(_))));
+    }
+
+    #[test]
+    fn test_geometry_overflow() {
+        assert!(matches!(Geometry::new(c_uint::MAX, 1, usize::MAX, None), Err(Error::OverflowError)));
+        assert!(matches!(Geometry::new(1, 3, 4, Some(usize::MAX)), Err(Error::OverflowError)));
+        assert!(matches!(Geometry::rgba(c_uint::MAX, c_uint::MAX), Err(Error::OverflowError)));
+    }
+
+    #[test]
+    fn test_geometry_without_stride() {
+        let geometry = Geometry::rgba(3, 2).unwrap();
+        assert_eq!((geometry.width(), geometry.height(), geometry.stride()), (3, 2, 12));
+        assert_eq!(geometry.total_len(), rgba_len(3, 2).unwrap());
+        assert_eq!(geometry.row_offset(1).unwrap(), 12);
+        assert_eq!(geometry.pixel_index(2, 1).unwrap(), 20);
+        assert!(geometry.row_offset(2).is_err());
+        assert!(geometry.pixel_index(3, 0).is_err());
+    }
+
+    #[test]
+    fn test_geometry_with_stride() {
+        let geometry = Geometry::new(3, 2, 3, Some(12)).unwrap();
+        assert_eq!((geometry.row_len(), geometry.stride()), (9, 12));
+        assert_eq!(geometry.total_len(), 12 + 9);
+        assert_eq!(geometry.pixel_index(1, 1).unwrap(), 15);
+        assert!(matches!(Geometry::new(3, 2, 3, Some(8)), Err(Error::ErrorValue(_))));
+    }
+
+    #[test]
+    fn test_outcome_count_changed() {
+        let before = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+        let after = [1, 2, 3, 4, 0, 6, 7, 8, 0];
+        assert_eq!(ProcessOutcome::count_changed(&before, &after, 3), 2);
+        assert_eq!(ProcessOutcome::count_changed(&before, &before, 3), 0);
+        let mut outcome = ProcessOutcome::default();
+        outcome.warn("skipped".to_string());
+        assert_eq!(outcome.warnings, ["skipped"]);
+    }
+
+    #[test]
+    fn test_abi_probe_response() {
+        let mut buf = vec![0; (PROBE_WIDTH * PROBE_HEIGHT * 4) as usize];
+        let version = unsafe { probe_response(PROBE_WIDTH, PROBE_HEIGHT, buf.as_mut_ptr(), PROBE_PARAMS.as_ptr()) };
+        assert_eq!(version, ABI_VERSION);
+        assert_eq!(buf, probe_pattern(buf.len()));
+        assert_eq!(&buf[..3], [255, 254, 253]);
+        let mut swapped = vec![0; buf.len()];
+        let version = unsafe { probe_response(PROBE_HEIGHT, PROBE_WIDTH, swapped.as_mut_ptr(), PROBE_PARAMS.as_ptr()) };
+        assert_eq!(version, 0);
+        assert_eq!(swapped, vec![0; buf.len()]);
+    }
+
+    #[test]
+    fn test_run_tiled_box_sum() {
+        let (width, height) = (5, 7);
+        let source: Vec<u8> = (0..width * height).map(|index| (index * 13 % 31) as u8).collect();
+        let box_sum = |x: usize, y: usize, pixel: &dyn Fn(usize, usize) -> u8| {
+            let mut sum = 0u8;
+            for ny in y.saturating_sub(1)..(y + 2).min(height) {
+                for nx in x.saturating_sub(1)..(x + 2).min(width) {
+                    sum = sum.wrapping_add(pixel(nx, ny));
+                }
+            }
+            sum
+        };
+        let expected: Vec<u8> = (0..width * height)
+            .map(|index| box_sum(index % width, index / width, &|x, y| source[y * width + x]))
+            .collect();
+        for threads in 1..=8 {
+            let mut dst = vec![0; source.len()];
+            run_tiled(&source, &mut dst, width, height, 1, 1, threads, |tile, band| {
+                for y in tile.rows() {
+                    for x in 0..tile.width() {
+                        band[tile.band_index(x, y)] = box_sum(x, y, &|x, y| tile.pixel(x, y)[0]);
+                    }
+                }
+            })
+            .unwrap();
+            assert_eq!(dst, expected, "threads {}", threads);
+        }
+        let mut short = vec![0; 3];
+        assert!(matches!(
+            run_tiled(&source, &mut short, width, height, 1, 1, 2, |_, _| ()),
+            Err(Error::ErrorValue(_))
+        ));
+    }
 }
@@ -1,20 +1,257 @@
 //! Модуль для реализации логирования
 //!
 //! Предоставляет функциональность поддержки логирования
+//!
+//! # Многопоточность
+//!
+//! `process_image` плагина может вызываться одновременно из нескольких потоков (пакетная
+//! обработка с одной загруженной библиотекой). Поэтому логгер инициализируется один раз
+//! ([`setup_logger_once`]), а уровень из конфигурации вызова действует только в потоке
+//! вызова до его завершения ([`set_call_level`]) и может быть как ниже, так и выше уровня
+//! инициализации: глобальный `log::max_level` всегда `Trace`, записи отбрасывает сам логгер.
+//! Рабочие потоки [`crate::tile::run_tiled`] и [`crate::transform::run_transform`] получают
+//! уровень вызывающего потока. Часовой пояс меток времени ([`set_log_timezone`]) остается
+//! общим для процесса
 
 use env_logger::{Builder, Target};
-use log::LevelFilter;
+use log::{Level, LevelFilter, Metadata, Record};
+use std::cell::Cell;
+use std::fmt::Display;
 use std::fs::File;
 use std::io::Write;
+use std::panic::Location;
+use std::path::{Path, PathBuf};
+use serde::Deserialize;
+use std::marker::PhantomData;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use crate::error::Error;
 
+/// Количество записанных в лог предупреждений и ошибок
+static WARNING_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Метки времени лога в UTC
+static LOG_UTC: AtomicBool = AtomicBool::new(false);
+
+/// Логгер уже инициализирован [`setup_logger_once`]
+static LOGGER_READY: Mutex<bool> = Mutex::new(false);
+
+thread_local! {
+    /// Уровень логирования текущего вызова, см. [`set_call_level`]
+    static CALL_LEVEL: Cell<Option<LevelFilter>> = const { Cell::new(None) };
+}
+
+///
+/// Восстановление уровня логирования потока при завершении вызова
+///
+/// Возвращается [`set_call_level`], привязан к потоку, поэтому не `Send`
+///
+#[must_use = "the call level is reset when the guard is dropped"]
+pub struct CallLevelGuard {
+    previous: Option<LevelFilter>,
+    _thread: PhantomData<*const ()>,
+}
+
+impl Drop for CallLevelGuard {
+    fn drop(&mut self) {
+        CALL_LEVEL.with(|level| level.set(self.previous));
+    }
+}
+
+///
+/// Уровень логирования для текущего потока
+///
+/// Заменяет в этом потоке уровень, переданный [`setup_logger`]: записи, не прошедшие уровень,
+/// отбрасываются, записи выше уровня инициализации (например, `trace`) пишутся. Другие потоки
+/// не затрагиваются. Уровень действует, пока жив возвращенный дескриптор, затем
+/// восстанавливается предыдущий
+///
+/// # Параметры
+///
+/// * `level` - уровень логирования вызова
+///
+/// # Usage
+///
+///```ignore
+/// let _log_level = set_call_level(get_log_level("warn"));
+/// log::info!("skipped in this thread");
+///```
+pub fn set_call_level(level: LevelFilter) -> CallLevelGuard {
+    CallLevelGuard {
+        previous: CALL_LEVEL.with(|current| current.replace(Some(level))),
+        _thread: PhantomData,
+    }
+}
+
+/// Уровень логирования текущего потока, `None` если не задан [`set_call_level`]
+pub fn call_level() -> Option<LevelFilter> {
+    CALL_LEVEL.with(Cell::get)
+}
+
+///
+/// Часовой пояс меток времени лога
+///
+/// В конфигурации плагинов задается полем `log_tz`: `"local"`, `"utc"`
+///
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LogTimezone {
+    /// Локальное время
+    #[default]
+    Local,
+    /// UTC
+    Utc,
+}
+
+///
+/// Выбор часового пояса меток времени лога
+///
+/// Действует на все последующие записи, в том числе после инициализации логера
+///
+pub fn set_log_timezone(timezone: LogTimezone) {
+    LOG_UTC.store(timezone == LogTimezone::Utc, Ordering::Relaxed);
+}
+
+/// Текущий часовой пояс меток времени лога
+pub fn log_timezone() -> LogTimezone {
+    if LOG_UTC.load(Ordering::Relaxed) {
+        LogTimezone::Utc
+    } else {
+        LogTimezone::Local
+    }
+}
+
+///
+/// Форматирование метки времени лога
+///
+/// # Параметры
+///
+/// * `time` - момент времени
+/// * `timezone` - часовой пояс
+///
+/// # Возращает
+/// Строку `YYYY-MM-DD HH:MM:SS.mmm`, для UTC с суффиксом `Z`
+///
+pub fn format_timestamp(time: chrono::DateTime<chrono::Utc>, timezone: LogTimezone) -> String {
+    const FORMAT: &str = "%Y-%m-%d %H:%M:%S%.3f";
+    match timezone {
+        LogTimezone::Local => time.with_timezone(&chrono::Local).format(FORMAT).to_string(),
+        LogTimezone::Utc => time.format(FORMAT).to_string() + "Z",
+    }
+}
+
+/// Логгер, подсчитывающий записанные предупреждения и ошибки
+struct CountingLogger {
+    inner: env_logger::Logger,
+    /// Уровень потоков без [`set_call_level`]
+    level: LevelFilter,
+}
+
+impl CountingLogger {
+    /// Проходит ли уровень записи фильтр текущего потока
+    fn level_enabled(&self, level: Level) -> bool {
+        level <= call_level().unwrap_or(self.level)
+    }
+}
+
+impl log::Log for CountingLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata) && self.level_enabled(metadata.level())
+    }
+
+    fn log(&self, record: &Record) {
+        if self.inner.matches(record) && self.level_enabled(record.level()) {
+            if record.level() <= Level::Warn {
+                WARNING_COUNT.fetch_add(1, Ordering::Relaxed);
+            }
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+///
+/// Количество предупреждений и ошибок, записанных в лог с момента инициализации логера
+///
+/// Учитываются только записи, прошедшие фильтр уровня логирования
+///
+pub fn warning_count() -> usize {
+    WARNING_COUNT.load(Ordering::Relaxed)
+}
+
+/// Сброс счетчика [`warning_count`] перед проверкой в тестах
+#[cfg(test)]
+pub(crate) fn reset_warning_count() {
+    WARNING_COUNT.store(0, Ordering::Relaxed);
+}
+
+///
+/// Экспорт из плагина функции `plugin_warning_count`, возвращающей [`warning_count`]
+///
+/// Используется хостом в строгом режиме (`--strict`): если за время вызова плагина
+/// счетчик вырос, обработка завершается ошибкой
+///
+/// # Пример
+///
+///```ignore
+/// plugins_support::export_warning_count!();
+///```
+#[macro_export]
+macro_rules! export_warning_count {
+    () => {
+        /// Количество предупреждений и ошибок, записанных плагином в лог
+        #[unsafe(no_mangle)]
+        pub extern "C" fn plugin_warning_count() -> std::ffi::c_uint {
+            $crate::logger::warning_count() as std::ffi::c_uint
+        }
+    };
+}
+
+///
+/// Открытие файла лога с запасным вариантом во временной директории
+///
+/// # Параметры
+///
+/// * `file` - запрошенный путь файла лога
+///
+/// # Возращает
+/// Открытый файл и его путь. Если файл по запрошенному пути создать не удалось
+/// (например, текущая директория доступна только для чтения), файл с тем же именем
+/// создается в `std::env::temp_dir()`, о чем выводится сообщение в stderr.
+/// Если не удалось и это, возвращается исходная ошибка
+///
+pub fn open_log_file(file: &str) -> Result<(File, PathBuf), Error> {
+    let error = match File::create(file) {
+        Ok(log_file) => return Ok((log_file, PathBuf::from(file))),
+        Err(e) => e,
+    };
+    let name = Path::new(file).file_name().unwrap_or("plugin.log".as_ref());
+    let fallback = std::env::temp_dir().join(name);
+    match File::create(&fallback) {
+        Ok(log_file) => {
+            eprintln!(
+                "Cannot create log file {}: {}, logging to {}",
+                file,
+                error,
+                fallback.display()
+            );
+            Ok((log_file, fallback))
+        }
+        Err(_) => Err(error.into()),
+    }
+}
+
 ///
 /// Инициализация логера
 ///
 ///
 /// # Параметры
 ///
-/// * `level` - LevelFilter уровень логирования ("Error", "Warn", "Info", "Debug", "Trace").
+/// * `level` - LevelFilter уровень логирования ("Error", "Warn", "Info", "Debug", "Trace"),
+///   в потоке с [`set_call_level`] действует уровень вызова
 /// * `file` - Файл для записи логов.
 ///
 /// # Формат лога
@@ -31,6 +268,12 @@ use crate::error::Error;
 /// 2023-03-01 12:34:56.789 [INFO] main.rs:42 - Application started successfully
 /// ```
 ///
+/// Часовой пояс метки времени задается [`set_log_timezone`] (по умолчанию локальное время,
+/// для UTC добавляется суффикс `Z`).
+///
+/// Записанные предупреждения и ошибки подсчитываются, см. [`warning_count`].
+/// Если файл создать не удалось, лог пишется во временную директорию, см. [`open_log_file`]
+///
 /// # Usage
 ///
 ///```ignore
@@ -40,13 +283,13 @@ use crate::error::Error;
 /// log::info!("This is an informational message.");
 ///```
 pub fn setup_logger(level: LevelFilter, file: &str) -> Result<(), Error> {
-    let log_file = File::create(file)?;
-    Builder::new()
+    let (log_file, _) = open_log_file(file)?;
+    let logger = Builder::new()
         .format(|buf, record| {
             writeln!(
                 buf,
                 "{} [{}] {}:{} - {}",
-                chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+                format_timestamp(chrono::Utc::now(), log_timezone()),
                 record.level(),
                 record.file().unwrap_or("unknown"),
                 record.line().unwrap_or(0),
@@ -54,12 +297,146 @@ pub fn setup_logger(level: LevelFilter, file: &str) -> Result<(), Error> {
             )
         })
         .target(Target::Pipe(Box::new(log_file)))
-        .filter(None, level) // Уровень по умолчанию
+        .filter(None, LevelFilter::Trace) // Уровень фильтрует CountingLogger
         .write_style(env_logger::WriteStyle::Always) // Всегда использовать цвета
-        .try_init()?;
+        .build();
+    log::set_boxed_logger(Box::new(CountingLogger { inner: logger, level }))?;
+    log::set_max_level(LevelFilter::Trace);
     Ok(())
 }
 
+///
+/// Однократная инициализация логера
+///
+/// Первый вызов выполняет [`setup_logger`], последующие (в том числе одновременные из других
+/// потоков) ничего не делают и не пересоздают файл лога. Если глобальный логгер уже
+/// установлен другим кодом, возвращается `LoggerInitError`, повторные вызовы - `Ok`
+///
+/// # Параметры
+///
+/// * `level` - уровень логирования
+/// * `file` - файл для записи логов
+///
+pub fn setup_logger_once(level: LevelFilter, file: &str) -> Result<(), Error> {
+    let mut ready = LOGGER_READY.lock().unwrap_or_else(|e| e.into_inner());
+    if *ready {
+        return Ok(());
+    }
+    let result = setup_logger(level, file);
+    *ready = matches!(result, Ok(()) | Err(Error::LoggerInitError(_)));
+    result
+}
+
+///
+/// Логгер-дескриптор, пишущий в собственный файл без глобального фасада `log`
+///
+/// Каждая динамически загруженная библиотека содержит собственную копию статического
+/// состояния крейта `log`: хост и плагин инициализируют разные глобальные логгеры,
+/// а при общей копии (статическая сборка, тесты) вторая инициализация завершается ошибкой
+/// "logger already set", и сообщения второго участника теряются. Дескриптор не использует
+/// глобальное состояние, кроме счетчика [`warning_count`]: его можно создать в любой момент,
+/// несколько раз, передавать между потоками и использовать одновременно с [`setup_logger`].
+/// Формат записей совпадает с [`setup_logger`]
+///
+/// # Usage
+///
+///```ignore
+/// use log::LevelFilter;
+///
+/// let logger = FileLogger::open(LevelFilter::Info, "plugin.log")?;
+/// logger.info("Start plugin");
+///```
+pub struct FileLogger {
+    file: Mutex<File>,
+    path: PathBuf,
+    level: LevelFilter,
+}
+
+impl FileLogger {
+    ///
+    /// Открытие файла лога
+    ///
+    /// # Параметры
+    ///
+    /// * `level` - уровень логирования
+    /// * `file` - файл для записи логов, запасной вариант см. [`open_log_file`]
+    ///
+    pub fn open(level: LevelFilter, file: &str) -> Result<Self, Error> {
+        let (file, path) = open_log_file(file)?;
+        Ok(Self {
+            file: Mutex::new(file),
+            path,
+            level,
+        })
+    }
+
+    /// Путь к файлу лога (может отличаться от запрошенного, см. [`open_log_file`])
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Проходит ли запись уровня `level` фильтр логгера
+    pub fn enabled(&self, level: Level) -> bool {
+        level <= self.level
+    }
+
+    ///
+    /// Запись сообщения в лог
+    ///
+    /// Место вызова (файл и строка) берется из вызывающего кода.
+    /// Ошибки записи в файл игнорируются
+    ///
+    /// # Параметры
+    ///
+    /// * `level` - уровень записи
+    /// * `message` - сообщение
+    ///
+    #[track_caller]
+    pub fn log(&self, level: Level, message: impl Display) {
+        if !self.enabled(level) {
+            return;
+        }
+        if level <= Level::Warn {
+            WARNING_COUNT.fetch_add(1, Ordering::Relaxed);
+        }
+        let location = Location::caller();
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = writeln!(
+            file,
+            "{} [{}] {}:{} - {}",
+            format_timestamp(chrono::Utc::now(), log_timezone()),
+            level,
+            location.file(),
+            location.line(),
+            message
+        );
+    }
+
+    /// Запись уровня `Error`
+    #[track_caller]
+    pub fn error(&self, message: impl Display) {
+        self.log(Level::Error, message);
+    }
+
+    /// Запись уровня `Warn`
+    #[track_caller]
+    pub fn warn(&self, message: impl Display) {
+        self.log(Level::Warn, message);
+    }
+
+    /// Запись уровня `Info`
+    #[track_caller]
+    pub fn info(&self, message: impl Display) {
+        self.log(Level::Info, message);
+    }
+
+    /// Запись уровня `Debug`
+    #[track_caller]
+    pub fn debug(&self, message: impl Display) {
+        self.log(Level::Debug, message);
+    }
+}
+
 ///
 /// Преобразование теста уровня логирования в LogFilter
 ///
@@ -0,0 +1,42 @@
+//! Модуль для результата обработки безопасного Rust API плагинов
+//!
+//! Предоставляет [`ProcessOutcome`]: статистика обработки для встраивающих приложений,
+//! которым нужен отчет о проделанной работе, а не только `Result<(), Error>`
+
+///
+/// Результат обработки изображения
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProcessOutcome {
+    /// Количество пикселей, значение которых изменилось
+    pub pixels_changed: usize,
+    /// Предупреждения, записанные в лог во время обработки
+    pub warnings: Vec<String>,
+    /// Количество пропущенных операций (например, пикселей с переполнением индекса)
+    pub failures: usize,
+}
+
+impl ProcessOutcome {
+    ///
+    /// Подсчет изменившихся пикселей
+    ///
+    /// # Параметры
+    ///
+    /// * `before` - буфер до обработки
+    /// * `after` - буфер после обработки той же длины
+    /// * `bytes_per_pixel` - количество байт на пиксель
+    ///
+    pub fn count_changed(before: &[u8], after: &[u8], bytes_per_pixel: usize) -> usize {
+        before
+            .chunks_exact(bytes_per_pixel)
+            .zip(after.chunks_exact(bytes_per_pixel))
+            .filter(|(before, after)| before != after)
+            .count()
+    }
+
+    /// Запись предупреждения в лог и в результат
+    pub fn warn(&mut self, message: String) {
+        log::warn!("{}", message);
+        self.warnings.push(message);
+    }
+}
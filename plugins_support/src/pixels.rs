@@ -0,0 +1,49 @@
+//! Модуль для обхода пикселей буфера с координатами
+//!
+//! Предоставляет функциональность перебора пикселей `(x, y, pixel)` с однократной проверкой
+//! размера буфера и переполнения, для плагинов, которым нужны координаты пикселя
+
+use crate::error::Error;
+
+///
+/// Изменяемый обход пикселей буфера построчно
+///
+/// # Параметры
+///
+/// * `buf` - буфер изображения
+/// * `width` - ширина изображения в пикселях
+/// * `height` - высота изображения в пикселях
+/// * `bytes_per_pixel` - количество байт на пиксель
+///
+/// # Возращает
+/// Итератор `(x, y, pixel)`, где `pixel` - срез из `bytes_per_pixel` байт,
+/// `OverflowError` при переполнении размера или `ErrorValue`, если длина буфера
+/// не равна `width * height * bytes_per_pixel`
+///
+pub fn pixels_mut(
+    buf: &mut [u8],
+    width: usize,
+    height: usize,
+    bytes_per_pixel: usize,
+) -> Result<impl Iterator<Item = (usize, usize, &mut [u8])>, Error> {
+    if bytes_per_pixel == 0 {
+        return Err(Error::ErrorValue("Bytes per pixel cannot be 0".to_string()));
+    }
+    let len = width
+        .checked_mul(height)
+        .and_then(|len| len.checked_mul(bytes_per_pixel))
+        .ok_or(Error::OverflowError)?;
+    if buf.len() != len {
+        return Err(Error::ErrorValue(format!(
+            "Buffer length {} does not match {}x{}x{}",
+            buf.len(),
+            width,
+            height,
+            bytes_per_pixel
+        )));
+    }
+    Ok(buf
+        .chunks_exact_mut(bytes_per_pixel)
+        .enumerate()
+        .map(move |(index, pixel)| (index % width, index / width, pixel)))
+}
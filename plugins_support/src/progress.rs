@@ -0,0 +1,33 @@
+//! Модуль для реализации уведомлений о прогрессе обработки
+//!
+//! Предоставляет тип функции обратного вызова и перевод шагов в проценты
+
+use std::ffi::c_uint;
+
+///
+/// Функция обратного вызова прогресса, передаваемая хостом в плагин
+///
+/// # Параметры
+///
+/// * `percent` - процент выполнения (0..=100)
+///
+pub type ProgressCallback = extern "C" fn(percent: c_uint);
+
+///
+/// Перевод номера шага в процент выполнения
+///
+/// # Параметры
+///
+/// * `current` - номер завершенного шага (начиная с 1)
+/// * `total` - общее количество шагов
+///
+/// # Возращает
+/// Процент выполнения, не больше 100. Для `total == 0` возвращает 100
+///
+pub fn step_percent(current: usize, total: usize) -> c_uint {
+    if total == 0 {
+        return 100;
+    }
+    let percent = current.min(total).saturating_mul(100) / total;
+    percent as c_uint
+}
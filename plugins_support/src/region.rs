@@ -0,0 +1,120 @@
+//! Модуль для работы с областями изображения
+//!
+//! Предоставляет общее соглашение для плагинов: поле конфигурации `regions` со списком
+//! прямоугольных областей, к каждой из которых по очереди применяется преобразование.
+//! Поле поддерживают blur-plugin, color-vision-plugin и texture-noise-plugin
+//!
+//! Правила нормализации областей (см. [`resolve_regions`]):
+//!   - область нулевой ширины или высоты пропускается (ничего не изменяет)
+//!   - область, выходящая за правый или нижний край, обрезается по краю изображения
+//!   - область, левый верхний угол которой лежит за пределами изображения, - ошибка
+
+use crate::error::Error;
+use serde::Deserialize;
+
+/// Имя поля конфигурации со списком областей
+pub const REGIONS_FIELD: &str = "regions";
+
+///
+/// Прямоугольная область изображения
+///
+/// В конфигурации задается объектом `{"x": 0, "y": 0, "width": 10, "height": 10}`
+///
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    /// Левая граница в пикселях
+    pub x: usize,
+    /// Верхняя граница в пикселях
+    pub y: usize,
+    /// Ширина в пикселях
+    pub width: usize,
+    /// Высота в пикселях
+    pub height: usize,
+}
+
+impl Region {
+    /// Область, совпадающая со всем изображением
+    pub fn full(width: usize, height: usize) -> Self {
+        Self { x: 0, y: 0, width, height }
+    }
+
+    /// Область нулевой площади
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    ///
+    /// Нормализация области по размеру изображения
+    ///
+    /// # Параметры
+    ///
+    /// * `width` - ширина изображения в пикселях
+    /// * `height` - высота изображения в пикселях
+    ///
+    /// # Возращает
+    /// `None` для области нулевой площади, область, обрезанную по правому и нижнему краю
+    /// изображения, или `ErrorValue`, если левый верхний угол лежит за пределами изображения
+    ///
+    pub fn clamp(&self, width: usize, height: usize) -> Result<Option<Region>, Error> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+        if self.x >= width || self.y >= height {
+            return Err(Error::ErrorValue(format!(
+                "Region {:?} starts outside image bounds {}x{}",
+                self, width, height
+            )));
+        }
+        Ok(Some(Region {
+            x: self.x,
+            y: self.y,
+            width: self.width.min(width - self.x),
+            height: self.height.min(height - self.y),
+        }))
+    }
+
+    ///
+    /// Индексы пикселей области построчно
+    ///
+    /// # Параметры
+    ///
+    /// * `image_width` - ширина изображения в пикселях
+    ///
+    pub fn pixels(&self, image_width: usize) -> impl Iterator<Item = usize> + '_ {
+        (self.y..self.y + self.height)
+            .flat_map(move |y| (self.x..self.x + self.width).map(move |x| y * image_width + x))
+    }
+}
+
+///
+/// Список областей для обработки
+///
+/// # Параметры
+///
+/// * `regions` - области из конфигурации, `None` - все изображение
+/// * `width` - ширина изображения в пикселях
+/// * `height` - высота изображения в пикселях
+///
+/// # Возращает
+/// Нормализованные ([`Region::clamp`]) области в порядке конфигурации, без областей нулевой
+/// площади. Перекрывающиеся области обрабатываются по очереди, поэтому для неидемпотентных
+/// преобразований общая часть обрабатывается несколько раз. Пустой список - ни одной области.
+/// `ErrorValue`, если область начинается за пределами изображения
+///
+pub fn resolve_regions(
+    regions: Option<&[Region]>,
+    width: usize,
+    height: usize,
+) -> Result<Vec<Region>, Error> {
+    let Some(regions) = regions else {
+        return Ok(vec![Region::full(width, height)]);
+    };
+    let mut resolved = Vec::with_capacity(regions.len());
+    for region in regions {
+        match region.clamp(width, height)? {
+            Some(clamped) => resolved.push(clamped),
+            None => log::debug!("Skip empty region {:?}", region),
+        }
+    }
+    Ok(resolved)
+}
@@ -0,0 +1,80 @@
+//! Модуль для воспроизводимой генерации псевдослучайных чисел
+//!
+//! Предоставляет общий для плагинов генератор SplitMix64: одинаковое зерно `seed`
+//! дает одинаковую последовательность во всех плагинах, а [`Rng::split`] выделяет
+//! независимые потоки для параллельной обработки (например, поток на строку изображения)
+
+/// Шаг состояния SplitMix64 (дробная часть золотого сечения)
+const GOLDEN_GAMMA: u64 = 0x9E37_79B9_7F4A_7C15;
+
+///
+/// Перемешивание 64-битного значения (финализатор SplitMix64)
+///
+/// Биекция: разные входы дают разные выходы. Подходит для хеширования координат,
+/// когда значение нужно получить без последовательного состояния
+///
+/// # Параметры
+///
+/// * `value` - исходное значение
+///
+pub fn mix64(value: u64) -> u64 {
+    let mut z = value;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Генератор SplitMix64
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    ///
+    /// Генератор с заданным зерном
+    ///
+    /// # Параметры
+    ///
+    /// * `seed` - зерно генератора
+    ///
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Следующее 64-битное значение
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(GOLDEN_GAMMA);
+        mix64(self.state)
+    }
+
+    /// Следующее 32-битное значение (старшие биты `next_u64`)
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// Следующее значение в диапазоне `0.0..1.0` с 24 битами точности
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u32 << 24) as f32
+    }
+
+    /// Следующее значение в диапазоне `0.0..1.0` с 53 битами точности
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    ///
+    /// Независимый поток генератора
+    ///
+    /// Не изменяет состояние исходного генератора, поэтому поток с одним номером
+    /// воспроизводим независимо от порядка вызова. Зерно потока перемешивается,
+    /// чтобы последовательности соседних потоков не пересекались на практике
+    ///
+    /// # Параметры
+    ///
+    /// * `stream` - номер потока (например, номер строки или блока)
+    ///
+    pub fn split(&self, stream: u64) -> Rng {
+        Rng::new(mix64(self.state ^ mix64(stream.wrapping_add(GOLDEN_GAMMA))))
+    }
+}
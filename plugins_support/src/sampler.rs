@@ -0,0 +1,68 @@
+//! Модуль для выборки пикселей RGBA буфера по дробным координатам
+//!
+//! Предоставляет функциональность общего сэмплера для плагинов с передискретизацией:
+//! ближайший сосед или билинейная интерполяция
+
+use serde::Deserialize;
+
+const BYTE_PER_PIXEL: usize = 4;
+
+///
+/// Способ интерполяции
+///
+/// В конфигурации JSON задается полем `interpolation`: `"nearest"`, `"bilinear"`
+///
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Interpolation {
+    /// Ближайший сосед: быстрее, сохраняет резкие границы (pixel art)
+    Nearest,
+    /// Билинейная интерполяция: более гладкий результат
+    #[default]
+    Bilinear,
+}
+
+/// Пиксель по целочисленным координатам
+fn pixel(buf: &[u8], width: usize, x: usize, y: usize) -> &[u8] {
+    let index = (y * width + x) * BYTE_PER_PIXEL;
+    &buf[index..index + BYTE_PER_PIXEL]
+}
+
+///
+/// Выборка пикселя по дробным координатам
+///
+/// # Параметры
+///
+/// * `buf` - буфер RGBA (длина = width * height * 4)
+/// * `width` - ширина в пикселях
+/// * `height` - высота в пикселях
+/// * `x`, `y` - координаты, центр пикселя `(i, j)` находится в точке `(i, j)`.
+///   Координаты за пределами изображения прижимаются к краю
+/// * `interpolation` - способ интерполяции
+///
+pub fn sample(buf: &[u8], width: usize, height: usize, x: f64, y: f64, interpolation: Interpolation) -> [u8; 4] {
+    let x = x.clamp(0.0, (width - 1) as f64);
+    let y = y.clamp(0.0, (height - 1) as f64);
+    let mut result = [0; BYTE_PER_PIXEL];
+    match interpolation {
+        Interpolation::Nearest => {
+            result.copy_from_slice(pixel(buf, width, x.round() as usize, y.round() as usize));
+        }
+        Interpolation::Bilinear => {
+            let (left, top) = (x.floor() as usize, y.floor() as usize);
+            let (right, bottom) = ((left + 1).min(width - 1), (top + 1).min(height - 1));
+            let (fx, fy) = (x - left as f64, y - top as f64);
+            let corners = [
+                (pixel(buf, width, left, top), (1.0 - fx) * (1.0 - fy)),
+                (pixel(buf, width, right, top), fx * (1.0 - fy)),
+                (pixel(buf, width, left, bottom), (1.0 - fx) * fy),
+                (pixel(buf, width, right, bottom), fx * fy),
+            ];
+            for (channel, value) in result.iter_mut().enumerate() {
+                let sum: f64 = corners.iter().map(|(pixel, weight)| pixel[channel] as f64 * weight).sum();
+                *value = sum.round().clamp(0.0, 255.0) as u8;
+            }
+        }
+    }
+    result
+}
@@ -0,0 +1,220 @@
+//! Модуль для проверки соответствия плагина контракту хоста
+//!
+//! Предоставляет функциональность загрузки библиотеки плагина и проверки:
+//! наличия символа `process_image`, ответа на пробу ABI (`abi_probe`), метаданных
+//! (`plugin_params_schema`), отказа от обработки при нулевых указателях и отсутствия
+//! записи за пределы буфера изображения. Доступен с feature `testkit`
+
+use crate::abi::{ABI_VERSION, AbiProbeFn, PROBE_HEIGHT, PROBE_PARAMS, PROBE_WIDTH, ProcessImageFn, probe_pattern};
+use libloading::{Library, Symbol};
+use std::ffi::{CStr, CString, c_char, c_uint};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::ptr;
+
+/// Сигнатура функции счетчика предупреждений плагина (`export_warning_count!`)
+type WarningCountFn = extern "C" fn() -> c_uint;
+/// Сигнатура функции JSON Schema параметров плагина
+type ParamsSchemaFn = extern "C" fn() -> *const c_char;
+
+/// Байт-маркер, которым заполняется область за пределами изображения
+const GUARD_BYTE: u8 = 0xA5;
+/// Размер защитной области за пределами изображения
+const GUARD_LEN: usize = 16;
+
+/// Результат одной проверки
+#[derive(Debug, Clone)]
+pub struct ConformanceCheck {
+    /// Название проверки
+    pub name: String,
+    /// Проверка пройдена
+    pub passed: bool,
+    /// Подробности
+    pub details: String,
+}
+
+/// Отчет о проверке плагина
+#[derive(Debug, Clone)]
+pub struct ConformanceReport {
+    /// Путь к библиотеке плагина
+    pub library: PathBuf,
+    /// Выполненные проверки
+    pub checks: Vec<ConformanceCheck>,
+}
+
+impl ConformanceReport {
+    /// Все проверки пройдены
+    pub fn passed(&self) -> bool {
+        !self.checks.is_empty() && self.checks.iter().all(|check| check.passed)
+    }
+
+    /// Проверка с названием `name`, `None`, если она не выполнялась
+    pub fn check(&self, name: &str) -> Option<&ConformanceCheck> {
+        self.checks.iter().find(|check| check.name == name)
+    }
+
+    fn push(&mut self, name: &str, passed: bool, details: impl Into<String>) {
+        self.checks.push(ConformanceCheck {
+            name: name.to_string(),
+            passed,
+            details: details.into(),
+        });
+    }
+}
+
+impl fmt::Display for ConformanceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Conformance report: {}", self.library.display())?;
+        for check in &self.checks {
+            let status = if check.passed { "PASS" } else { "FAIL" };
+            writeln!(f, "  [{}] {}: {}", status, check.name, check.details)?;
+        }
+        write!(f, "Result: {}", if self.passed() { "PASS" } else { "FAIL" })
+    }
+}
+
+///
+/// Проверка библиотеки плагина
+///
+/// # Параметры
+///
+/// * `library` - путь к библиотеке плагина
+/// * `params` - корректная для плагина JSON конфигурация, используемая при пробном вызове
+///
+/// Отказ плагина от обработки при нулевом указателе определяется по росту счетчика
+/// `plugin_warning_count`: плагин должен записать ошибку в лог. Без этого символа
+/// проверки нулевых указателей не пройдены
+///
+/// # Возращает
+/// `ConformanceReport` со списком выполненных проверок. Если библиотеку не удалось
+/// загрузить или символ `process_image` не найден, последующие проверки не выполняются
+///
+/// # Пример
+///
+/// ```ignore
+/// let report = check_plugin(Path::new("target/debug/libblur_plugin.so"), r#"{"radius": 1, "step": 1}"#);
+/// println!("{}", report);
+/// assert!(report.passed());
+/// ```
+pub fn check_plugin(library: &Path, params: &str) -> ConformanceReport {
+    let mut report = ConformanceReport {
+        library: library.to_path_buf(),
+        checks: Vec::new(),
+    };
+    let lib = match unsafe { Library::new(library) } {
+        Ok(lib) => {
+            report.push("load", true, "library loaded");
+            lib
+        }
+        Err(e) => {
+            report.push("load", false, e.to_string());
+            return report;
+        }
+    };
+    let process_image: Symbol<ProcessImageFn> = match unsafe { lib.get("process_image") } {
+        Ok(symbol) => {
+            report.push("symbol process_image", true, "symbol resolved");
+            symbol
+        }
+        Err(e) => {
+            report.push("symbol process_image", false, e.to_string());
+            return report;
+        }
+    };
+    check_abi_probe(&lib, &mut report);
+    check_params_schema(&lib, &mut report);
+    let params = match CString::new(params) {
+        Ok(params) => params,
+        Err(e) => {
+            report.push("params", false, e.to_string());
+            return report;
+        }
+    };
+    let warning_count = unsafe { lib.get::<WarningCountFn>("plugin_warning_count") }.ok();
+    // Ошибка в логе плагина за время вызова `call`
+    let logged_error = |call: &dyn Fn()| match &warning_count {
+        Some(count) => {
+            let before = count();
+            call();
+            Ok(count() > before)
+        }
+        None => {
+            call();
+            Err("plugin_warning_count not exported, rejection cannot be observed")
+        }
+    };
+
+    let mut buf = vec![0u8; 4];
+    let rejected = logged_error(&|| unsafe { process_image(1, 1, ptr::null_mut(), params.as_ptr()) });
+    match rejected {
+        Ok(true) => report.push("null rgba_data", true, "call rejected with a logged error"),
+        Ok(false) => report.push("null rgba_data", false, "call returned without logging an error"),
+        Err(details) => report.push("null rgba_data", false, details),
+    }
+    let buf_ptr = buf.as_mut_ptr();
+    let rejected = logged_error(&|| unsafe { process_image(1, 1, buf_ptr, ptr::null()) });
+    let unchanged = buf == [0u8; 4];
+    match rejected {
+        Ok(true) if unchanged => report.push("null params", true, "call rejected with a logged error, buffer unchanged"),
+        Ok(_) if !unchanged => report.push("null params", false, "buffer modified without params"),
+        Ok(_) => report.push("null params", false, "call returned without logging an error"),
+        Err(details) => report.push("null params", false, details),
+    }
+
+    let mut buf = vec![GUARD_BYTE; 4 * 4 + GUARD_LEN];
+    buf[..16].iter_mut().enumerate().for_each(|(i, v)| *v = i as u8 * 16);
+    unsafe { process_image(2, 2, buf.as_mut_ptr(), params.as_ptr()) };
+    let guard_intact = buf[16..].iter().all(|&v| v == GUARD_BYTE);
+    report.push(
+        "2x2 call bounds",
+        guard_intact,
+        if guard_intact {
+            "no writes past width * height * 4"
+        } else {
+            "plugin wrote past the end of the image buffer"
+        },
+    );
+    report
+}
+
+/// Проба ABI: `abi_probe` должна вернуть [`ABI_VERSION`] и заполнить буфер [`probe_pattern`]
+fn check_abi_probe(lib: &Library, report: &mut ConformanceReport) {
+    let probe: Symbol<AbiProbeFn> = match unsafe { lib.get("abi_probe") } {
+        Ok(symbol) => symbol,
+        Err(e) => {
+            report.push("abi_probe", false, e.to_string());
+            return;
+        }
+    };
+    let len = (PROBE_WIDTH * PROBE_HEIGHT * 4) as usize;
+    let mut buf = vec![0u8; len];
+    let version = unsafe { probe(PROBE_WIDTH, PROBE_HEIGHT, buf.as_mut_ptr(), PROBE_PARAMS.as_ptr()) };
+    if version != ABI_VERSION {
+        report.push("abi_probe", false, format!("version {}, expected {}", version, ABI_VERSION));
+    } else if buf != probe_pattern(len) {
+        report.push("abi_probe", false, "probe buffer does not match the expected pattern");
+    } else {
+        report.push("abi_probe", true, format!("ABI version {}", version));
+    }
+}
+
+/// Метаданные: `plugin_params_schema`, если экспортирована, возвращает JSON объект
+fn check_params_schema(lib: &Library, report: &mut ConformanceReport) {
+    let Ok(schema) = (unsafe { lib.get::<ParamsSchemaFn>("plugin_params_schema") }) else {
+        report.push("metadata plugin_params_schema", true, "not exported");
+        return;
+    };
+    let schema = schema();
+    if schema.is_null() {
+        report.push("metadata plugin_params_schema", false, "null pointer returned");
+        return;
+    }
+    let schema = unsafe { CStr::from_ptr(schema) }.to_string_lossy();
+    match serde_json::from_str::<serde_json::Value>(&schema) {
+        Ok(value) if value.is_object() => {
+            report.push("metadata plugin_params_schema", true, "JSON Schema object")
+        }
+        Ok(_) => report.push("metadata plugin_params_schema", false, "schema is not a JSON object"),
+        Err(e) => report.push("metadata plugin_params_schema", false, format!("invalid JSON: {}", e)),
+    }
+}
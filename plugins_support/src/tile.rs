@@ -0,0 +1,179 @@
+//! Модуль для параллельной обработки изображения полосами с ореолом
+//!
+//! Предоставляет драйвер [`run_tiled`] для фильтров, читающих окрестность пикселя
+//! (свертки, билатеральный фильтр): результат делится на полосы строк, полосы
+//! обрабатываются в пуле потоков rayon, каждый поток пишет в свою полосу и читает
+//! неизменяемый исходный буфер в пределах полосы и `halo` строк над и под ней
+
+use crate::error::Error;
+use crate::logger::{call_level, set_call_level};
+use rayon::ThreadPoolBuilder;
+use rayon::prelude::*;
+use std::ops::Range;
+
+///
+/// Полоса результата и доступное потоку окно исходного изображения
+///
+/// Координаты всех методов - координаты полного изображения
+///
+#[derive(Debug, Clone)]
+pub struct Tile<'a> {
+    /// Строки исходного изображения `first_source_row..` с ореолом
+    source: &'a [u8],
+    /// Первая строка окна исходного изображения
+    first_source_row: usize,
+    /// Ширина изображения в пикселях
+    width: usize,
+    /// Количество байт на пиксель
+    bytes_per_pixel: usize,
+    /// Строки полосы результата
+    rows: Range<usize>,
+}
+
+impl Tile<'_> {
+    /// Ширина изображения в пикселях
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Строки изображения, которые записывает поток
+    pub fn rows(&self) -> Range<usize> {
+        self.rows.clone()
+    }
+
+    /// Строки исходного изображения, доступные потоку: полоса и ореол, обрезанный краями
+    pub fn source_rows(&self) -> Range<usize> {
+        let count = self.source.len() / (self.width * self.bytes_per_pixel);
+        self.first_source_row..self.first_source_row + count
+    }
+
+    ///
+    /// Пиксель исходного изображения
+    ///
+    /// # Параметры
+    ///
+    /// * `x` - столбец пикселя
+    /// * `y` - строка пикселя из [`Tile::source_rows`]
+    ///
+    /// # Panics
+    /// Если строка `y` не входит в окно (ореол меньше радиуса ядра) или `x` за краем
+    ///
+    pub fn pixel(&self, x: usize, y: usize) -> &[u8] {
+        assert!(
+            self.source_rows().contains(&y) && x < self.width,
+            "pixel ({}, {}) outside tile source rows {:?}",
+            x,
+            y,
+            self.source_rows()
+        );
+        let index = ((y - self.first_source_row) * self.width + x) * self.bytes_per_pixel;
+        &self.source[index..index + self.bytes_per_pixel]
+    }
+
+    ///
+    /// Индекс пикселя в буфере полосы результата
+    ///
+    /// # Параметры
+    ///
+    /// * `x` - столбец пикселя
+    /// * `y` - строка пикселя из [`Tile::rows`]
+    ///
+    pub fn band_index(&self, x: usize, y: usize) -> usize {
+        ((y - self.rows.start) * self.width + x) * self.bytes_per_pixel
+    }
+}
+
+///
+/// Параллельное применение фильтра окрестности
+///
+/// # Параметры
+///
+/// * `source` - исходный буфер изображения, не изменяется
+/// * `dst` - буфер результата той же длины
+/// * `width` - ширина изображения в пикселях
+/// * `height` - высота изображения в пикселях
+/// * `bytes_per_pixel` - количество байт на пиксель
+/// * `halo` - количество строк исходного изображения над и под полосой, доступных
+///   потоку (радиус ядра по вертикали)
+/// * `threads` - количество потоков, `0` - глобальный пул rayon (по числу доступных ядер)
+/// * `kernel` - обработка полосы: [`Tile`] и буфер полосы результата, вызывается
+///   из нескольких потоков
+///
+/// Результат не зависит от количества потоков, если `kernel` читает только пиксели
+/// из [`Tile::source_rows`]. Рабочие потоки логируют с уровнем вызывающего потока,
+/// см. [`set_call_level`]
+///
+/// # Возращает
+/// `OverflowError` при переполнении размера, `ErrorValue`, если длина `source` или `dst`
+/// не равна `width * height * bytes_per_pixel` или пул потоков не создан
+///
+#[allow(clippy::too_many_arguments)]
+pub fn run_tiled<F>(
+    source: &[u8],
+    dst: &mut [u8],
+    width: usize,
+    height: usize,
+    bytes_per_pixel: usize,
+    halo: usize,
+    threads: usize,
+    kernel: F,
+) -> Result<(), Error>
+where
+    F: Fn(&Tile, &mut [u8]) + Sync,
+{
+    let row_len = width.checked_mul(bytes_per_pixel).ok_or(Error::OverflowError)?;
+    let len = row_len.checked_mul(height).ok_or(Error::OverflowError)?;
+    if source.len() != len || dst.len() != len {
+        return Err(Error::ErrorValue(format!(
+            "Buffer lengths {} and {} do not match {}x{}x{}",
+            source.len(),
+            dst.len(),
+            width,
+            height,
+            bytes_per_pixel
+        )));
+    }
+    if len == 0 {
+        return Ok(());
+    }
+    let workers = match threads {
+        0 => rayon::current_num_threads(),
+        threads => threads,
+    };
+    let rows_per_tile = height.div_ceil(workers.min(height));
+    let tile = |index: usize| {
+        let first_row = index * rows_per_tile;
+        let last_row = (first_row + rows_per_tile).min(height);
+        let first_source_row = first_row.saturating_sub(halo);
+        let last_source_row = last_row.saturating_add(halo).min(height);
+        Tile {
+            source: &source[first_source_row * row_len..last_source_row * row_len],
+            first_source_row,
+            width,
+            bytes_per_pixel,
+            rows: first_row..last_row,
+        }
+    };
+    if rows_per_tile == height {
+        kernel(&tile(0), dst);
+        return Ok(());
+    }
+    let (level, kernel) = (call_level(), &kernel);
+    let bands = dst.par_chunks_mut(rows_per_tile * row_len).enumerate();
+    let run = move || {
+        bands.for_each(|(index, band)| {
+            let _level = level.map(set_call_level);
+            kernel(&tile(index), band)
+        })
+    };
+    if threads == 0 {
+        run();
+    } else {
+        ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .map_err(|e| Error::ErrorValue(format!("Thread pool: {}", e)))?
+            .install(run);
+    }
+    Ok(())
+}
@@ -0,0 +1,167 @@
+//! Модуль для попиксельных преобразований
+//!
+//! Предоставляет трейт [`PixelTransform`] для простых преобразований пикселя (яркость,
+//! инверсия, тонирование) и драйвер [`run_transform`], применяющий любое преобразование
+//! к RGBA буферу в области и в несколько потоков. Преобразования составляются во время
+//! выполнения (`Vec<Box<dyn PixelTransform>>`) без динамической загрузки плагинов
+
+use crate::error::Error;
+use crate::logger::{call_level, set_call_level};
+use crate::region::Region;
+use std::num::NonZeroUsize;
+use std::thread;
+
+/// Сведения о кадре, общие для всех пикселей
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameCtx {
+    /// Ширина изображения в пикселях
+    pub width: usize,
+    /// Высота изображения в пикселях
+    pub height: usize,
+}
+
+///
+/// Преобразование одного RGBA пикселя
+///
+/// Вызывается из нескольких потоков, поэтому реализация должна быть `Sync`
+///
+pub trait PixelTransform: Sync {
+    ///
+    /// Преобразование пикселя
+    ///
+    /// # Параметры
+    ///
+    /// * `px` - пиксель RGBA
+    /// * `x` - столбец пикселя
+    /// * `y` - строка пикселя
+    /// * `ctx` - сведения о кадре
+    ///
+    fn apply(&self, px: &mut [u8; 4], x: usize, y: usize, ctx: &FrameCtx);
+}
+
+/// Последовательное применение преобразований в порядке списка
+impl PixelTransform for [Box<dyn PixelTransform>] {
+    fn apply(&self, px: &mut [u8; 4], x: usize, y: usize, ctx: &FrameCtx) {
+        for transform in self {
+            transform.apply(px, x, y, ctx);
+        }
+    }
+}
+
+/// Последовательное применение преобразований в порядке списка
+impl PixelTransform for Vec<Box<dyn PixelTransform>> {
+    fn apply(&self, px: &mut [u8; 4], x: usize, y: usize, ctx: &FrameCtx) {
+        self.as_slice().apply(px, x, y, ctx);
+    }
+}
+
+/// Инверсия цветовых каналов, альфа-канал не изменяется
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Invert;
+
+impl PixelTransform for Invert {
+    fn apply(&self, px: &mut [u8; 4], _x: usize, _y: usize, _ctx: &FrameCtx) {
+        for channel in &mut px[..3] {
+            *channel = 255 - *channel;
+        }
+    }
+}
+
+/// Изменение яркости цветовых каналов на `delta` с насыщением
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Brightness {
+    /// Смещение значения канала
+    pub delta: i16,
+}
+
+impl PixelTransform for Brightness {
+    fn apply(&self, px: &mut [u8; 4], _x: usize, _y: usize, _ctx: &FrameCtx) {
+        for channel in &mut px[..3] {
+            *channel = (i16::from(*channel) + self.delta).clamp(0, 255) as u8;
+        }
+    }
+}
+
+/// Применение преобразования к строкам `rows`, начинающимся со строки `first_row`
+fn apply_rows(
+    rows: &mut [u8],
+    first_row: usize,
+    region: &Region,
+    transform: &dyn PixelTransform,
+    ctx: &FrameCtx,
+) {
+    for (offset, row) in rows.chunks_exact_mut(ctx.width * 4).enumerate() {
+        let y = first_row + offset;
+        if y < region.y || y >= region.y + region.height {
+            continue;
+        }
+        let row = &mut row[region.x * 4..(region.x + region.width) * 4];
+        for (index, px) in row.chunks_exact_mut(4).enumerate() {
+            let px: &mut [u8; 4] = px.try_into().expect("chunk of 4 bytes");
+            transform.apply(px, region.x + index, y, ctx);
+        }
+    }
+}
+
+///
+/// Применение преобразования к RGBA буферу
+///
+/// # Параметры
+///
+/// * `buf` - буфер изображения RGBA
+/// * `width` - ширина изображения в пикселях
+/// * `height` - высота изображения в пикселях
+/// * `transform` - преобразование пикселя
+/// * `roi` - область применения (нормализуется по [`Region::clamp`]), `None` - все изображение
+/// * `threads` - количество потоков, `0` - по числу доступных ядер; рабочие потоки логируют
+///   с уровнем вызывающего потока, см. [`set_call_level`]
+///
+/// # Возращает
+/// `OverflowError` при переполнении размера, `ErrorValue`, если длина буфера не равна
+/// `width * height * 4` или область начинается за пределами изображения
+///
+pub fn run_transform(
+    buf: &mut [u8],
+    width: usize,
+    height: usize,
+    transform: &dyn PixelTransform,
+    roi: Option<Region>,
+    threads: usize,
+) -> Result<(), Error> {
+    let row_len = width.checked_mul(4).ok_or(Error::OverflowError)?;
+    let len = row_len.checked_mul(height).ok_or(Error::OverflowError)?;
+    if buf.len() != len {
+        return Err(Error::ErrorValue(format!(
+            "Buffer length {} does not match {}x{}x4",
+            buf.len(),
+            width,
+            height
+        )));
+    }
+    let Some(region) = roi.unwrap_or(Region::full(width, height)).clamp(width, height)? else {
+        return Ok(());
+    };
+    let ctx = FrameCtx { width, height };
+    let threads = match threads {
+        0 => thread::available_parallelism().map_or(1, NonZeroUsize::get),
+        threads => threads,
+    };
+    // Делятся только строки области, строки вне ее не передаются потокам
+    let rows = &mut buf[region.y * row_len..(region.y + region.height) * row_len];
+    let rows_per_thread = region.height.div_ceil(threads.min(region.height));
+    if rows_per_thread == region.height {
+        apply_rows(rows, region.y, &region, transform, &ctx);
+        return Ok(());
+    }
+    let level = call_level();
+    thread::scope(|scope| {
+        for (index, chunk) in rows.chunks_mut(rows_per_thread * row_len).enumerate() {
+            let (region, ctx) = (&region, &ctx);
+            scope.spawn(move || {
+                let _level = level.map(set_call_level);
+                apply_rows(chunk, region.y + index * rows_per_thread, region, transform, ctx)
+            });
+        }
+    });
+    Ok(())
+}
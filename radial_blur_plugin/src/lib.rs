@@ -0,0 +1,291 @@
+#![warn(missing_docs)]
+//! Модуль для реализации обработки изображения - радиальное (zoom) размытие
+//!
+//! Предоставляет функциональность размытия вдоль лучей, направленных к заданному центру:
+//! для каждого пикселя усредняются точки исходного изображения между пикселем и центром
+
+use log::LevelFilter;
+use plugins_support::config_parse::warn_ignored_fields;
+use plugins_support::image_size::rgba_len;
+use plugins_support::logger::{
+    LogTimezone, get_log_level, set_call_level, set_log_timezone, setup_logger_once,
+};
+use plugins_support::sampler::{Interpolation, sample};
+use plugins_support::{config_parse::ConfigReader, error::Error};
+use serde::Deserialize;
+use std::ffi::{CStr, c_char, c_uint};
+use std::slice;
+
+const PKG_NAME: &str = env!("CARGO_PKG_NAME");
+const BYTE_PER_PIXEL: usize = 4;
+
+plugins_support::export_warning_count!();
+plugins_support::export_abi_probe!();
+
+/// Поля конфигурации, которые использует плагин
+const KNOWN_FIELDS: &[&str] = &["center_x", "center_y", "strength", "samples", "interpolation"];
+
+#[derive(Deserialize, Debug)]
+struct ConfigTransform {
+    center_x: f64,
+    center_y: f64,
+    strength: f64,
+    samples: usize,
+    interpolation: Option<Interpolation>,
+    log_level: Option<String>,
+    log_tz: Option<LogTimezone>,
+}
+
+/// Радиальное размытие RGBA буфера
+///
+/// # Аргументы
+/// * `buf`      – изменяемый буфер RGBA (длина = width * height * 4)
+/// * `width`    – ширина в пикселях
+/// * `height`   – высота в пикселях
+/// * `center`   – центр размытия в долях ширины и высоты (0..=1)
+/// * `strength` – доля расстояния до центра, на которую распространяется размытие (>= 0)
+/// * `samples`  – количество точек усреднения (>= 1)
+/// * `interpolation` – способ выборки точек между пикселями
+///
+pub fn radial_blur(
+    buf: &mut [u8],
+    width: usize,
+    height: usize,
+    center: (f64, f64),
+    strength: f64,
+    samples: usize,
+    interpolation: Interpolation,
+) -> Result<(), Error> {
+    if samples == 0 {
+        return Err(Error::ErrorValue("Samples must be at least 1".to_string()));
+    }
+    if strength.is_nan() || strength < 0.0 {
+        return Err(Error::ErrorValue("Strength must be non-negative".to_string()));
+    }
+    if !(0.0..=1.0).contains(&center.0) || !(0.0..=1.0).contains(&center.1) {
+        return Err(Error::ErrorValue("Center must be in 0..=1".to_string()));
+    }
+    let center_x = center.0 * (width - 1) as f64;
+    let center_y = center.1 * (height - 1) as f64;
+    let source = buf.to_vec();
+    for y in 0..height {
+        for x in 0..width {
+            let dx = center_x - x as f64;
+            let dy = center_y - y as f64;
+            let mut sum = [0usize; BYTE_PER_PIXEL];
+            for step in 0..samples {
+                let t = strength * step as f64 / samples as f64;
+                let point = sample(&source, width, height, x as f64 + dx * t, y as f64 + dy * t, interpolation);
+                for (value, channel) in sum.iter_mut().zip(point) {
+                    *value += channel as usize;
+                }
+            }
+            let index = (y * width + x) * BYTE_PER_PIXEL;
+            for (channel, value) in sum.iter().enumerate() {
+                buf[index + channel] = ((value + samples / 2) / samples) as u8;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// ```rust
+///
+///  Трансформация RGBA буффера радиальным размытием
+///
+///
+///  # Параметры
+///   - `width` (`c_uint`):  ширина изображения в пикселях
+///   - `height` (`c_uint`): высота изображения в пикселях
+///   - `rgba_data` (`*mut u8`): Мутабельный сырой указатель на RGBA буфер. Размер буфера `width * height * 4` bytes.
+///   - `params` (`*const c_char`): Сырой указатель на нуль терменированноу строку  конфигурации JSON-формата.
+///
+///  # Параметры конфигурации (JSON формат)
+///   - `log_level` (optional, string): уровень логирования ("Debug", "Info").
+///   - `log_tz` (optional, string): часовой пояс меток времени лога ("local", "utc"), по умолчанию "local"
+///   - `center_x` (required, float): центр размытия по горизонтали в долях ширины, 0..=1
+///   - `center_y` (required, float): центр размытия по вертикали в долях высоты, 0..=1
+///   - `strength` (required, float): доля расстояния до центра, на которую тянется размытие. Должна быть >= 0
+///   - `samples` (required, integer): количество точек усреднения. Должно быть >= 1
+///   - `interpolation` (optional, string): выборка точек ("nearest", "bilinear"), по умолчанию "bilinear"
+///
+///  # Пример JSON:
+///  ```json
+///  {
+///     "log_level": "Debug",
+///     "center_x": 0.5,
+///     "center_y": 0.5,
+///     "strength": 0.2,
+///     "samples": 16,
+///     "interpolation": "bilinear"
+///  }
+///  ```
+///
+///
+///   # Error Handling
+///   - Ошибки фиксируются в логе
+///   - Поля конфигурации, которые плагин не использует, фиксируются в логе предупреждением
+///
+///
+///   # Пример С
+///  ```c
+///  // In C code
+///   unsigned int width = 1920;
+///   unsigned int height = 1080;
+///   unsigned char *image_data = ...; // RGBA buffer, allocated elsewhere
+///   const char *config = "{\"center_x\": 0.5, \"center_y\": 0.5, \"strength\": 0.2, \"samples\": 16}";
+///   process_image(width, height, image_data, config);
+///   ```
+/// # Safety
+///  Данная функция  помечена `unsafe`:
+///   - Работа напрямую с сырыми указателями (`rgba_data`, `params`) предстаялет external C code
+///   - Использования недопустимого указателя или парметров width, height undefined behavior.
+/// ```
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn process_image(
+    width: c_uint,
+    height: c_uint,
+    rgba_data: *mut u8,
+    params: *const c_char,
+) {
+    let file = PKG_NAME.to_owned() + ".log";
+    if let Err(e) = setup_logger_once(LevelFilter::Debug, &file) {
+        match e {
+            Error::LoggerInitError(_) => {
+                log::warn!("Logger init error: {}", e);
+            }
+            _ => {
+                log::error!("Logger init error: {}", e);
+                return;
+            }
+        }
+    }
+    log::info!("Start plugin {}", &file);
+    if params.is_null() {
+        log::error!("Pointer params is_null");
+        return;
+    }
+    let config = unsafe { CStr::from_ptr(params) };
+    let params_config = match config.to_str() {
+        Ok(config) => {
+            warn_ignored_fields(config, KNOWN_FIELDS);
+            let config: Result<ConfigReader<ConfigTransform>, Error> =
+                ConfigReader::try_from(config);
+            if let Ok(config) = config {
+                config
+            } else {
+                log::error!("Error converting config to string");
+                return;
+            }
+        }
+        _ => {
+            log::error!("Invalid config file");
+            return;
+        }
+    };
+    // Уровень из конфигурации действует только в потоке вызова до его завершения
+    let _log_level = params_config
+        .config
+        .log_level
+        .as_deref()
+        .map(|log_level| set_call_level(get_log_level(log_level)));
+    if let Some(log_tz) = params_config.config.log_tz {
+        set_log_timezone(log_tz);
+    }
+    if rgba_data.is_null() {
+        log::error!("Null pointer rgba_data");
+        return;
+    }
+    if width == 0 {
+        log::error!("width cannot be 0");
+        return;
+    }
+    if height == 0 {
+        log::error!("height cannot be 0");
+        return;
+    }
+    let len_in_pixel = match rgba_len(width, height) {
+        Ok(len) => len,
+        Err(e) => {
+            log::error!("Length calculation failed: {}", e);
+            return;
+        }
+    };
+    let height: usize = match height.try_into() {
+        Ok(h) => h,
+        Err(_) => {
+            log::error!("Height conversion failed");
+            return;
+        }
+    };
+    let width: usize = match width.try_into() {
+        Ok(w) => w,
+        Err(_) => {
+            log::error!("Width conversion failed");
+            return;
+        }
+    };
+    log::info!("Start converting image");
+    let buf = unsafe { slice::from_raw_parts_mut(rgba_data, len_in_pixel) };
+    let config = &params_config.config;
+    if let Err(e) = radial_blur(
+        buf,
+        width,
+        height,
+        (config.center_x, config.center_y),
+        config.strength,
+        config.samples,
+        config.interpolation.unwrap_or_default(),
+    ) {
+        log::error!("Radial blur error: {}", e);
+        return;
+    }
+    log::info!("Finish converting image");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    const SIZE: usize = 33;
+
+    /// Вертикальные полосы шириной в один пиксель
+    fn stripes() -> Vec<u8> {
+        let mut buf = vec![255; SIZE * SIZE * BYTE_PER_PIXEL];
+        for (index, pixel) in buf.chunks_exact_mut(BYTE_PER_PIXEL).enumerate() {
+            if index % SIZE % 2 == 1 {
+                pixel[..3].fill(0);
+            }
+        }
+        buf
+    }
+
+    #[test]
+    fn test_radial_blur_center_and_edge() {
+        let source = stripes();
+        let mut buf = source.clone();
+        let json = r#"{"center_x": 0.5, "center_y": 0.5, "strength": 0.3, "samples": 8, "interpolation": "nearest"}"#;
+        let params_cstring = CString::new(json).unwrap();
+        unsafe { process_image(SIZE as c_uint, SIZE as c_uint, buf.as_mut_ptr(), params_cstring.as_ptr()) };
+        let change = |x: usize, y: usize| {
+            let index = (y * SIZE + x) * BYTE_PER_PIXEL;
+            (buf[index] as i32 - source[index] as i32).abs()
+        };
+        let middle = SIZE / 2;
+        assert!(change(middle, middle) <= 2);
+        assert!(change(middle + 1, middle) <= 2);
+        assert!(change(0, middle) > 64, "edge change {}", change(0, middle));
+        assert!(change(SIZE - 1, middle) > 64);
+        assert!(buf.chunks_exact(BYTE_PER_PIXEL).all(|pixel| pixel[3] == 255));
+    }
+
+    #[test]
+    fn test_radial_blur_invalid_config() {
+        let mut buf = stripes();
+        assert!(radial_blur(&mut buf, SIZE, SIZE, (0.5, 0.5), 0.3, 0, Interpolation::Nearest).is_err());
+        assert!(radial_blur(&mut buf, SIZE, SIZE, (0.5, 0.5), -1.0, 4, Interpolation::Nearest).is_err());
+        assert!(radial_blur(&mut buf, SIZE, SIZE, (1.5, 0.5), 0.3, 4, Interpolation::Nearest).is_err());
+        assert_eq!(buf, stripes());
+    }
+}
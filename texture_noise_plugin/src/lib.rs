@@ -0,0 +1,379 @@
+#![warn(missing_docs)]
+//! Модуль для реализации обработки изображения - наложение процедурного шума
+//!
+//! Предоставляет функциональность генерации value noise (несколько октав, задаваемое зерно)
+//! и наложения его на изображение в одном из режимов наложения
+
+use log::LevelFilter;
+use plugins_support::blend_mode::{BlendMode, blend_channel};
+use plugins_support::config_parse::warn_ignored_fields;
+use plugins_support::image_size::rgba_len;
+use plugins_support::region::{REGIONS_FIELD, Region, resolve_regions};
+use plugins_support::logger::{
+    LogTimezone, get_log_level, set_call_level, set_log_timezone, setup_logger_once,
+};
+use plugins_support::rng::mix64;
+use plugins_support::{config_parse::ConfigReader, error::Error};
+use serde::Deserialize;
+use std::ffi::{CStr, c_char, c_uint};
+use std::slice;
+
+const PKG_NAME: &str = env!("CARGO_PKG_NAME");
+const BYTE_PER_PIXEL: usize = 4;
+
+plugins_support::export_warning_count!();
+plugins_support::export_abi_probe!();
+
+/// Поля конфигурации, которые использует плагин
+const KNOWN_FIELDS: &[&str] = &["scale", "octaves", "seed", "mode", "opacity", REGIONS_FIELD];
+
+#[derive(Deserialize, Debug)]
+struct ConfigTransform {
+    scale: f64,
+    octaves: usize,
+    seed: Option<u64>,
+    mode: Option<BlendMode>,
+    opacity: f64,
+    regions: Option<Vec<Region>>,
+    log_level: Option<String>,
+    log_tz: Option<LogTimezone>,
+}
+
+/// Псевдослучайное значение 0..=1 в узле решетки
+fn lattice(seed: u64, x: i64, y: i64) -> f64 {
+    let hash = mix64(
+        seed ^ (x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ (y as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F),
+    );
+    (hash >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Гладкая интерполяция между узлами решетки
+fn smoothstep(t: f64) -> f64 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Value noise одной октавы в точке `(x, y)`, результат 0..=1
+fn value_noise(seed: u64, x: f64, y: f64) -> f64 {
+    let (left, top) = (x.floor(), y.floor());
+    let (fx, fy) = (smoothstep(x - left), smoothstep(y - top));
+    let (left, top) = (left as i64, top as i64);
+    let upper = lattice(seed, left, top) * (1.0 - fx) + lattice(seed, left + 1, top) * fx;
+    let lower = lattice(seed, left, top + 1) * (1.0 - fx) + lattice(seed, left + 1, top + 1) * fx;
+    upper * (1.0 - fy) + lower * fy
+}
+
+///
+/// Многооктавный value noise в точке, результат 0..=1
+///
+/// # Аргументы
+/// * `seed`    – зерно генератора
+/// * `x`, `y`  – координаты пикселя
+/// * `scale`   – размер ячейки первой октавы в пикселях (> 0)
+/// * `octaves` – количество октав, каждая следующая вдвое мельче и вдвое слабее
+///
+pub fn fractal_noise(seed: u64, x: f64, y: f64, scale: f64, octaves: usize) -> f64 {
+    let mut sum = 0.0;
+    let mut total = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0 / scale;
+    for octave in 0..octaves {
+        sum += value_noise(seed.wrapping_add(octave as u64), x * frequency, y * frequency) * amplitude;
+        total += amplitude;
+        amplitude /= 2.0;
+        frequency *= 2.0;
+    }
+    sum / total
+}
+
+/// Наложение шума на RGBA буфер
+///
+/// # Аргументы
+/// * `buf`     – изменяемый буфер RGBA (длина = width * height * 4), альфа-канал не изменяется
+/// * `width`   – ширина в пикселях
+/// * `regions` – области наложения (уже нормализованные, см. `resolve_regions`),
+///   координаты шума отсчитываются от угла изображения, а не области
+/// * `seed`    – зерно генератора
+/// * `scale`   – размер ячейки первой октавы в пикселях (> 0)
+/// * `octaves` – количество октав (>= 1)
+/// * `mode`    – режим наложения
+/// * `opacity` – непрозрачность шума (0..=1)
+///
+#[allow(clippy::too_many_arguments)]
+pub fn apply_noise(
+    buf: &mut [u8],
+    width: usize,
+    regions: &[Region],
+    seed: u64,
+    scale: f64,
+    octaves: usize,
+    mode: BlendMode,
+    opacity: f64,
+) -> Result<(), Error> {
+    if scale.is_nan() || scale <= 0.0 {
+        return Err(Error::ErrorValue("Scale must be greater than 0".to_string()));
+    }
+    if octaves == 0 {
+        return Err(Error::ErrorValue("Octaves must be at least 1".to_string()));
+    }
+    if !(0.0..=1.0).contains(&opacity) {
+        return Err(Error::ErrorValue("Opacity must be in 0..=1".to_string()));
+    }
+    for region in regions {
+        for index in region.pixels(width) {
+            let (x, y) = ((index % width) as f64, (index / width) as f64);
+            let noise = (fractal_noise(seed, x, y, scale, octaves) * 255.0).round() as u8;
+            let pixel = &mut buf[index * BYTE_PER_PIXEL..(index + 1) * BYTE_PER_PIXEL];
+            for value in &mut pixel[..3] {
+                let blended = blend_channel(mode, *value, noise) as f64;
+                *value = (*value as f64 + (blended - *value as f64) * opacity).round() as u8;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// ```rust
+///
+///  Трансформация RGBA буффера наложением процедурного шума
+///
+///
+///  # Параметры
+///   - `width` (`c_uint`):  ширина изображения в пикселях
+///   - `height` (`c_uint`): высота изображения в пикселях
+///   - `rgba_data` (`*mut u8`): Мутабельный сырой указатель на RGBA буфер. Размер буфера `width * height * 4` bytes.
+///   - `params` (`*const c_char`): Сырой указатель на нуль терменированноу строку  конфигурации JSON-формата.
+///
+///  # Параметры конфигурации (JSON формат)
+///   - `log_level` (optional, string): уровень логирования ("Debug", "Info").
+///   - `log_tz` (optional, string): часовой пояс меток времени лога ("local", "utc"), по умолчанию "local"
+///   - `scale` (required, float): размер ячейки шума в пикселях. Должен быть больше 0
+///   - `octaves` (required, integer): количество октав. Должно быть >= 1
+///   - `seed` (optional, integer): зерно генератора, по умолчанию 0
+///   - `mode` (optional, string): режим наложения ("normal", "multiply", "screen", "overlay", "add", "difference"), по умолчанию "normal"
+///   - `opacity` (required, float): непрозрачность шума 0..=1
+///   - `regions` (optional, array): Области наложения `{"x", "y", "width", "height"}`,
+///     по умолчанию все изображение. Области обрабатываются по очереди, на перекрывающуюся
+///     часть шум накладывается несколько раз. Область нулевой площади пропускается,
+///     выходящая за край изображения обрезается по краю
+///
+///  # Пример JSON:
+///  ```json
+///  {
+///     "log_level": "Debug",
+///     "scale": 32.0,
+///     "octaves": 4,
+///     "seed": 42,
+///     "mode": "overlay",
+///     "opacity": 0.3
+///  }
+///  ```
+///
+///
+///   # Error Handling
+///   - Ошибки фиксируются в логе
+///   - Поля конфигурации, которые плагин не использует, фиксируются в логе предупреждением
+///
+///
+///   # Пример С
+///  ```c
+///  // In C code
+///   unsigned int width = 1920;
+///   unsigned int height = 1080;
+///   unsigned char *image_data = ...; // RGBA buffer, allocated elsewhere
+///   const char *config = "{\"scale\": 32.0, \"octaves\": 4, \"seed\": 42, \"opacity\": 0.3}";
+///   process_image(width, height, image_data, config);
+///   ```
+/// # Safety
+///  Данная функция  помечена `unsafe`:
+///   - Работа напрямую с сырыми указателями (`rgba_data`, `params`) предстаялет external C code
+///   - Использования недопустимого указателя или парметров width, height undefined behavior.
+/// ```
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn process_image(
+    width: c_uint,
+    height: c_uint,
+    rgba_data: *mut u8,
+    params: *const c_char,
+) {
+    let file = PKG_NAME.to_owned() + ".log";
+    if let Err(e) = setup_logger_once(LevelFilter::Debug, &file) {
+        match e {
+            Error::LoggerInitError(_) => {
+                log::warn!("Logger init error: {}", e);
+            }
+            _ => {
+                log::error!("Logger init error: {}", e);
+                return;
+            }
+        }
+    }
+    log::info!("Start plugin {}", &file);
+    if params.is_null() {
+        log::error!("Pointer params is_null");
+        return;
+    }
+    let config = unsafe { CStr::from_ptr(params) };
+    let params_config = match config.to_str() {
+        Ok(config) => {
+            warn_ignored_fields(config, KNOWN_FIELDS);
+            let config: Result<ConfigReader<ConfigTransform>, Error> =
+                ConfigReader::try_from(config);
+            if let Ok(config) = config {
+                config
+            } else {
+                log::error!("Error converting config to string");
+                return;
+            }
+        }
+        _ => {
+            log::error!("Invalid config file");
+            return;
+        }
+    };
+    // Уровень из конфигурации действует только в потоке вызова до его завершения
+    let _log_level = params_config
+        .config
+        .log_level
+        .as_deref()
+        .map(|log_level| set_call_level(get_log_level(log_level)));
+    if let Some(log_tz) = params_config.config.log_tz {
+        set_log_timezone(log_tz);
+    }
+    if rgba_data.is_null() {
+        log::error!("Null pointer rgba_data");
+        return;
+    }
+    if width == 0 {
+        log::error!("width cannot be 0");
+        return;
+    }
+    if height == 0 {
+        log::error!("height cannot be 0");
+        return;
+    }
+    let len_in_pixel = match rgba_len(width, height) {
+        Ok(len) => len,
+        Err(e) => {
+            log::error!("Length calculation failed: {}", e);
+            return;
+        }
+    };
+    let width: usize = match width.try_into() {
+        Ok(w) => w,
+        Err(_) => {
+            log::error!("Width conversion failed");
+            return;
+        }
+    };
+    let regions = match resolve_regions(params_config.config.regions.as_deref(), width, height as usize) {
+        Ok(regions) => regions,
+        Err(e) => {
+            log::error!("Invalid regions: {}", e);
+            return;
+        }
+    };
+    log::info!("Start converting image");
+    let buf = unsafe { slice::from_raw_parts_mut(rgba_data, len_in_pixel) };
+    let config = &params_config.config;
+    if let Err(e) = apply_noise(
+        buf,
+        width,
+        &regions,
+        config.seed.unwrap_or(0),
+        config.scale,
+        config.octaves,
+        config.mode.unwrap_or_default(),
+        config.opacity,
+    ) {
+        log::error!("Texture noise error: {}", e);
+        return;
+    }
+    log::info!("Finish converting image");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    const WIDTH: usize = 16;
+    const HEIGHT: usize = 8;
+
+    fn gray() -> Vec<u8> {
+        vec![128; WIDTH * HEIGHT * BYTE_PER_PIXEL]
+    }
+
+    fn run(json: &str) -> Vec<u8> {
+        let mut buf = gray();
+        let params_cstring = CString::new(json).unwrap();
+        unsafe { process_image(WIDTH as c_uint, HEIGHT as c_uint, buf.as_mut_ptr(), params_cstring.as_ptr()) };
+        buf
+    }
+
+    #[test]
+    fn test_noise_same_seed_identical() {
+        let json = r#"{"scale": 4.0, "octaves": 3, "seed": 7, "mode": "normal", "opacity": 0.8}"#;
+        let first = run(json);
+        assert_eq!(first, run(json));
+        assert_ne!(first, gray());
+        let other = run(r#"{"scale": 4.0, "octaves": 3, "seed": 8, "mode": "normal", "opacity": 0.8}"#);
+        assert_ne!(first, other);
+        assert!(first.chunks_exact(BYTE_PER_PIXEL).all(|pixel| pixel[3] == 128));
+    }
+
+    #[test]
+    fn test_noise_zero_opacity_noop() {
+        let result = run(r#"{"scale": 4.0, "octaves": 3, "seed": 7, "mode": "overlay", "opacity": 0.0}"#);
+        assert_eq!(result, gray());
+    }
+
+    fn changed_pixels(buf: &[u8]) -> Vec<usize> {
+        let pixels = buf.chunks_exact(BYTE_PER_PIXEL).enumerate();
+        pixels.filter(|(_, pixel)| *pixel != [128; BYTE_PER_PIXEL]).map(|(i, _)| i).collect()
+    }
+
+    #[test]
+    fn test_noise_regions_only() {
+        let full = run(r#"{"scale": 4.0, "octaves": 3, "seed": 7, "mode": "difference", "opacity": 1.0}"#);
+        let result = run(
+            r#"{"scale": 4.0, "octaves": 3, "seed": 7, "mode": "difference", "opacity": 1.0, "regions": [
+                {"x": 1, "y": 1, "width": 1, "height": 1},
+                {"x": 5, "y": 3, "width": 1, "height": 1}
+            ]}"#,
+        );
+        let (first, second) = (WIDTH + 1, 3 * WIDTH + 5);
+        assert_eq!(changed_pixels(&result), vec![first, second]);
+        // Шум в области совпадает с шумом при обработке всего изображения
+        for index in [first, second] {
+            let range = index * BYTE_PER_PIXEL..(index + 1) * BYTE_PER_PIXEL;
+            assert_eq!(result[range.clone()], full[range]);
+        }
+    }
+
+    #[test]
+    fn test_noise_zero_area_region_noop() {
+        let json = r#"{"scale": 4.0, "octaves": 3, "seed": 7, "opacity": 1.0, "regions": [{"x": 2, "y": 2, "width": 5, "height": 0}]}"#;
+        assert_eq!(run(json), gray());
+    }
+
+    #[test]
+    fn test_noise_over_wide_region_clamped() {
+        let full = run(r#"{"scale": 4.0, "octaves": 3, "seed": 7, "mode": "difference", "opacity": 1.0}"#);
+        let json = r#"{"scale": 4.0, "octaves": 3, "seed": 7, "mode": "difference", "opacity": 1.0,
+            "regions": [{"x": 0, "y": 6, "width": 1000, "height": 1000}]}"#;
+        let result = run(json);
+        let split = 6 * WIDTH * BYTE_PER_PIXEL;
+        assert_eq!(result[..split], gray()[..split]);
+        assert_eq!(result[split..], full[split..]);
+    }
+
+    #[test]
+    fn test_fractal_noise_range() {
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                let noise = fractal_noise(1, x as f64, y as f64, 3.0, 4);
+                assert!((0.0..=1.0).contains(&noise));
+            }
+        }
+    }
+}
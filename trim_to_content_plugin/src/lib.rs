@@ -0,0 +1,274 @@
+#![warn(missing_docs)]
+//! Модуль для реализации обработки изображения - обрезка по содержимому
+//!
+//! Предоставляет функциональность поиска ограничивающего прямоугольника непрозрачного
+//! содержимого и переноса его в левый верхний угол холста того же размера
+
+use log::LevelFilter;
+use plugins_support::config_parse::warn_ignored_fields;
+use plugins_support::image_size::rgba_len;
+use plugins_support::logger::{
+    LogTimezone, get_log_level, set_call_level, set_log_timezone, setup_logger_once,
+};
+use plugins_support::region::Region;
+use plugins_support::{config_parse::ConfigReader, error::Error};
+use serde::Deserialize;
+use std::ffi::{CStr, c_char, c_uint};
+use std::slice;
+
+const PKG_NAME: &str = env!("CARGO_PKG_NAME");
+const BYTE_PER_PIXEL: usize = 4;
+const ALPHA: usize = 3;
+
+plugins_support::export_warning_count!();
+plugins_support::export_abi_probe!();
+
+/// Поля конфигурации, которые использует плагин
+const KNOWN_FIELDS: &[&str] = &["alpha_threshold"];
+
+#[derive(Deserialize, Debug)]
+struct ConfigTransform {
+    alpha_threshold: Option<u8>,
+    log_level: Option<String>,
+    log_tz: Option<LogTimezone>,
+}
+
+/// Ограничивающий прямоугольник пикселей с альфа-каналом больше `alpha_threshold`
+///
+/// # Аргументы
+/// * `buf`    – буфер RGBA (длина = width * height * 4)
+/// * `width`  – ширина в пикселях
+/// * `height` – высота в пикселях
+/// * `alpha_threshold` – пиксели с альфа-каналом не больше порога считаются пустыми
+///
+/// Возвращает `None`, если изображение не содержит непрозрачных пикселей
+///
+pub fn content_bounds(buf: &[u8], width: usize, height: usize, alpha_threshold: u8) -> Option<Region> {
+    let (mut left, mut top, mut right, mut bottom) = (width, height, 0, 0);
+    for (index, pixel) in buf.chunks_exact(BYTE_PER_PIXEL).enumerate().take(width * height) {
+        if pixel[ALPHA] > alpha_threshold {
+            let (x, y) = (index % width, index / width);
+            left = left.min(x);
+            top = top.min(y);
+            right = right.max(x + 1);
+            bottom = bottom.max(y + 1);
+        }
+    }
+    (left < right).then(|| Region {
+        x: left,
+        y: top,
+        width: right - left,
+        height: bottom - top,
+    })
+}
+
+/// Перенос содержимого в левый верхний угол
+///
+/// # Аргументы
+/// * `buf`    – изменяемый буфер RGBA (длина = width * height * 4)
+/// * `width`  – ширина в пикселях
+/// * `height` – высота в пикселях
+/// * `alpha_threshold` – пиксели с альфа-каналом не больше порога считаются пустыми
+///
+/// Прямоугольник [`content_bounds`] копируется в начало координат, освободившаяся
+/// область заполняется прозрачными пикселями `[0, 0, 0, 0]`. Возвращает найденный
+/// прямоугольник, пустое изображение не изменяется
+///
+pub fn trim_to_content(buf: &mut [u8], width: usize, height: usize, alpha_threshold: u8) -> Option<Region> {
+    let bounds = content_bounds(buf, width, height, alpha_threshold)?;
+    let row_len = bounds.width * BYTE_PER_PIXEL;
+    // Строка назначения всегда не правее и не ниже исходной, копирование сверху вниз
+    // не затирает еще не перенесенные строки
+    for row in 0..bounds.height {
+        let source = ((bounds.y + row) * width + bounds.x) * BYTE_PER_PIXEL;
+        let target = row * width * BYTE_PER_PIXEL;
+        buf.copy_within(source..source + row_len, target);
+    }
+    for (index, pixel) in buf.chunks_exact_mut(BYTE_PER_PIXEL).enumerate().take(width * height) {
+        if index % width >= bounds.width || index / width >= bounds.height {
+            pixel.fill(0);
+        }
+    }
+    Some(bounds)
+}
+
+/// ```rust
+///
+///  Трансформация RGBA буффера переносом непрозрачного содержимого в левый верхний угол
+///
+///
+///  # Параметры
+///   - `width` (`c_uint`):  ширина изображения в пикселях
+///   - `height` (`c_uint`): высота изображения в пикселях
+///   - `rgba_data` (`*mut u8`): Мутабельный сырой указатель на RGBA буфер. Размер буфера `width * height * 4` bytes.
+///   - `params` (`*const c_char`): Сырой указатель на нуль терменированноу строку  конфигурации JSON-формата.
+///
+///  # Параметры конфигурации (JSON формат)
+///   - `log_level` (optional, string): уровень логирования ("Debug", "Info").
+///   - `log_tz` (optional, string): часовой пояс меток времени лога ("local", "utc"), по умолчанию "local"
+///   - `alpha_threshold` (optional, integer): пиксели с альфа-каналом не больше порога считаются
+///     пустыми (0..=255), по умолчанию 0
+///
+///  # Пример JSON:
+///  ```json
+///  {
+///     "log_level": "Debug",
+///     "alpha_threshold": 16
+///  }
+///  ```
+///
+///
+///   # Error Handling
+///   - Ошибки фиксируются в логе
+///   - Поля конфигурации, которые плагин не использует, фиксируются в логе предупреждением
+///
+///
+///   # Пример С
+///  ```c
+///  // In C code
+///   unsigned int width = 1920;
+///   unsigned int height = 1080;
+///   unsigned char *image_data = ...; // RGBA buffer, allocated elsewhere
+///   const char *config = "{\"alpha_threshold\": 16}";
+///   process_image(width, height, image_data, config);
+///   ```
+/// # Safety
+///  Данная функция  помечена `unsafe`:
+///   - Работа напрямую с сырыми указателями (`rgba_data`, `params`) предстаялет external C code
+///   - Использования недопустимого указателя или парметров width, height undefined behavior.
+/// ```
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn process_image(
+    width: c_uint,
+    height: c_uint,
+    rgba_data: *mut u8,
+    params: *const c_char,
+) {
+    let file = PKG_NAME.to_owned() + ".log";
+    if let Err(e) = setup_logger_once(LevelFilter::Debug, &file) {
+        match e {
+            Error::LoggerInitError(_) => {
+                log::warn!("Logger init error: {}", e);
+            }
+            _ => {
+                log::error!("Logger init error: {}", e);
+                return;
+            }
+        }
+    }
+    log::info!("Start plugin {}", &file);
+    if params.is_null() {
+        log::error!("Pointer params is_null");
+        return;
+    }
+    let config = unsafe { CStr::from_ptr(params) };
+    let params_config = match config.to_str() {
+        Ok(config) => {
+            warn_ignored_fields(config, KNOWN_FIELDS);
+            let config: Result<ConfigReader<ConfigTransform>, Error> =
+                ConfigReader::try_from(config);
+            if let Ok(config) = config {
+                config
+            } else {
+                log::error!("Error converting config to string");
+                return;
+            }
+        }
+        _ => {
+            log::error!("Invalid config file");
+            return;
+        }
+    };
+    // Уровень из конфигурации действует только в потоке вызова до его завершения
+    let _log_level = params_config
+        .config
+        .log_level
+        .as_deref()
+        .map(|log_level| set_call_level(get_log_level(log_level)));
+    if let Some(log_tz) = params_config.config.log_tz {
+        set_log_timezone(log_tz);
+    }
+    if rgba_data.is_null() {
+        log::error!("Null pointer rgba_data");
+        return;
+    }
+    if width == 0 {
+        log::error!("width cannot be 0");
+        return;
+    }
+    if height == 0 {
+        log::error!("height cannot be 0");
+        return;
+    }
+    let len_in_pixel = match rgba_len(width, height) {
+        Ok(len) => len,
+        Err(e) => {
+            log::error!("Length calculation failed: {}", e);
+            return;
+        }
+    };
+    let height: usize = match height.try_into() {
+        Ok(h) => h,
+        Err(_) => {
+            log::error!("Height conversion failed");
+            return;
+        }
+    };
+    let width: usize = match width.try_into() {
+        Ok(w) => w,
+        Err(_) => {
+            log::error!("Width conversion failed");
+            return;
+        }
+    };
+    log::info!("Start converting image");
+    let buf = unsafe { slice::from_raw_parts_mut(rgba_data, len_in_pixel) };
+    let alpha_threshold = params_config.config.alpha_threshold.unwrap_or(0);
+    match trim_to_content(buf, width, height, alpha_threshold) {
+        Some(bounds) => log::info!("Content bounds {:?} moved to origin", bounds),
+        None => log::info!("No pixels with alpha above {}, image unchanged", alpha_threshold),
+    }
+    log::info!("Finish converting image");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    #[test]
+    fn test_trim_to_content_center() {
+        // 4x4, непрозрачный блок 2x2 в центре, остальное - полупрозрачный фон
+        let mut buf = vec![0u8; 4 * 4 * BYTE_PER_PIXEL];
+        for (index, pixel) in buf.chunks_exact_mut(BYTE_PER_PIXEL).enumerate() {
+            let (x, y) = (index % 4, index / 4);
+            if (1..3).contains(&x) && (1..3).contains(&y) {
+                pixel.copy_from_slice(&[x as u8 * 10, y as u8 * 10, 0, 255]);
+            } else {
+                pixel.copy_from_slice(&[200, 200, 200, 8]);
+            }
+        }
+        let json = r#"{"alpha_threshold": 8}"#;
+        let params_cstring = CString::new(json).unwrap();
+        unsafe { process_image(4, 4, buf.as_mut_ptr(), params_cstring.as_ptr()) };
+        for (index, pixel) in buf.chunks_exact(BYTE_PER_PIXEL).enumerate() {
+            let (x, y) = (index % 4, index / 4);
+            if x < 2 && y < 2 {
+                assert_eq!(pixel, [(x as u8 + 1) * 10, (y as u8 + 1) * 10, 0, 255]);
+            } else {
+                assert_eq!(pixel, [0, 0, 0, 0], "pixel ({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_content_bounds() {
+        let mut buf = vec![0u8; 5 * 3 * BYTE_PER_PIXEL];
+        assert_eq!(content_bounds(&buf, 5, 3, 0), None);
+        buf[(5 + 3) * BYTE_PER_PIXEL + ALPHA] = 1;
+        buf[(2 * 5 + 4) * BYTE_PER_PIXEL + ALPHA] = 1;
+        let bounds = Region { x: 3, y: 1, width: 2, height: 2 };
+        assert_eq!(content_bounds(&buf, 5, 3, 0), Some(bounds));
+        assert_eq!(content_bounds(&buf, 5, 3, 1), None);
+    }
+}